@@ -0,0 +1,84 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc, time::Duration};
+
+use clap::Args;
+
+use crate::audio::AudioOutput;
+use voicepeak_srt_core::{
+    audio::{self, ConcatOptions},
+    format_srt_time, parse, FilePattern, SubtitleBuilder,
+};
+
+/// 編集済みの字幕に合わせて音声を並べ直したwavを書き出す
+#[derive(Args, Debug)]
+pub struct AlignArgs {
+    /// 時刻を直した字幕ファイル(srtまたはvtt)
+    subtitles: PathBuf,
+    #[arg(short, long)]
+    input_path: String,
+    /// 書き出す音声(拡張子がmp3・opus・m4a・aacならffmpegでエンコードする)
+    #[arg(short, long, default_value = "./aligned.wav")]
+    output_path: PathBuf,
+    /// ファイル名の正規表現(名前付きキャプチャseq・speaker・textを使う)
+    #[arg(long)]
+    pattern: Option<String>,
+    /// エンコードするときのビットレート
+    #[arg(long, default_value = "192k")]
+    audio_bitrate: String,
+    /// ffmpegのパス
+    #[arg(long, default_value = "ffmpeg")]
+    ffmpeg: String,
+}
+
+pub fn run(args: &AlignArgs) -> crate::CliResult {
+    let cues = parse::read_subtitle_file(&args.subtitles)?;
+
+    // 字幕を作るときと同じ順で、字幕になる音声だけを取り出す
+    let pattern = match &args.pattern {
+        Some(pattern) => FilePattern::new(pattern)?,
+        None => FilePattern::default(),
+    };
+    let clips = Rc::new(RefCell::new(Vec::new()));
+    let blocks = SubtitleBuilder::from_dir(&args.input_path)
+        .pattern(pattern)
+        .on_clip({
+            let clips = Rc::clone(&clips);
+            move |clip| clips.borrow_mut().push(clip)
+        })
+        .build()?;
+    let clips: Vec<_> = blocks
+        .iter()
+        .filter_map(|block| {
+            clips
+                .borrow()
+                .iter()
+                .find(|clip| clip.start == block.start)
+                .cloned()
+        })
+        .collect();
+
+    let aligned = audio::align_to_cues(&clips, &cues)?;
+    for (clip, next) in aligned.iter().zip(cues.iter().skip(1)) {
+        // 字幕の時刻はミリ秒単位のため、1ミリ秒未満の重なりは無視する
+        if clip.start + clip.duration > next.start + Duration::from_millis(1) {
+            eprintln!(
+                "警告: {}の音声が次のブロック({})に重なります",
+                clip.path.display(),
+                format_srt_time(next.start)
+            );
+        }
+    }
+
+    crate::audio::write_concat_audio(
+        &args.input_path,
+        &aligned,
+        Duration::ZERO,
+        &ConcatOptions::new(),
+        &AudioOutput {
+            path: &args.output_path,
+            ffmpeg: &args.ffmpeg,
+            bitrate: &args.audio_bitrate,
+            peaks: None,
+            zoom: 256,
+        },
+    )
+}
@@ -0,0 +1,201 @@
+//! つなげた音声の書き出し
+//!
+//! wav以外の形式は、一度wavに書き出してからffmpegでエンコードする。
+
+use std::{collections::BTreeMap, fs, io::Write, path::Path, process::Command, time::Duration};
+
+use voicepeak_srt_core::{
+    audio::{self, Clip, ConcatOptions, Peaks},
+    DirSource, OutputFile,
+};
+
+use crate::ffmpeg;
+
+/// 出力する音声の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    Wav,
+    Mp3,
+    Opus,
+    /// MP4(m4a)に入れたAAC
+    M4a,
+    /// ADTSのAAC
+    Aac,
+}
+
+impl Codec {
+    /// 拡張子から形式を決める
+    fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match ext.as_deref() {
+            Some("wav") => Ok(Self::Wav),
+            Some("mp3") => Ok(Self::Mp3),
+            Some("opus" | "ogg") => Ok(Self::Opus),
+            Some("m4a" | "mp4") => Ok(Self::M4a),
+            Some("aac") => Ok(Self::Aac),
+            _ => Err(format!(
+                "音声の形式を拡張子から決められません(wav・mp3・opus・m4a・aacに対応): {}",
+                path.display()
+            )),
+        }
+    }
+
+    /// ffmpegに渡すエンコーダーとコンテナ
+    fn ffmpeg_args(self) -> [&'static str; 4] {
+        match self {
+            Self::Wav => ["-c:a", "pcm_s16le", "-f", "wav"],
+            Self::Mp3 => ["-c:a", "libmp3lame", "-f", "mp3"],
+            Self::Opus => ["-c:a", "libopus", "-f", "opus"],
+            Self::M4a => ["-c:a", "aac", "-f", "ipod"],
+            Self::Aac => ["-c:a", "aac", "-f", "adts"],
+        }
+    }
+}
+
+/// つなげた音声の書き出し先
+pub struct AudioOutput<'a> {
+    /// 音声のパス(拡張子で形式を決める)
+    pub path: &'a Path,
+    /// ffmpegのパス
+    pub ffmpeg: &'a str,
+    /// エンコードするときのビットレート
+    pub bitrate: &'a str,
+    /// 波形のピークの書き出し先(拡張子が`dat`ならバイナリ、それ以外はJSON)
+    pub peaks: Option<&'a Path>,
+    /// 1ピークあたりのフレーム数
+    pub zoom: u32,
+}
+
+/// 音声を字幕と同じ時刻に並べて書き出す
+pub fn write_concat_audio(
+    input_path: &str,
+    clips: &[Clip],
+    offset: Duration,
+    options: &ConcatOptions,
+    output: &AudioOutput,
+) -> crate::CliResult {
+    let path = output.path;
+    let codec = Codec::from_path(path)?;
+    let clips: Vec<Clip> = clips
+        .iter()
+        .map(|clip| Clip::new(&clip.path, clip.start + offset, clip.duration))
+        .collect();
+    let source = DirSource::new(input_path);
+
+    if codec == Codec::Wav {
+        let mut file = OutputFile::create(path)?;
+        audio::concat_wav_with(&source, &clips, options, &mut file)?;
+        file.flush()?;
+        write_peaks(file.part_path(), output)?;
+        file.commit()?;
+        return Ok(());
+    }
+
+    // エンコード前のwavは確定せずに削除する
+    let mut wav = OutputFile::create(path.with_extension("wav"))?;
+    audio::concat_wav_with(&source, &clips, options, &mut wav)?;
+    wav.flush()?;
+    write_peaks(wav.part_path(), output)?;
+    let (ffmpeg, bitrate) = (output.ffmpeg, output.bitrate);
+    let file = OutputFile::create(path)?;
+    let mut command = Command::new(ffmpeg);
+    command
+        .args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(wav.part_path())
+        .args(codec.ffmpeg_args())
+        .args(["-b:a", bitrate])
+        .arg(file.part_path());
+    ffmpeg::run(ffmpeg, &mut command, "エンコード")?;
+    file.commit()?;
+
+    Ok(())
+}
+
+/// つなげたwavから波形のピークを書き出す
+fn write_peaks(wav: &Path, output: &AudioOutput) -> crate::CliResult {
+    let Some(path) = output.peaks else {
+        return Ok(());
+    };
+
+    let peaks = Peaks::from_wav(&DirSource::new("."), wav, output.zoom)?;
+    let mut file = OutputFile::create(path)?;
+    match path.extension().is_some_and(|ext| ext == "dat") {
+        true => peaks.write_dat(&mut file)?,
+        false => peaks.write_json(&mut file)?,
+    }
+    file.commit()?;
+
+    Ok(())
+}
+
+/// 字幕のある音声を開始時刻とテキストの名前で`dir`にコピーする
+///
+/// `cues`は音声の順(`clips`の位置)とテキストの組。1つの音声を分けたブロックは
+/// テキストをつなげて1つのファイルにする
+pub fn export_cues(
+    dir: &Path,
+    clips: &[Clip],
+    cues: &[(usize, String)],
+    offset: Duration,
+) -> crate::CliResult {
+    fs::create_dir_all(dir)?;
+    let mut texts: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for (clip, text) in cues {
+        texts.entry(*clip).or_default().push(text);
+    }
+    for (clip, texts) in texts {
+        let Some(clip) = clips.get(clip) else {
+            continue;
+        };
+        let path = dir.join(audio::cue_file_name(clip.start + offset, &texts.join(" ")));
+        fs::copy(&clip.path, &path)
+            .map_err(|e| format!("{}を書き出せません: {}", path.display(), e))?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_codec_from_path() {
+    assert_eq!(Ok(Codec::Wav), Codec::from_path(Path::new("out.WAV")));
+    assert_eq!(Ok(Codec::Mp3), Codec::from_path(Path::new("a/out.mp3")));
+    assert_eq!(Ok(Codec::M4a), Codec::from_path(Path::new("out.m4a")));
+    assert!(Codec::from_path(Path::new("out")).is_err());
+}
+
+#[test]
+fn test_export_cues() {
+    let dir = std::env::temp_dir().join(format!("voicepeak-srt-export-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    // 開始時刻が同じ音声でも取り違えない
+    let clips: Vec<Clip> = ["000.wav", "001.wav"]
+        .iter()
+        .map(|name| {
+            fs::write(dir.join(name), name).unwrap();
+            Clip::new(dir.join(name), Duration::ZERO, Duration::from_secs(1))
+        })
+        .collect();
+    let cues = [
+        (0, "一行目".to_string()),
+        (0, "二行目".to_string()),
+        (1, "次".to_string()),
+    ];
+    let out = dir.join("cues");
+    export_cues(&out, &clips, &cues, Duration::from_secs(10)).unwrap();
+    let mut names: Vec<String> = fs::read_dir(&out)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    let copied = fs::read_to_string(out.join("00-00-10_000__次.wav")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    // 分けたブロックは1つの音声にまとめる
+    names.sort();
+    assert_eq!(
+        vec!["00-00-10_000__一行目 二行目.wav", "00-00-10_000__次.wav"],
+        names
+    );
+    assert_eq!("001.wav", copied);
+}
@@ -0,0 +1,97 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use clap::Args;
+
+use crate::ffmpeg;
+
+/// ffmpegで動画に字幕を焼き込む
+#[derive(Args, Debug)]
+pub struct BurnArgs {
+    /// 字幕を焼き込む動画
+    video: PathBuf,
+    /// 焼き込む字幕(srt・vtt・ass、assならスタイルもそのまま使う)
+    #[arg(short, long, default_value = "./subtitles.srt")]
+    subtitles: PathBuf,
+    #[arg(short, long, default_value = "./burned.mp4")]
+    output_path: PathBuf,
+    /// srt・vttに適用するASSのスタイル(例: "FontName=Noto Sans JP,FontSize=24")
+    #[arg(long)]
+    style: Option<String>,
+    /// 先頭の指定秒数だけを低画質で素早く書き出して確認する
+    #[arg(long)]
+    preview: Option<u32>,
+    /// ffmpegのパス
+    #[arg(long, default_value = "ffmpeg")]
+    ffmpeg: String,
+}
+
+pub fn run(args: &BurnArgs) -> crate::CliResult {
+    if !args.subtitles.exists() {
+        return Err(format!("字幕ファイルがありません: {}", args.subtitles.display()).into());
+    }
+
+    let mut command = Command::new(&args.ffmpeg);
+    command
+        .args(["-hide_banner", "-loglevel", "error", "-stats", "-y", "-i"])
+        .arg(&args.video)
+        .arg("-vf")
+        .arg(subtitle_filter(&args.subtitles, args.style.as_deref()))
+        .args(["-c:a", "copy"]);
+    if let Some(secs) = args.preview {
+        command
+            .arg("-t")
+            .arg(secs.to_string())
+            .args(["-preset", "ultrafast", "-crf", "30"]);
+    }
+    command.arg(&args.output_path);
+    ffmpeg::run(&args.ffmpeg, &mut command, "焼き込み")
+}
+
+/// 字幕を焼き込むフィルター(assはスタイルをそのまま使い、それ以外はスタイルを上書きできる)
+pub(crate) fn subtitle_filter(path: &Path, style: Option<&str>) -> String {
+    let is_ass = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ass"));
+    // Windowsの区切り文字はフィルターの中ではエスケープが必要になるため使わない
+    let path = escape_filter(&path.to_string_lossy().replace('\\', "/"));
+    match (is_ass, style) {
+        (true, _) => format!("ass={}", path),
+        (false, Some(style)) => format!("subtitles={}:force_style={}", path, escape_filter(style)),
+        (false, None) => format!("subtitles={}", path),
+    }
+}
+
+/// フィルターのオプション値とフィルターグラフの2段階でエスケープする
+fn escape_filter(value: &str) -> String {
+    let escape = |text: &str, special: &[char]| {
+        text.chars().fold(String::new(), |mut out, c| {
+            if special.contains(&c) {
+                out.push('\\');
+            }
+            out.push(c);
+            out
+        })
+    };
+    escape(
+        &escape(value, &['\\', '\'', ':']),
+        &['\\', '\'', '[', ']', ',', ';'],
+    )
+}
+
+#[test]
+fn test_subtitle_filter() {
+    assert_eq!(
+        "subtitles=C\\\\:/voice/sub.srt:force_style=FontSize=24\\,Outline=2",
+        subtitle_filter(
+            Path::new("C:\\voice\\sub.srt"),
+            Some("FontSize=24,Outline=2")
+        )
+    );
+    assert_eq!(
+        "ass=sub.ass",
+        subtitle_filter(Path::new("sub.ass"), Some("x"))
+    );
+}
@@ -0,0 +1,99 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::Args;
+use voicepeak_srt_core::{format_srt_time, parse, write_subtitle_file, SrtBlock};
+
+/// 手で編集した字幕を整える(開始時刻順に並べて番号を振り直し、わずかな重なりと改行を直す)
+#[derive(Args, Debug)]
+pub struct CleanArgs {
+    /// 整える字幕ファイル(srtまたはvtt)
+    subtitles: PathBuf,
+    /// これ以下の重なり(ミリ秒)は前のブロックの終わりを縮めて直す
+    #[arg(long, default_value_t = 100)]
+    max_overlap_ms: u64,
+    /// 書き出す字幕(省略時は元のファイルを上書きする)
+    #[arg(short, long)]
+    output_path: Option<PathBuf>,
+}
+
+pub fn run(args: &CleanArgs) -> crate::CliResult {
+    let blocks = parse::read_subtitle_file(&args.subtitles)?;
+    let (blocks, fixed) = clean(blocks, Duration::from_millis(args.max_overlap_ms));
+
+    // 大きな重なりは意図したものかもしれないため、直さずに知らせる
+    for pair in blocks.windows(2) {
+        if pair[1].start < pair[0].end {
+            eprintln!(
+                "警告: {}のブロックが前のブロックに{}ms重なります",
+                format_srt_time(pair[1].start),
+                (pair[0].end - pair[1].start).as_millis()
+            );
+        }
+    }
+    eprintln!(
+        "{}ブロックを整え、{}か所の重なりを直しました",
+        blocks.len(),
+        fixed
+    );
+
+    let output = args.output_path.as_ref().unwrap_or(&args.subtitles);
+    write_subtitle_file(blocks, output)?;
+    Ok(())
+}
+
+/// ブロックを整え、直した重なりの数を返す
+fn clean(mut blocks: Vec<SrtBlock>, max_overlap: Duration) -> (Vec<SrtBlock>, usize) {
+    // 開始時刻が同じブロックは元の順を保つ
+    blocks.sort_by_key(|block| block.start);
+
+    let mut fixed = 0;
+    for i in 0..blocks.len() {
+        let block = &mut blocks[i];
+        block.index = i + 1;
+        block.text = block
+            .text
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_matches('\n')
+            .to_string();
+
+        if let Some(next_start) = blocks.get(i + 1).map(|next| next.start) {
+            let block = &mut blocks[i];
+            if next_start < block.end && block.end - next_start <= max_overlap {
+                block.end = next_start;
+                fixed += 1;
+            }
+        }
+    }
+    (blocks, fixed)
+}
+
+#[test]
+fn test_clean() {
+    let mut blocks = vec![
+        SrtBlock::new(
+            5,
+            Duration::from_secs(3),
+            Duration::from_secs(4),
+            "三つ目\r",
+        ),
+        SrtBlock::new(1, Duration::ZERO, Duration::from_millis(2050), "一つ目  \n"),
+        SrtBlock::new(2, Duration::from_secs(2), Duration::from_secs(5), "二つ目"),
+    ];
+    blocks[0].text.push_str("\n続き");
+    let (blocks, fixed) = clean(blocks, Duration::from_millis(100));
+
+    assert_eq!(1, fixed);
+    assert_eq!(
+        vec![(1, "一つ目"), (2, "二つ目"), (3, "三つ目\n続き")],
+        blocks
+            .iter()
+            .map(|b| (b.index, b.text.as_str()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(Duration::from_secs(2), blocks[0].end);
+    // 1秒の重なりは直さない
+    assert_eq!(Duration::from_secs(5), blocks[1].end);
+}
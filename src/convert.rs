@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use voicepeak_srt_core::{
+    parse::{self, SubtitleFormat},
+    write_subtitles, EncodedWriter, LineEnding, NewlineWriter, OutputEncoding, OutputFile,
+};
+
+use crate::generate::{output_encoding, parse_line_endings, parse_output_encoding};
+
+/// 字幕ファイルの形式を変換する(音声がなくても使える)
+#[derive(Args, Debug)]
+pub struct ConvertArgs {
+    /// 変換する字幕ファイル
+    input: PathBuf,
+    /// 書き出す字幕ファイル
+    #[arg(short, long)]
+    output_path: PathBuf,
+    /// 入力形式(省略時は拡張子と内容から判定)
+    #[arg(long, value_enum)]
+    from: Option<ConvertFormat>,
+    /// 出力形式(省略時は拡張子から判定)
+    #[arg(long, value_enum)]
+    to: Option<ConvertFormat>,
+    /// 書き出す文字コード(utf-8・utf-8-bom・shift-jis・utf-16le)
+    #[arg(long, default_value = "utf-8", value_parser = parse_output_encoding)]
+    output_encoding: OutputEncoding,
+    /// 先頭にBOMを付ける(文字コードを自動で判定できないWindowsのエディターや再生機器向け)
+    #[arg(long)]
+    bom: bool,
+    /// 改行コード(lf・crlf)
+    #[arg(long, default_value = "lf", value_parser = parse_line_endings)]
+    line_endings: LineEnding,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ConvertFormat {
+    Srt,
+    Vtt,
+    Ass,
+    Sbv,
+    Json,
+}
+
+impl From<ConvertFormat> for SubtitleFormat {
+    fn from(format: ConvertFormat) -> Self {
+        match format {
+            ConvertFormat::Srt => SubtitleFormat::Srt,
+            ConvertFormat::Vtt => SubtitleFormat::Vtt,
+            ConvertFormat::Ass => SubtitleFormat::Ass,
+            ConvertFormat::Sbv => SubtitleFormat::Sbv,
+            ConvertFormat::Json => SubtitleFormat::Json,
+        }
+    }
+}
+
+pub fn run(args: &ConvertArgs) -> crate::CliResult {
+    let blocks = match args.from {
+        Some(format) => {
+            let text = std::fs::read_to_string(&args.input)?;
+            parse::parse_subtitles(&text, format.into())?
+        }
+        None => parse::read_subtitle_file(&args.input)?,
+    };
+
+    let format = match args.to {
+        Some(format) => format.into(),
+        None => SubtitleFormat::from_path(&args.output_path).ok_or_else(|| {
+            format!(
+                "出力形式を判定できません。--toで指定してください: {}",
+                args.output_path.display()
+            )
+        })?,
+    };
+    let count = blocks.len();
+    let mut file = OutputFile::create(&args.output_path)?;
+    write_subtitles(
+        blocks,
+        format,
+        NewlineWriter::new(
+            EncodedWriter::new(&mut file, output_encoding(args.output_encoding, args.bom)?),
+            args.line_endings,
+        ),
+    )?;
+    file.commit()?;
+    eprintln!("{}ブロックを変換しました", count);
+    Ok(())
+}
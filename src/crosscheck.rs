@@ -0,0 +1,143 @@
+use std::{
+    cell::RefCell,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    rc::Rc,
+    time::Duration,
+};
+
+use clap::Args;
+use voicepeak_srt_core::{
+    audio::{self, Clip, ConcatOptions},
+    format_srt_time,
+    validate::text_similarity,
+    DirSource, SubtitleBuilder,
+};
+
+use crate::presets;
+
+/// whisper.cppに読ませる音声のサンプリング周波数
+const WHISPER_SAMPLING_RATE: u32 = 16000;
+
+/// whisperで音声を書き起こし、txtと食い違う(合成後にtxtを編集した)音声を探す
+#[derive(Args, Debug)]
+pub struct CrosscheckArgs {
+    #[arg(short, long)]
+    input_path: String,
+    /// 入力のプリセット
+    #[arg(long, default_value = "voicepeak")]
+    preset: String,
+    /// 独自のプリセットを書いたJSON
+    #[arg(long)]
+    preset_file: Option<PathBuf>,
+    /// whisper.cppのモデル(ggml-*.bin)
+    #[arg(long)]
+    model: PathBuf,
+    /// 書き起こす言語
+    #[arg(long, default_value = "ja")]
+    language: String,
+    /// これより一致度(0〜1)が低い音声を報告する
+    #[arg(long, default_value_t = 0.6)]
+    threshold: f64,
+    /// whisper.cppのパス
+    #[arg(long, default_value = "whisper-cli")]
+    whisper: String,
+}
+
+pub fn run(args: &CrosscheckArgs) -> crate::CliResult {
+    // 字幕を作るときと同じ順で、字幕になる音声とテキストを取り出す
+    let preset = presets::find(&args.preset, args.preset_file.as_deref())?;
+    let clips = Rc::new(RefCell::new(Vec::new()));
+    let blocks = SubtitleBuilder::from_dir(&args.input_path)
+        .preset(&preset)
+        .on_clip({
+            let clips = Rc::clone(&clips);
+            move |clip| clips.borrow_mut().push(clip)
+        })
+        .build()?;
+
+    let source = DirSource::new(&args.input_path);
+    let temp = std::env::temp_dir().join(format!("voicepeak-srt-{}.wav", std::process::id()));
+    let mut mismatches = 0;
+    for block in &blocks {
+        let Some(clip) = clips
+            .borrow()
+            .iter()
+            .find(|c| c.start == block.start)
+            .cloned()
+        else {
+            continue;
+        };
+        let result = transcribe(args, &source, &clip, &temp);
+        let _ = fs::remove_file(&temp);
+        let recognized = result?;
+
+        let similarity = text_similarity(&block.text, &recognized);
+        if similarity < args.threshold {
+            mismatches += 1;
+            println!(
+                "{} {} 一致度{:.0}% ({})",
+                block.index,
+                format_srt_time(block.start),
+                similarity * 100.,
+                clip.path.display()
+            );
+            println!("  txt : {}", block.text.replace('\n', " "));
+            println!("  音声: {}", recognized);
+        }
+    }
+
+    if mismatches > 0 {
+        return Err(format!(
+            "txtと音声が食い違っている可能性のある音声が{}個あります",
+            mismatches
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 音声を16kHzにしてwhisper.cppで書き起こす
+fn transcribe(
+    args: &CrosscheckArgs,
+    source: &DirSource,
+    clip: &Clip,
+    temp: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let single = [Clip::new(clip.path.clone(), Duration::ZERO, clip.duration)];
+    let options = ConcatOptions::new().sampling_rate(Some(WHISPER_SAMPLING_RATE));
+    audio::concat_wav_with(
+        source,
+        &single,
+        &options,
+        io::BufWriter::new(File::create(temp)?),
+    )?;
+
+    let output = Command::new(&args.whisper)
+        .arg("-m")
+        .arg(&args.model)
+        .args(["-l", &args.language, "-nt", "-np", "-f"])
+        .arg(temp)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => format!(
+                "whisper.cppが見つかりません({})。インストールするか--whisperでパスを指定してください",
+                args.whisper
+            ),
+            _ => format!("whisper.cppを起動できません: {}", e),
+        })?;
+    if !output.status.success() {
+        return Err(format!(
+            "whisper.cppでの書き起こしに失敗しました({}): {}",
+            clip.path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(text.lines().map(str::trim).collect::<Vec<_>>().join(""))
+}
@@ -0,0 +1,103 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::Args;
+use voicepeak_srt_core::{
+    diff::{compare_cues, CueChange},
+    format_srt_time, parse,
+};
+
+/// 2つの字幕ファイルをブロックごとに比べ、テキストと時刻の違いを表示する
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// 比べる元の字幕ファイル
+    old: PathBuf,
+    /// 比べる新しい字幕ファイル
+    new: PathBuf,
+    /// これ以下の時刻の差(ミリ秒)は無視する
+    #[arg(long, default_value_t = 20)]
+    tolerance_ms: u64,
+    /// 違いをJSONで表示する
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: &DiffArgs) -> crate::CliResult {
+    let old = parse::read_subtitle_file(&args.old)?;
+    let new = parse::read_subtitle_file(&args.new)?;
+    let changes = compare_cues(&old, &new, Duration::from_millis(args.tolerance_ms));
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+        return Ok(());
+    }
+    for change in &changes {
+        println!("{}", describe(change));
+    }
+    if changes.is_empty() {
+        eprintln!("違いはありません");
+    } else {
+        eprintln!("{}ブロックに違いがあります", changes.len());
+    }
+    Ok(())
+}
+
+/// 違いを読みやすい形にする
+fn describe(change: &CueChange) -> String {
+    let one_line = |text: &str| text.trim_end().replace('\n', " ");
+    match (&change.old, &change.new) {
+        (Some(old), None) => format!(
+            "{} 削除 {} {}",
+            change.position,
+            format_srt_time(old.start),
+            one_line(&old.text)
+        ),
+        (None, Some(new)) => format!(
+            "{} 追加 {} {}",
+            change.position,
+            format_srt_time(new.start),
+            one_line(&new.text)
+        ),
+        (Some(old), Some(new)) => {
+            let mut out = format!("{} {}", change.position, format_srt_time(new.start));
+            if change.start_delta_ms != 0 || change.end_delta_ms != 0 {
+                out.push_str(&format!(
+                    " 開始{:+}ms 終了{:+}ms",
+                    change.start_delta_ms, change.end_delta_ms
+                ));
+            }
+            if change.text_changed {
+                out.push_str(&format!(
+                    "\n  - {}\n  + {}",
+                    one_line(&old.text),
+                    one_line(&new.text)
+                ));
+            }
+            out
+        }
+        (None, None) => String::new(),
+    }
+}
+
+#[test]
+fn test_describe() {
+    use voicepeak_srt_core::SrtBlock;
+
+    let old = [SrtBlock::new(
+        1,
+        Duration::from_secs(1),
+        Duration::from_secs(2),
+        "前",
+    )];
+    let new = [SrtBlock::new(
+        1,
+        Duration::from_millis(1500),
+        Duration::from_secs(2),
+        "後",
+    )];
+    let changes = compare_cues(&old, &new, Duration::ZERO);
+
+    assert_eq!(
+        "1 00:00:01,500 開始+500ms 終了+0ms\n  - 前\n  + 後",
+        describe(&changes[0])
+    );
+}
@@ -0,0 +1,25 @@
+//! ffmpegの呼び出し
+
+use std::{
+    io,
+    process::{Command, Stdio},
+};
+
+/// ffmpegを実行し、失敗したら`action`に失敗したエラーにする
+pub fn run(ffmpeg: &str, command: &mut Command, action: &str) -> crate::CliResult {
+    let status = command
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => format!(
+                "ffmpegが見つかりません({})。インストールするか--ffmpegでパスを指定してください",
+                ffmpeg
+            ),
+            _ => format!("ffmpegを起動できません: {}", e),
+        })?;
+    if !status.success() {
+        return Err(format!("ffmpegでの{}に失敗しました({})", action, status).into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! ffprobeによる音声の長さの取得
+
+use std::{
+    io,
+    path::Path,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use voicepeak_srt_core::{DurationProvider, InputSource, WavDurationProvider};
+
+/// wavとして読めない音声(24bitやADPCMなど)の長さをffprobeで取得する
+pub struct FfprobeFallback {
+    ffprobe: String,
+}
+
+impl FfprobeFallback {
+    /// `ffprobe`を呼び出して長さを取得する
+    pub fn new(ffprobe: &str) -> Self {
+        Self {
+            ffprobe: ffprobe.to_string(),
+        }
+    }
+
+    /// ffprobeで長さを取得する
+    fn probe(&self, path: &Path) -> io::Result<Duration> {
+        let output = Command::new(&self.ffprobe)
+            .args(["-v", "error", "-show_entries", "format=duration"])
+            .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+            .arg(path)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => io::Error::new(
+                    e.kind(),
+                    format!("ffprobeが見つかりません({})", self.ffprobe),
+                ),
+                _ => e,
+            })?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        text.trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|secs| output.status.success() && secs.is_finite() && *secs > 0.)
+            .map(Duration::from_secs_f64)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "ffprobeで長さを取得できません: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ),
+                )
+            })
+    }
+}
+
+impl DurationProvider for FfprobeFallback {
+    fn duration(&self, source: &dyn InputSource, path: &Path) -> io::Result<Duration> {
+        WavDurationProvider
+            .duration(source, path)
+            .or_else(|wav_error| {
+                self.probe(path)
+                    .map_err(|e| io::Error::new(wav_error.kind(), format!("{} / {}", wav_error, e)))
+            })
+    }
+}
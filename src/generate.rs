@@ -0,0 +1,1328 @@
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use clap::{Args, ValueEnum};
+use voicepeak_srt_core::{
+    audio::{Clip, ConcatOptions, Loudness, SilenceCheck},
+    cache::{DurationCache, CACHE_FILE_NAME},
+    chapters, decode_shift_jis, diff, emotion, format_srt_time,
+    furigana::{self, ReadingDictionary, RubyStyle, RubyTarget},
+    parse::{self, SubtitleFormat},
+    screenplay, transform,
+    validate::{chars_per_second, suggest_split, SpeechRate},
+    AssWriter, CueAlign, CueIdentifier, CuePosition, DuplicatePolicy, EmptyTextPolicy,
+    EncodedWriter, FilePattern, HourOverflow, JsonWriter, LineEnding, Metadata, MltWriter,
+    MultilinePolicy, NewlineWriter, OutputEncoding, OutputFile, Positions, Project, RegionWriter,
+    SrtBlock, SrtWriter, Strictness, SubtitleBuilder, SubtitleWriter, Timeline, TimestampFormat,
+    VttWriter, WavDurationProvider,
+};
+
+#[cfg(feature = "script")]
+use crate::script;
+use crate::{
+    audio::{self, AudioOutput},
+    ffprobe::FfprobeFallback,
+    html_preview, mecab, presets,
+};
+
+/// 標準出力に書き出す出力先の指定
+const STDOUT: &str = "-";
+
+/// wavとtxtから字幕ファイルを作成する
+#[derive(Args, Debug)]
+pub struct GenerateArgs {
+    #[arg(short, long)]
+    input_path: String,
+    /// 出力先(`-`なら標準出力に書き出す)
+    #[arg(short, long, default_value = "./subtitles.srt")]
+    output_path: String,
+    /// ファイル名の正規表現(名前付きキャプチャseq・speaker・textを使う。プリセットのパターンより優先する)
+    #[arg(long)]
+    pattern: Option<String>,
+    /// 音声合成ソフトの書き出しに合わせたファイル名のパターンと文字コード(`presets list`で一覧を表示する)
+    #[arg(long, default_value = "voicepeak")]
+    preset: String,
+    /// 独自のプリセットを書いたJSON
+    #[arg(long)]
+    preset_file: Option<PathBuf>,
+    /// 音声の順とナレーターを並べた書き出し一覧(JSONか1行に`ファイル名<TAB>ナレーター`)。ファイル名の連番より優先する
+    #[arg(long)]
+    timeline: Option<PathBuf>,
+    /// txtの代わりに`話者: セリフ`形式の台本を使う(セリフは音声と連番の順に対応させる)
+    #[arg(long)]
+    screenplay: Option<PathBuf>,
+    /// txtの代わりに1行1セリフ(`話者,セリフ`も可)の一覧を使う(UTF-8かShift-JIS)
+    #[arg(long, conflicts_with = "screenplay")]
+    text_list: Option<PathBuf>,
+    /// txtの代わりに、全音声のテキストを空行か`---`の行で区切ってまとめたファイルを使う(UTF-8かShift-JIS)
+    #[arg(long, conflicts_with_all = ["screenplay", "text_list"])]
+    combined_script: Option<PathBuf>,
+    /// 各音声のテキストの訳を空行か`---`の行で区切って並べたファイル(元のテキストの下に重ねる。ASSでは別のスタイルにする)
+    #[arg(long)]
+    translation: Option<PathBuf>,
+    /// 感情パラメーターを読み込む.vpp(または書き出した情報のJSON、ブロックは音声の順に対応させる)
+    #[arg(long)]
+    emotions: Option<PathBuf>,
+    /// --emotionsでこの強さ(0〜100)より強い感情だけを使う
+    #[arg(long, default_value_t = 0.)]
+    emotion_threshold: f64,
+    /// 感情ごとの文字の色(srtは<font color>、assは感情の名前のスタイル。例: --emotion-color happy=#ffd700、複数指定可)
+    #[arg(long, value_parser = parse_emotion_color)]
+    emotion_color: Vec<(String, String)>,
+    /// ASSを縦書きにして画面の右端に置く(英数字は全角にし、句読点が列の先頭に来ないよう折り返す)
+    #[arg(long)]
+    vertical: bool,
+    /// --verticalの1列の文字数
+    #[arg(long, default_value_t = 18)]
+    vertical_column: usize,
+    /// ASSの縦書きに使うフォント(縦書き用の`@`は自動で付ける)
+    #[arg(long, default_value = "MS Gothic")]
+    vertical_font: String,
+    /// 聴覚障害者向け字幕(SDH)にする(話者が替わるたびに`（話者）`を付ける。txtや台本の`[音楽]`のような行は注記として話者を付けない)
+    #[arg(long)]
+    sdh: bool,
+    /// 漢字に読みを添える(rareは常用漢字にない漢字だけ、allはすべての漢字)。読みはMeCabで求める
+    #[arg(long, value_enum)]
+    furigana: Option<Furigana>,
+    /// --furiganaでMeCabの代わりに使う読みの辞書(1行に`表記<TAB>よみ`)
+    #[arg(long)]
+    furigana_dict: Option<PathBuf>,
+    /// mecabのパス
+    #[arg(long, default_value = "mecab")]
+    mecab: String,
+    /// ASSの読みの文字の大きさ(ASS以外では読みを括弧で添える)
+    #[arg(long, default_value_t = 24)]
+    furigana_font_size: u32,
+    /// ASSの訳のスタイルの文字の大きさ(1080pでの大きさ)
+    #[arg(long, default_value_t = 36)]
+    translation_font_size: u32,
+    /// 出力形式
+    #[arg(short, long, value_enum, default_value_t = Format::Srt)]
+    format: Format,
+    /// 字幕の文字コード(utf-8・utf-8-bom・shift-jis・utf-16le)
+    #[arg(long, default_value = "utf-8", value_parser = parse_output_encoding)]
+    output_encoding: OutputEncoding,
+    /// 先頭にBOMを付ける(文字コードを自動で判定できないWindowsのエディターや再生機器向け)
+    #[arg(long)]
+    bom: bool,
+    /// 改行コード(lf・crlf)
+    #[arg(long, default_value = "lf", value_parser = parse_line_endings)]
+    line_endings: LineEnding,
+    /// srt・vttのタイムスタンプの秒の区切り文字(省略時はsrtがcomma、vttがperiod)
+    #[arg(long)]
+    time_separator: Option<TimeSeparator>,
+    /// srt・vttのタイムスタンプの小数点以下の桁数
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(0..=3))]
+    time_precision: u8,
+    /// srt・vttで100時間以上の時刻をどう書き出すか
+    #[arg(long, value_enum, default_value_t = HoursOverflow::Extend)]
+    hours_overflow: HoursOverflow,
+    /// vttのヘッダに書き足す情報(`Title=番組名`、`Language=ja`など。繰り返し指定できる)
+    #[arg(long, value_parser = parse_vtt_meta)]
+    vtt_meta: Vec<(String, String)>,
+    /// vttの先頭に書き足すNOTEブロック(繰り返し指定できる)
+    #[arg(long, value_parser = parse_vtt_note)]
+    vtt_note: Vec<String>,
+    /// vttのブロックの識別子(元の音声のファイル名や連番にすると、字幕から音声をたどれる)
+    #[arg(long, value_enum, default_value_t = VttId::Index)]
+    vtt_id: VttId,
+    /// srt・vttの番号の行を書き出さない
+    #[arg(long)]
+    no_index: bool,
+    /// assに--concat-audioの音声を書き込み、Aegisubで開くと音声も読み込まれるようにする
+    #[arg(long, requires = "concat_audio")]
+    aegisub: bool,
+    /// assに書き込む、Aegisubで開く動画
+    #[arg(long)]
+    video: Option<PathBuf>,
+    /// 出力先に合わせた設定(youtubeは字幕を再生バーより上に表示する)
+    #[arg(long, value_enum)]
+    profile: Option<Profile>,
+    /// 字幕の表示位置(bottom・top・画面上端からの割合%、vttでは割合、srtでは上下のみ反映)
+    #[arg(long, value_parser = parse_position)]
+    position: Option<CuePosition>,
+    /// 話者ごとの表示位置(例: --speaker-position B=top、複数指定可)
+    #[arg(long, value_parser = parse_speaker_position)]
+    speaker_position: Vec<(String, CuePosition)>,
+    /// 字幕の横方向の揃え(left・center・right)
+    #[arg(long, value_parser = parse_align)]
+    align: Option<CueAlign>,
+    /// 話者ごとの揃え(例: --speaker-align B=right、複数指定可)
+    #[arg(long, value_parser = parse_speaker_align)]
+    speaker_align: Vec<(String, CueAlign)>,
+    /// srtで話者ごとにテキストを囲む<font color>の色(例: --speaker-color B=#ff8800、複数指定可)
+    #[arg(long, value_parser = parse_speaker_color)]
+    speaker_color: Vec<(String, String)>,
+    /// 全体の開始時刻をずらす(ミリ秒)
+    #[arg(long, default_value_t = 0)]
+    offset_ms: u64,
+    /// 最初のブロックの番号
+    #[arg(long, default_value_t = 1)]
+    start_index: usize,
+    /// 音声の間に入れる無音(ミリ秒、字幕と--concat-audioの両方に反映する)
+    #[arg(long, default_value_t = 0)]
+    gap_ms: u64,
+    /// 音声のつなぎ目をクロスフェードする(ミリ秒、--gap-msより長い分だけ音声を重ね、字幕の時刻も合わせる)
+    #[arg(long, default_value_t = 0)]
+    crossfade_ms: u64,
+    /// 1行の最大文字数(0で折り返さない)
+    #[arg(long, default_value_t = 0)]
+    wrap: usize,
+    /// 1秒あたりの文字数がこれを超えるブロックを警告する
+    #[arg(long)]
+    max_cps: Option<f64>,
+    /// 警告も含めて問題があれば失敗する
+    #[arg(long, conflicts_with = "lenient")]
+    strict: bool,
+    /// 揃っていない・読み込めないファイルを飛ばして続ける
+    #[arg(long)]
+    lenient: bool,
+    /// 同じ連番のファイルが複数あるときは更新日時が最も新しいものを使う
+    #[arg(long)]
+    newest_duplicate: bool,
+    /// テキストをNFCに正規化する(macOSで書き出したファイル向け)
+    #[arg(long)]
+    nfc: bool,
+    /// 1秒あたりに読み上げる文字数(音声とテキストの長さが大きく違う連番を警告する)
+    #[arg(long, default_value_t = 7.)]
+    expected_cps: f64,
+    /// 音声とテキストの長さを比較しない
+    #[arg(long)]
+    no_duration_check: bool,
+    /// ほとんど無音の音声(書き出しに失敗した音声など)を警告する
+    #[arg(long)]
+    check_silence: bool,
+    /// --check-silenceで無音とみなす音量(dBFS)
+    #[arg(long, default_value_t = -60., allow_hyphen_values = true)]
+    silence_threshold_db: f64,
+    /// 音声の長さのキャッシュ(入力フォルダーの.voicepeak-srt.cache)を使わない
+    #[arg(long)]
+    no_cache: bool,
+    /// 既存の出力ファイルとの差分を表示してから書き出す
+    #[arg(long)]
+    diff: bool,
+    /// 既存の出力ファイルの最後のブロックの終わりと番号から続けて書き足す(何回かに分けて合成する場合向け)
+    #[arg(long)]
+    append: bool,
+    /// 書き出さずに確認だけ行う(--diffと組み合わせて変更を確認する)
+    #[arg(long)]
+    dry_run: bool,
+    /// 字幕と同じ時間軸で音声をつなげて書き出す(拡張子がmp3・opus・m4a・aacならffmpegでエンコードする)
+    #[arg(long)]
+    concat_audio: Option<PathBuf>,
+    /// つなげた音声のラウドネスを揃える(目標のLUFS、例: -16)
+    #[arg(long, allow_negative_numbers = true)]
+    loudness: Option<f64>,
+    /// --loudnessで全体ではなく音声ごとに揃える
+    #[arg(long, requires = "loudness")]
+    loudness_per_clip: bool,
+    /// つなげた音声のサンプリングレート(省略時は最初のwavに合わせ、異なるwavは変換する)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    sample_rate: Option<u32>,
+    /// 字幕ごとの音声を`HH-MM-SS_mmm__テキスト.wav`の名前でこのフォルダーにコピーする
+    #[arg(long)]
+    export_cues: Option<PathBuf>,
+    /// 入力フォルダーのchapters.txt(1行に`連番 章の名前`)の章を、音声の時刻に合わせて書き出す
+    #[arg(long)]
+    chapters_out: Option<PathBuf>,
+    /// --chapters-outの形式
+    #[arg(long, value_enum, default_value_t = ChapterFormat::Vtt)]
+    chapter_format: ChapterFormat,
+    /// 入力フォルダーの中の章ごとのフォルダーから、それぞれ字幕と音声を作成する
+    #[arg(long)]
+    split_by_chapter: bool,
+    /// --split-by-chapterで、全章を続けて並べたときの各章の開始時刻をJSONに書き出す
+    #[arg(long, requires = "split_by_chapter")]
+    chapter_map: Option<PathBuf>,
+    /// つなげた音声の波形のピークを書き出す(audiowaveform形式、拡張子がdatならバイナリ、それ以外はJSON)
+    #[arg(long, requires = "concat_audio")]
+    peaks: Option<PathBuf>,
+    /// つなげた音声を埋め込み、字幕の一覧を押すとその時刻から再生できるHTMLを書き出す
+    #[arg(long, requires = "concat_audio")]
+    html_preview: Option<PathBuf>,
+    /// ブロックごとの話者・感情・元の音声・連番をJSONに書き出す
+    #[arg(long)]
+    metadata: Option<PathBuf>,
+    /// 1ピークあたりのフレーム数
+    #[arg(long, default_value_t = 256, value_parser = clap::value_parser!(u32).range(1..))]
+    peaks_zoom: u32,
+    /// エンコードするときのビットレート
+    #[arg(long, default_value = "192k")]
+    audio_bitrate: String,
+    /// wavとして読めない音声(24bit・ADPCMなど)の長さをffprobeで取得する
+    #[arg(long)]
+    allow_ffprobe: bool,
+    /// ffprobeのパス
+    #[arg(long, default_value = "ffprobe")]
+    ffprobe: String,
+    /// ffmpegのパス
+    #[arg(long, default_value = "ffmpeg")]
+    ffmpeg: String,
+    /// 空のtxtの扱い
+    #[arg(long, value_enum, default_value_t = EmptyText::Skip)]
+    empty_text: EmptyText,
+    /// --empty-text placeholderで入れるテキスト
+    #[arg(long, default_value = "…")]
+    placeholder: String,
+    /// 複数行のtxtの扱い(空行は取り除く)
+    #[arg(long, value_enum, default_value_t = Multiline::Keep)]
+    multiline: Multiline,
+    /// ブロックごとに呼び出すrhaiスクリプト(transform(cue)関数を定義する)
+    #[cfg(feature = "script")]
+    #[arg(long)]
+    script: Option<std::path::PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    Srt,
+    Vtt,
+    Ass,
+    Json,
+    /// Vegas Proなどの編集ソフト向けのリージョン一覧(タブ区切り)
+    Regions,
+    /// Shotcut(MLT XML)の字幕トラック
+    Mlt,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum TimeSeparator {
+    /// `00:00:01,500`
+    Comma,
+    /// `00:00:01.500`
+    Period,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum HoursOverflow {
+    /// 時の桁を増やす(`100:00:00,000`)
+    Extend,
+    /// エラーにする
+    Error,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum VttId {
+    /// 字幕番号
+    Index,
+    /// 元の音声のファイル名
+    File,
+    /// 元の音声の連番
+    Sequence,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ChapterFormat {
+    /// WebVTTのチャプタートラック
+    Vtt,
+    /// ffmpegのメタデータ(-map_metadataで音声や動画に埋め込む)
+    Ffmetadata,
+    /// YouTubeの概要欄に貼るタイムスタンプ
+    Youtube,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Profile {
+    /// YouTubeの再生バーに重ならないよう字幕を上げる
+    Youtube,
+}
+
+/// 文字コードの名前を書き出す文字コードにする
+pub fn parse_output_encoding(text: &str) -> Result<OutputEncoding, String> {
+    match text.to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" => Ok(OutputEncoding::Utf8),
+        "utf-8-bom" | "utf8-bom" => Ok(OutputEncoding::Utf8Bom),
+        "shift-jis" | "shift_jis" | "sjis" | "cp932" => Ok(OutputEncoding::ShiftJis),
+        "utf-16le" | "utf-16" => Ok(OutputEncoding::Utf16Le),
+        _ => Err(format!(
+            "utf-8・utf-8-bom・shift-jis・utf-16leのいずれかを指定してください: {}",
+            text
+        )),
+    }
+}
+
+/// `lf`・`crlf`を改行コードにする
+pub fn parse_line_endings(text: &str) -> Result<LineEnding, String> {
+    match text.to_ascii_lowercase().as_str() {
+        "lf" => Ok(LineEnding::Lf),
+        "crlf" => Ok(LineEnding::Crlf),
+        _ => Err(format!("lf・crlfのいずれかを指定してください: {}", text)),
+    }
+}
+
+/// `--bom`の指定に合わせて書き出す文字コードを決める
+pub fn output_encoding(encoding: OutputEncoding, bom: bool) -> Result<OutputEncoding, String> {
+    match bom {
+        true => encoding
+            .with_bom()
+            .ok_or_else(|| "Shift-JISにはBOMを付けられません".to_string()),
+        false => Ok(encoding),
+    }
+}
+
+/// `bottom`・`top`・画面上端からの割合(%)を表示位置にする
+fn parse_position(text: &str) -> Result<CuePosition, String> {
+    match text {
+        "bottom" => Ok(CuePosition::Bottom),
+        "top" => Ok(CuePosition::Top),
+        _ => text
+            .trim_end_matches('%')
+            .parse::<u8>()
+            .ok()
+            .filter(|&percent| percent <= 100)
+            .map(CuePosition::Line)
+            .ok_or_else(|| format!("bottom・top・0〜100のいずれかを指定してください: {}", text)),
+    }
+}
+
+/// `話者=位置`を話者ごとの表示位置にする
+fn parse_speaker_position(text: &str) -> Result<(String, CuePosition), String> {
+    let (speaker, position) = text
+        .split_once('=')
+        .ok_or_else(|| format!("話者=位置の形式で指定してください: {}", text))?;
+    Ok((speaker.to_string(), parse_position(position)?))
+}
+
+/// `left`・`center`・`right`を揃えにする
+fn parse_align(text: &str) -> Result<CueAlign, String> {
+    match text {
+        "left" => Ok(CueAlign::Left),
+        "center" => Ok(CueAlign::Center),
+        "right" => Ok(CueAlign::Right),
+        _ => Err(format!(
+            "left・center・rightのいずれかを指定してください: {}",
+            text
+        )),
+    }
+}
+
+/// `話者=揃え`を話者ごとの揃えにする
+fn parse_speaker_align(text: &str) -> Result<(String, CueAlign), String> {
+    let (speaker, align) = text
+        .split_once('=')
+        .ok_or_else(|| format!("話者=揃えの形式で指定してください: {}", text))?;
+    Ok((speaker.to_string(), parse_align(align)?))
+}
+
+/// `話者=#RRGGBB`を話者ごとの色にする
+fn parse_speaker_color(text: &str) -> Result<(String, String), String> {
+    let (speaker, color) = text
+        .split_once('=')
+        .ok_or_else(|| format!("話者=色の形式で指定してください: {}", text))?;
+    check_color(color)?;
+    Ok((speaker.to_string(), color.to_string()))
+}
+
+/// `感情=#RRGGBB`を感情ごとの色にする
+fn parse_emotion_color(text: &str) -> Result<(String, String), String> {
+    let (emotion, color) = text
+        .split_once('=')
+        .ok_or_else(|| format!("感情=色の形式で指定してください: {}", text))?;
+    check_color(color)?;
+    Ok((emotion.to_string(), color.to_string()))
+}
+
+fn check_color(color: &str) -> Result<(), String> {
+    let is_hex = color
+        .strip_prefix('#')
+        .is_some_and(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+    match is_hex {
+        true => Ok(()),
+        false => Err(format!("色は#ff8800の形式で指定してください: {}", color)),
+    }
+}
+
+/// `キー=値`をvttのヘッダの情報にする
+fn parse_vtt_meta(text: &str) -> Result<(String, String), String> {
+    let (key, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("キー=値の形式で指定してください: {}", text))?;
+    if key.is_empty() || key.contains(':') || text.contains("-->") || text.contains('\n') {
+        return Err(format!("vttのヘッダに書けない情報です: {}", text));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// NOTEブロックにできるテキストか確かめる
+fn parse_vtt_note(text: &str) -> Result<String, String> {
+    if text.trim().is_empty()
+        || text.contains("-->")
+        || text.lines().any(|line| line.trim().is_empty())
+    {
+        return Err(format!(
+            "空行や-->を含むテキストはNOTEにできません: {}",
+            text
+        ));
+    }
+    Ok(text.to_string())
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Furigana {
+    /// 常用漢字にない漢字だけ
+    Rare,
+    /// すべての漢字
+    All,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Multiline {
+    /// 複数行のまま1つのブロックにする
+    Keep,
+    /// 1行につなげる
+    Join,
+    /// 行ごとのブロックに分け、音声の長さを読みの長さで割り振る
+    Split,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum EmptyText {
+    /// ブロックを作らない
+    Skip,
+    /// --placeholderのテキストを入れる
+    Placeholder,
+    /// エラーにする
+    Error,
+    /// 空のまま出力する
+    Keep,
+}
+
+/// 1つのフォルダーから作成する出力先
+struct Outputs {
+    input_path: String,
+    output_path: String,
+    concat_audio: Option<PathBuf>,
+    export_cues: Option<PathBuf>,
+    peaks: Option<PathBuf>,
+    html_preview: Option<PathBuf>,
+    metadata: Option<PathBuf>,
+}
+
+pub fn run(args: &GenerateArgs) -> crate::CliResult {
+    let outputs = Outputs {
+        input_path: args.input_path.clone(),
+        output_path: args.output_path.clone(),
+        concat_audio: args.concat_audio.clone(),
+        export_cues: args.export_cues.clone(),
+        peaks: args.peaks.clone(),
+        html_preview: args.html_preview.clone(),
+        metadata: args.metadata.clone(),
+    };
+    if !args.split_by_chapter {
+        generate(args, &outputs)?;
+        return Ok(());
+    }
+    if args.output_path == STDOUT {
+        return Err("章ごとに書き出す場合は標準出力を出力先にできません".into());
+    }
+    if args.chapters_out.is_some() {
+        return Err("--chapters-outは--split-by-chapterと同時に使えません".into());
+    }
+
+    // 章ごとのフォルダーを名前順に処理し、出力先の名前に章の名前を付ける
+    let mut chapters: Vec<PathBuf> = fs::read_dir(&args.input_path)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    chapters.sort();
+    if chapters.is_empty() {
+        return Err(format!("{}に章のフォルダーがありません", args.input_path).into());
+    }
+
+    let mut map = Vec::new();
+    let mut offset = Duration::ZERO;
+    for chapter in &chapters {
+        let name = chapter.file_name().unwrap().to_string_lossy().into_owned();
+        let outputs = Outputs {
+            input_path: chapter.to_string_lossy().into_owned(),
+            output_path: chapter_path(Path::new(&outputs.output_path), &name)
+                .to_string_lossy()
+                .into_owned(),
+            concat_audio: outputs
+                .concat_audio
+                .as_deref()
+                .map(|path| chapter_path(path, &name)),
+            export_cues: outputs.export_cues.as_deref().map(|dir| dir.join(&name)),
+            peaks: outputs
+                .peaks
+                .as_deref()
+                .map(|path| chapter_path(path, &name)),
+            html_preview: outputs
+                .html_preview
+                .as_deref()
+                .map(|path| chapter_path(path, &name)),
+            metadata: outputs
+                .metadata
+                .as_deref()
+                .map(|path| chapter_path(path, &name)),
+        };
+        eprintln!("{}: {}", name, outputs.output_path);
+        let duration = generate(args, &outputs)?;
+        map.push(serde_json::json!({
+            "chapter": name,
+            "subtitles": outputs.output_path,
+            "audio": outputs.concat_audio,
+            "offset_ms": offset.as_millis() as u64,
+            "duration_ms": duration.as_millis() as u64,
+        }));
+        offset += duration;
+    }
+
+    // 全章を続けて並べたときの各章の開始時刻
+    if let (Some(path), false) = (&args.chapter_map, args.dry_run) {
+        let mut file = OutputFile::create(path)?;
+        serde_json::to_writer_pretty(&mut file, &serde_json::json!({ "chapters": map }))?;
+        file.commit()?;
+    }
+
+    Ok(())
+}
+
+/// `path`の名前の後ろに章の名前を付ける(`subtitles.srt`→`subtitles-ch01.srt`)
+fn chapter_path(path: &Path, chapter: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, chapter, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, chapter),
+    };
+    path.with_file_name(name)
+}
+
+/// 字幕と音声を作成し、全体の長さ(最後の音声の終わり)を返す
+fn generate(
+    args: &GenerateArgs,
+    outputs: &Outputs,
+) -> Result<Duration, Box<dyn std::error::Error>> {
+    // wavとtxtからsrtのブロック情報を作成する
+    let preset = presets::find(&args.preset, args.preset_file.as_deref())?;
+    let pattern = match &args.pattern {
+        Some(pattern) => FilePattern::new(pattern)?,
+        None => preset.pattern.clone(),
+    };
+    // 書き出し一覧があれば、ファイル名の連番の代わりにその順に並べる
+    let pattern = match &args.timeline {
+        Some(path) => {
+            let text = read_text_file(path, "書き出し一覧")?;
+            let timeline = Timeline::parse(&text)
+                .map_err(|e| format!("書き出し一覧を読み込めません({}): {}", path.display(), e))?;
+            if timeline.entries().is_empty() {
+                return Err(format!("書き出し一覧に音声がありません: {}", path.display()).into());
+            }
+            pattern.with_timeline(timeline)
+        }
+        None => pattern,
+    };
+    let strictness = if args.strict {
+        Strictness::Strict
+    } else if args.lenient {
+        Strictness::Lenient
+    } else {
+        Strictness::Normal
+    };
+    // 既存の出力に書き足す場合は、最後のブロックの終わりと番号から続ける
+    let existing = match args.append {
+        true => existing_cues(args, outputs)?,
+        false => Vec::new(),
+    };
+    let (append_offset, start_index) = match existing.last() {
+        Some(last) => (
+            last.end + Duration::from_millis(args.gap_ms),
+            last.index + 1,
+        ),
+        None => (Duration::ZERO, args.start_index),
+    };
+
+    let warnings = Rc::new(RefCell::new(Vec::new()));
+    let sink = Rc::clone(&warnings);
+    let clips = Rc::new(RefCell::new(Vec::new()));
+    let cues = Rc::new(RefCell::new(Vec::new()));
+    let builder = SubtitleBuilder::from_dir(&outputs.input_path)
+        .preset(&preset)
+        .pattern(pattern.clone())
+        .strictness(strictness)
+        .duplicates(if args.newest_duplicate {
+            DuplicatePolicy::Newest
+        } else {
+            DuplicatePolicy::Error
+        })
+        .empty_text(match args.empty_text {
+            EmptyText::Skip => EmptyTextPolicy::Skip,
+            EmptyText::Placeholder => EmptyTextPolicy::Placeholder(args.placeholder.clone()),
+            EmptyText::Error => EmptyTextPolicy::Error,
+            EmptyText::Keep => EmptyTextPolicy::Keep,
+        })
+        .multiline(match args.multiline {
+            Multiline::Keep => MultilinePolicy::Keep,
+            Multiline::Join => MultilinePolicy::Join,
+            Multiline::Split => MultilinePolicy::Split,
+        })
+        .normalize_nfc(args.nfc)
+        .check_speech_rate((!args.no_duration_check).then(|| SpeechRate::new(args.expected_cps)))
+        .check_silence(
+            args.check_silence
+                .then(|| SilenceCheck::new(args.silence_threshold_db)),
+        )
+        .on_warning(move |e| sink.borrow_mut().push(e))
+        .on_clip({
+            let clips = Rc::clone(&clips);
+            move |clip| clips.borrow_mut().push(clip)
+        })
+        .map({
+            // 分けたブロックも元の音声と対応づけるため、音声の順と合わせて記録する
+            let clips = Rc::clone(&clips);
+            let cues = Rc::clone(&cues);
+            move |block| {
+                if let Some(clip) = clip_position(&clips.borrow(), block) {
+                    cues.borrow_mut().push((clip, block.text.clone()));
+                }
+            }
+        })
+        .gap(Duration::from_millis(args.gap_ms))
+        .crossfade(Duration::from_millis(args.crossfade_ms))
+        .offset(Duration::from_millis(args.offset_ms) + append_offset)
+        .wrap(args.wrap)
+        .start_index(start_index);
+
+    // 音声の長さはコア数だけ並列に取得し、wavとして読めない音声はffprobeで取得する。
+    // 前回から変わっていない音声はキャッシュの長さを使う
+    let cache = (!args.no_cache).then(|| {
+        Arc::new(DurationCache::load(
+            Path::new(&outputs.input_path).join(CACHE_FILE_NAME),
+        ))
+    });
+    let builder = match (args.allow_ffprobe, &cache) {
+        (true, Some(cache)) => builder.parallel_duration_provider(DurationCache::provider(
+            cache,
+            FfprobeFallback::new(&args.ffprobe),
+        )),
+        (false, Some(cache)) => {
+            builder.parallel_duration_provider(DurationCache::provider(cache, WavDurationProvider))
+        }
+        (true, None) => builder.parallel_duration_provider(FfprobeFallback::new(&args.ffprobe)),
+        (false, None) => builder.parallel_duration_provider(WavDurationProvider),
+    };
+
+    // 台本があればtxtの代わりに使う
+    let builder = match &args.screenplay {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .map_err(|e| format!("台本を読み込めません({}): {}", path.display(), e))?;
+            builder.screenplay(screenplay::parse_screenplay(&text))
+        }
+        None => builder,
+    };
+
+    // セリフの一覧があればtxtの代わりに使う
+    let builder = match &args.text_list {
+        Some(path) => {
+            let text = read_text_file(path, "セリフの一覧")?;
+            builder.screenplay(screenplay::parse_text_list(&text))
+        }
+        None => builder,
+    };
+
+    // まとめたテキストがあれば、区切りごとに音声の順に割り当てる
+    let builder = match &args.combined_script {
+        Some(path) => {
+            let text = read_text_file(path, "まとめたテキスト")?;
+            builder.screenplay(screenplay::parse_combined_script(&text))
+        }
+        None => builder,
+    };
+
+    // 訳があれば音声の順に割り当て、別のスタイルにできない形式では元のテキストの下に重ねる
+    let builder = match &args.translation {
+        Some(path) => {
+            let text = read_text_file(path, "訳")?;
+            let translations: Vec<String> = screenplay::parse_combined_script(&text)
+                .into_iter()
+                .map(|line| line.text)
+                .collect();
+            let stack = !matches!(args.format, Format::Ass | Format::Json);
+            let clips = Rc::clone(&clips);
+            builder.map(move |block| {
+                block.translation = clip_position(&clips.borrow(), block)
+                    .and_then(|clip| translations.get(clip))
+                    .cloned();
+                if stack {
+                    block.text = block.stacked_text();
+                    block.translation = None;
+                }
+            })
+        }
+        None => builder,
+    };
+
+    // 感情パラメーターがあれば、いちばん強い感情を音声の順に割り当てる
+    let builder = match &args.emotions {
+        Some(path) => {
+            let text = read_text_file(path, "感情パラメーター")?;
+            let emotions: Vec<Option<String>> = emotion::parse_emotions(&text)
+                .map_err(|e| {
+                    format!(
+                        "感情パラメーターを読み込めません({}): {}",
+                        path.display(),
+                        e
+                    )
+                })?
+                .iter()
+                .map(|params| params.dominant(args.emotion_threshold).map(str::to_string))
+                .collect();
+            let clips = Rc::clone(&clips);
+            builder.map(move |block| {
+                let emotion =
+                    clip_position(&clips.borrow(), block).and_then(|clip| emotions.get(clip));
+                if let Some(Some(emotion)) = emotion {
+                    block.emotion = Some(emotion.clone());
+                }
+            })
+        }
+        None => builder,
+    };
+
+    // SDHでは話者が替わるたびに話者名を付ける
+    let builder = match args.sdh {
+        true => builder.transform(transform::SpeakerLabels::new()),
+        false => builder,
+    };
+
+    // 難しい漢字に読みを添える(縦書きの折り返しより前に付ける)
+    let builder = match args.furigana {
+        Some(target) => {
+            let target = match target {
+                Furigana::Rare => RubyTarget::Rare,
+                Furigana::All => RubyTarget::All,
+            };
+            let style = match args.format {
+                Format::Ass => RubyStyle::Ass {
+                    font_size: args.furigana_font_size,
+                },
+                _ => RubyStyle::Brackets,
+            };
+            match &args.furigana_dict {
+                Some(path) => {
+                    let dictionary = ReadingDictionary::parse(&read_text_file(path, "読みの辞書")?);
+                    builder.map(move |block| {
+                        let morphemes = dictionary.segment(&block.text);
+                        block.text = furigana::add_furigana(&morphemes, target, style);
+                    })
+                }
+                None => {
+                    // MeCabを何度も起動しないよう、先にすべてのブロックを作成してまとめて解析する
+                    let mut blocks = builder
+                        .build()
+                        .inspect_err(|_| print_warnings(&warnings.borrow()))?;
+                    let texts: Vec<&str> = blocks.iter().map(|block| block.text.as_str()).collect();
+                    let morphemes = mecab::analyze_all(&args.mecab, &texts)?;
+                    for (block, morphemes) in blocks.iter_mut().zip(morphemes) {
+                        block.text = furigana::add_furigana(&morphemes, target, style);
+                    }
+                    SubtitleBuilder::from_blocks(blocks)
+                }
+            }
+        }
+        None => builder,
+    };
+
+    // 縦書きは列の長さで折り返し、英数字を全角にする
+    let builder = match args.vertical {
+        true if !matches!(args.format, Format::Ass) => {
+            return Err("--verticalはASS(--format ass)でのみ使えます".into())
+        }
+        true => {
+            let column = args.vertical_column;
+            builder.map(move |block| block.text = transform::vertical_text(&block.text, column))
+        }
+        false => builder,
+    };
+
+    // スクリプトによる変換
+    #[cfg(feature = "script")]
+    let builder = match &args.script {
+        Some(path) => builder.transform(
+            script::ScriptTransform::from_file(path)
+                .map_err(|e| format!("スクリプトを読み込めません: {}", e))?,
+        ),
+        None => builder,
+    };
+
+    // 既存の出力と比べる場合は、先にすべてのブロックを作成する
+    let to_stdout = outputs.output_path == STDOUT;
+    if args.diff && to_stdout {
+        return Err("標準出力に書き出す場合は--diffを使えません".into());
+    }
+    let builder = if args.diff {
+        let blocks = builder
+            .build()
+            .inspect_err(|_| print_warnings(&warnings.borrow()))?;
+        print_diff(&outputs.output_path, args.format, &blocks)?;
+        SubtitleBuilder::from_blocks(blocks)
+    } else {
+        builder
+    };
+
+    // 字幕ファイル作成(失敗した場合は既存のファイルを残す)
+    // ブロックは作成したそばから書き出すため、字幕全体をメモリに持たない
+    let mut file = match args.dry_run || to_stdout {
+        true => None,
+        false => Some(OutputFile::create(&outputs.output_path)?),
+    };
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+    let mut sink = io::sink();
+    let out: &mut dyn Write = match &mut file {
+        Some(file) => file,
+        None if to_stdout && !args.dry_run => &mut stdout,
+        None => &mut sink,
+    };
+    let out = NewlineWriter::new(
+        EncodedWriter::new(out, output_encoding(args.output_encoding, args.bom)?),
+        args.line_endings,
+    );
+    let mut writer: Box<dyn SubtitleWriter + '_> = match args.format {
+        Format::Srt => {
+            let writer = args.speaker_color.iter().fold(
+                SrtWriter::new(out)
+                    .with_positions(positions(args))
+                    .with_timestamp_format(timestamp_format(args, TimestampFormat::srt()))
+                    .with_index(!args.no_index),
+                |writer, (speaker, color)| writer.with_speaker_color(speaker, color),
+            );
+            Box::new(
+                args.emotion_color
+                    .iter()
+                    .fold(writer, |writer, (emotion, color)| {
+                        writer.with_emotion_color(emotion, color)
+                    }),
+            )
+        }
+        Format::Vtt => {
+            let writer = VttWriter::new(out)
+                .with_positions(positions(args))
+                .with_timestamp_format(timestamp_format(args, TimestampFormat::vtt()))
+                .with_index(!args.no_index)
+                .with_identifier(match args.vtt_id {
+                    VttId::Index => CueIdentifier::Index,
+                    VttId::File => CueIdentifier::FileName,
+                    VttId::Sequence => CueIdentifier::Sequence(pattern.clone()),
+                });
+            let writer = args.vtt_meta.iter().fold(writer, |writer, (key, value)| {
+                writer.with_metadata(key.as_str(), value.as_str())
+            });
+            Box::new(
+                args.vtt_note
+                    .iter()
+                    .fold(writer, |writer, note| writer.with_note(note.as_str())),
+            )
+        }
+        Format::Ass => Box::new(
+            args.emotion_color
+                .iter()
+                .fold(
+                    match args.vertical {
+                        true => AssWriter::new(out).with_font(args.vertical_font.as_str(), 48),
+                        false => AssWriter::new(out),
+                    },
+                    |writer, (emotion, color)| writer.with_emotion_style(emotion, color),
+                )
+                .with_positions(positions(args))
+                .with_translation_font_size(args.translation_font_size)
+                .with_vertical(args.vertical)
+                .with_aegisub_files(
+                    outputs
+                        .concat_audio
+                        .as_deref()
+                        .filter(|_| args.aegisub)
+                        .map(|path| relative_to_output(path, &outputs.output_path)),
+                    args.video
+                        .as_deref()
+                        .map(|path| relative_to_output(path, &outputs.output_path)),
+                ),
+        ),
+        Format::Json => Box::new(JsonWriter::new(out)),
+        Format::Regions => Box::new(RegionWriter::new(out)),
+        Format::Mlt => Box::new(MltWriter::new(out)),
+    };
+    // プレビューとブロックごとの情報、速すぎるブロックの確認には、番号を振り直して書き出したブロックを使う
+    let keep_finished = outputs.html_preview.is_some() || outputs.metadata.is_some();
+    let mut finished = Vec::new();
+    let mut fast_blocks = Vec::new();
+    let result = write_after(&existing, builder, &mut writer, |block| {
+        if keep_finished {
+            finished.push(block.clone());
+        }
+        if let Some(max_cps) = args.max_cps {
+            let cps = chars_per_second(block);
+            if cps > max_cps {
+                fast_blocks.push((block.clone(), cps));
+            }
+        }
+    });
+    drop(writer);
+
+    // 問題は最後にまとめて表示する
+    print_warnings(&warnings.borrow());
+    result?;
+
+    report_fast_blocks(&fast_blocks, args.max_cps.unwrap_or_default());
+    if args.strict && !fast_blocks.is_empty() {
+        return Err("厳格モードのため、速すぎるブロックがある字幕は書き出しません".into());
+    }
+
+    if let Some(file) = file {
+        file.commit()?;
+    }
+
+    // キャッシュを書き出せなくても字幕はできているので、警告だけにする
+    if let (Some(cache), false) = (&cache, args.dry_run) {
+        if let Err(e) = cache.save() {
+            eprintln!("警告: 音声の長さのキャッシュを書き出せません: {}", e);
+        }
+    }
+
+    // 字幕の書き出しに成功してから音声をつなげる
+    if let (Some(path), false) = (&outputs.concat_audio, args.dry_run) {
+        let offset = Duration::from_millis(args.offset_ms);
+        audio::write_concat_audio(
+            &outputs.input_path,
+            &clips.borrow(),
+            offset,
+            &ConcatOptions::new()
+                .loudness(args.loudness.map(|target| {
+                    if args.loudness_per_clip {
+                        Loudness::Clip(target)
+                    } else {
+                        Loudness::Program(target)
+                    }
+                }))
+                .crossfade(Duration::from_millis(args.crossfade_ms))
+                .sampling_rate(args.sample_rate),
+            &AudioOutput {
+                path,
+                ffmpeg: &args.ffmpeg,
+                bitrate: &args.audio_bitrate,
+                peaks: outputs.peaks.as_deref(),
+                zoom: args.peaks_zoom,
+            },
+        )?;
+    }
+    if let (Some(path), Some(audio), false) =
+        (&outputs.html_preview, &outputs.concat_audio, args.dry_run)
+    {
+        html_preview::write_html_preview(path, audio, &finished)?;
+    }
+    if let (Some(path), false) = (&outputs.metadata, args.dry_run) {
+        let mut file = OutputFile::create(path)?;
+        Metadata::from_blocks(&finished, &pattern).write_json(&mut file)?;
+        file.commit()?;
+    }
+    if let (Some(dir), false) = (&outputs.export_cues, args.dry_run) {
+        let offset = Duration::from_millis(args.offset_ms);
+        audio::export_cues(dir, &clips.borrow(), &cues.borrow(), offset)?;
+    }
+
+    // 章の一覧の連番を、その音声の開始時刻にして書き出す
+    if let (Some(path), false) = (&args.chapters_out, args.dry_run) {
+        let titles = chapters::read_chapters(Path::new(&outputs.input_path))?.ok_or_else(|| {
+            format!(
+                "{}に{}がありません",
+                outputs.input_path,
+                chapters::CHAPTERS_FILE_NAME
+            )
+        })?;
+        let offset = Duration::from_millis(args.offset_ms) + append_offset;
+        let placed = chapters::place_chapters(&titles, &clips.borrow(), &pattern, offset);
+        let mut file = OutputFile::create(path)?;
+        match args.chapter_format {
+            ChapterFormat::Vtt => chapters::write_vtt_chapters(&placed, &mut file)?,
+            ChapterFormat::Ffmetadata => chapters::write_ffmetadata(&placed, &mut file)?,
+            ChapterFormat::Youtube => {
+                file.write_all(chapters::youtube_chapters(&placed).as_bytes())?
+            }
+        }
+        file.commit()?;
+        eprintln!("{}章を書き出しました: {}", placed.len(), path.display());
+    }
+
+    let end = clips
+        .borrow()
+        .iter()
+        .map(|clip| clip.start + clip.duration)
+        .max()
+        .unwrap_or_default();
+    Ok(end + Duration::from_millis(args.offset_ms))
+}
+
+/// UTF-8かShift-JISのテキストファイルを読み込む(`what`はエラーに表示する名前)
+fn read_text_file(path: &Path, what: &str) -> Result<String, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("{}を読み込めません({}): {}", what, path.display(), e))?;
+    String::from_utf8(bytes)
+        .or_else(|e| decode_shift_jis(e.as_bytes()).ok_or(()))
+        .map_err(|_| {
+            format!(
+                "{}がUTF-8でもShift-JISでもありません: {}",
+                what,
+                path.display()
+            )
+        })
+}
+
+/// `--append`で続ける既存の出力を読み込む(まだなければ空)
+fn existing_cues(
+    args: &GenerateArgs,
+    outputs: &Outputs,
+) -> Result<Vec<SrtBlock>, Box<dyn std::error::Error>> {
+    if outputs.output_path == STDOUT {
+        return Err("標準出力に書き出す場合は--appendを使えません".into());
+    }
+    if args.diff || args.split_by_chapter {
+        return Err("--appendは--diff・--split-by-chapterと同時に使えません".into());
+    }
+    // 音声は今回のフォルダーの分しか作れず、字幕と時間軸が合わない
+    if outputs.concat_audio.is_some() || outputs.export_cues.is_some() {
+        return Err("--appendは--concat-audio・--export-cuesと同時に使えません".into());
+    }
+    let path = Path::new(&outputs.output_path);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(parse::read_subtitle_file(path)?)
+}
+
+/// 既存のブロックに続けて、新しく作成したブロックを書き出す
+///
+/// 新しいブロックは書き出すたびに`on_written`にも渡す(番号は振り直した後のもの)
+fn write_after<F: FnMut(&SrtBlock)>(
+    existing: &[SrtBlock],
+    builder: SubtitleBuilder,
+    writer: &mut dyn SubtitleWriter,
+    mut on_written: F,
+) -> voicepeak_srt_core::Result<()> {
+    writer.write_header()?;
+    for block in existing {
+        writer.write_cue(block)?;
+    }
+    for block in builder {
+        let block = block?;
+        writer.write_cue(&block)?;
+        on_written(&block);
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// 字幕ファイルと同じフォルダーにあればファイル名だけにする(Aegisubは字幕からの相対パスで探す)
+fn relative_to_output(path: &Path, output_path: &str) -> String {
+    let output_dir = Path::new(output_path).parent().unwrap_or(Path::new(""));
+    match path.parent() {
+        Some(dir) if dir == output_dir => path.file_name().unwrap().to_string_lossy().into_owned(),
+        _ => path.to_string_lossy().into_owned(),
+    }
+}
+
+/// 形式の既定の書き方`base`にタイムスタンプの指定を反映する
+fn timestamp_format(args: &GenerateArgs, base: TimestampFormat) -> TimestampFormat {
+    let format =
+        base.with_precision(args.time_precision)
+            .with_hour_overflow(match args.hours_overflow {
+                HoursOverflow::Extend => HourOverflow::Extend,
+                HoursOverflow::Error => HourOverflow::Error,
+            });
+    match args.time_separator {
+        Some(TimeSeparator::Comma) => format.with_separator(','),
+        Some(TimeSeparator::Period) => format.with_separator('.'),
+        None => format,
+    }
+}
+
+/// 指定された表示位置をまとめる
+fn positions(args: &GenerateArgs) -> Positions {
+    let default = match (args.position, args.profile) {
+        (Some(position), _) => position,
+        (None, Some(Profile::Youtube)) => CuePosition::Line(75),
+        (None, None) => CuePosition::Bottom,
+    };
+    let positions = args.speaker_position.iter().fold(
+        Positions::new()
+            .default_position(default)
+            .default_align(args.align.unwrap_or_default()),
+        |positions, (speaker, position)| positions.speaker(speaker, *position),
+    );
+    args.speaker_align
+        .iter()
+        .fold(positions, |positions, (speaker, align)| {
+            positions.speaker_align(speaker, *align)
+        })
+}
+
+/// 警告をまとめて表示する
+fn print_warnings(warnings: &[voicepeak_srt_core::Error]) {
+    for warning in warnings {
+        eprintln!("警告: {}", warning);
+    }
+}
+
+/// 既存の出力ファイルとの差分を表示する
+fn print_diff(path: &str, format: Format, blocks: &[SrtBlock]) -> crate::CliResult {
+    // Shift-JISで書き出したファイルとも比べられるようにする
+    let text = std::fs::read(path).map(|bytes| {
+        String::from_utf8(bytes)
+            .or_else(|e| decode_shift_jis(e.as_bytes()).ok_or(()))
+            .unwrap_or_default()
+    });
+    let old = match text {
+        Ok(text) => match format {
+            Format::Srt => parse::parse_subtitles(&text, SubtitleFormat::Srt)?,
+            Format::Vtt => parse::parse_subtitles(&text, SubtitleFormat::Vtt)?,
+            Format::Json => Project::from_json(text.as_bytes())?.cues,
+            Format::Ass | Format::Regions | Format::Mlt => {
+                return Err("この形式の差分の表示には対応していません".into())
+            }
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let diff = diff::unified_diff(&old, blocks, path, &format!("{} (新)", path), 1);
+    if diff.is_empty() {
+        eprintln!("{}から変更はありません", path);
+    } else {
+        print!("{}", diff);
+    }
+
+    Ok(())
+}
+
+/// ブロックの元になった音声が、飛ばした音声も含めて何番目(0から)か
+///
+/// 番号は飛ばしたブロックや分けたブロックでずれるため、訳や感情を音声の順に割り当てるときに使う
+fn clip_position(clips: &[Clip], block: &SrtBlock) -> Option<usize> {
+    let source = block.source.as_deref()?;
+    clips.iter().rposition(|clip| clip.path == source)
+}
+
+/// 速すぎるブロックと分割案を表示する
+fn report_fast_blocks(blocks: &[(SrtBlock, f64)], max_cps: f64) {
+    if blocks.is_empty() {
+        return;
+    }
+
+    eprintln!(
+        "警告: 1秒あたり{}文字を超えるブロックが{}件あります",
+        max_cps,
+        blocks.len()
+    );
+    for (block, cps) in blocks {
+        eprintln!(
+            "  ブロック{} ({} --> {}, {:.1}文字/秒): {}",
+            block.index,
+            format_srt_time(block.start),
+            format_srt_time(block.end),
+            cps,
+            block.text.replace('\n', " ")
+        );
+        if let Some((head, tail)) = suggest_split(&block.text) {
+            eprintln!("    分割案: 「{}」/「{}」", head, tail);
+        }
+    }
+}
+
+#[test]
+fn test_chapter_path() {
+    assert_eq!(
+        Path::new("out/subtitles-ch01.srt"),
+        chapter_path(Path::new("out/subtitles.srt"), "ch01")
+    );
+    assert_eq!(
+        Path::new("voice-ch02"),
+        chapter_path(Path::new("voice"), "ch02")
+    );
+}
+
+#[test]
+fn test_relative_to_output() {
+    assert_eq!(
+        relative_to_output(Path::new("./voice.wav"), "./voice.ass"),
+        "voice.wav"
+    );
+    assert_eq!(
+        relative_to_output(Path::new("audio/voice.wav"), "voice.ass"),
+        "audio/voice.wav"
+    );
+}
+
+#[test]
+fn test_write_after() {
+    let existing = [SrtBlock::new(
+        1,
+        Duration::ZERO,
+        Duration::from_secs(1),
+        "前回",
+    )];
+    let builder = SubtitleBuilder::from_blocks(vec![
+        SrtBlock::new(1, Duration::ZERO, Duration::from_secs(1), "除く"),
+        SrtBlock::new(2, Duration::ZERO, Duration::from_secs(2), "今回"),
+    ])
+    .filter(|block| block.text != "除く")
+    .offset(Duration::from_secs(1))
+    .start_index(2);
+    let mut writer = SrtWriter::new(Vec::new());
+    let mut written = Vec::new();
+    write_after(&existing, builder, &mut writer, |block| {
+        written.push((block.index, block.text.clone()))
+    })
+    .unwrap();
+
+    assert_eq!(
+        "1\n00:00:00,000 --> 00:00:01,000\n前回\n\n2\n00:00:01,000 --> 00:00:03,000\n今回",
+        String::from_utf8(writer.into_inner()).unwrap()
+    );
+    // 飛ばしたブロックと--start-indexの分を振り直した番号で渡す
+    assert_eq!(vec![(2, "今回".to_string())], written);
+}
+
+#[test]
+fn test_clip_position() {
+    use voicepeak_srt_core::MemorySource;
+
+    let mut source = MemorySource::new();
+    for (name, text) in [("000-a", "一つ目"), ("001-a", " "), ("002-a", "三つ目")] {
+        source.insert(format!("{}.wav", name), Vec::new());
+        source.insert(format!("{}.txt", name), text.as_bytes().to_vec());
+    }
+    let clips = Rc::new(RefCell::new(Vec::new()));
+    let positions = Rc::new(RefCell::new(Vec::new()));
+    let blocks = SubtitleBuilder::from_source(source)
+        .duration_provider(|_: &dyn voicepeak_srt_core::InputSource, _: &Path| {
+            Ok(Duration::from_secs(1))
+        })
+        .empty_text(EmptyTextPolicy::Skip)
+        .on_warning(|_| {})
+        .on_clip({
+            let clips = Rc::clone(&clips);
+            move |clip| clips.borrow_mut().push(clip)
+        })
+        .map({
+            let clips = Rc::clone(&clips);
+            let positions = Rc::clone(&positions);
+            move |block| {
+                positions
+                    .borrow_mut()
+                    .push(clip_position(&clips.borrow(), block))
+            }
+        })
+        .build()
+        .unwrap();
+
+    // 空のテキストで飛ばした音声も数える
+    assert_eq!(2, blocks[1].index);
+    assert_eq!(vec![Some(0), Some(2)], *positions.borrow());
+}
@@ -0,0 +1,158 @@
+//! 音声と字幕を確認するHTMLの書き出し
+//!
+//! つなげた音声をdata URIで埋め込むため、HTMLのファイル1つだけで再生できる。
+
+use std::{fs, io::Write, path::Path, time::Duration};
+
+use voicepeak_srt_core::{format_srt_time, OutputFile, SrtBlock};
+
+/// 字幕の一覧と音声のプレーヤーを並べたHTMLを書き出す(ブロックを押すとその時刻から再生する)
+pub fn write_html_preview(path: &Path, audio: &Path, cues: &[SrtBlock]) -> crate::CliResult {
+    let data =
+        fs::read(audio).map_err(|e| format!("音声を読み込めません({}): {}", audio.display(), e))?;
+    let title = audio
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let html = render(&title, mime_type(audio), &data, cues);
+
+    let mut file = OutputFile::create(path)?;
+    file.write_all(html.as_bytes())?;
+    file.commit()?;
+    eprintln!("プレビューを書き出しました: {}", path.display());
+
+    Ok(())
+}
+
+/// 拡張子から音声のMIMEタイプを決める(形式はつなげた音声と同じ)
+fn mime_type(audio: &Path) -> &'static str {
+    let ext = audio
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    match ext.as_deref() {
+        Some("mp3") => "audio/mpeg",
+        Some("opus" | "ogg") => "audio/ogg",
+        Some("m4a" | "mp4") => "audio/mp4",
+        Some("aac") => "audio/aac",
+        _ => "audio/wav",
+    }
+}
+
+fn render(title: &str, mime: &str, audio: &[u8], cues: &[SrtBlock]) -> String {
+    let rows: String = cues
+        .iter()
+        .map(|cue| {
+            // 字幕と同じくミリ秒未満は切り捨てる
+            let seconds = |time: Duration| time.as_millis() as f64 / 1000.;
+            format!(
+                "<tr data-start=\"{:.3}\" data-end=\"{:.3}\"><td>{}</td><td>{} → {}</td><td>{}</td></tr>\n",
+                seconds(cue.start),
+                seconds(cue.end),
+                cue.index,
+                format_srt_time(cue.start),
+                format_srt_time(cue.end),
+                escape_html(&cue.stacked_text()).replace('\n', "<br>")
+            )
+        })
+        .collect();
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"ja\">
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 0; }}
+audio {{ position: sticky; top: 0; width: 100%; background: #fff; }}
+table {{ border-collapse: collapse; width: 100%; }}
+td {{ padding: 4px 8px; border-bottom: 1px solid #ddd; vertical-align: top; }}
+td:nth-child(-n+2) {{ white-space: nowrap; color: #666; font-family: monospace; }}
+tr {{ cursor: pointer; }}
+tr.current {{ background: #ffe9a8; }}
+</style>
+</head>
+<body>
+<audio id=\"audio\" controls src=\"data:{mime};base64,{audio}\"></audio>
+<table>
+{rows}</table>
+<script>
+const audio = document.getElementById(\"audio\");
+const rows = Array.from(document.querySelectorAll(\"tr\"));
+for (const row of rows) {{
+  row.addEventListener(\"click\", () => {{
+    audio.currentTime = Number(row.dataset.start);
+    audio.play();
+  }});
+}}
+audio.addEventListener(\"timeupdate\", () => {{
+  for (const row of rows) {{
+    const current = Number(row.dataset.start) <= audio.currentTime && audio.currentTime < Number(row.dataset.end);
+    row.classList.toggle(\"current\", current);
+  }}
+}});
+</script>
+</body>
+</html>
+",
+        title = escape_html(title),
+        audio = base64(audio),
+    )
+}
+
+/// HTMLの特殊文字をエスケープする
+fn escape_html(text: &str) -> String {
+    text.chars().fold(String::new(), |mut out, c| {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+        out
+    })
+}
+
+/// Base64で符号化する
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => out.push(TABLE[(n >> (18 - i * 6)) as usize & 0x3F] as char),
+                false => out.push('='),
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn test_base64() {
+    assert_eq!("", base64(b""));
+    assert_eq!("Zg==", base64(b"f"));
+    assert_eq!("Zm8=", base64(b"fo"));
+    assert_eq!("Zm9vYmFy", base64(b"foobar"));
+}
+
+#[test]
+fn test_render() {
+    let cue = SrtBlock::new(
+        1,
+        Duration::from_millis(500),
+        Duration::from_millis(1500),
+        "<砂塵>\n舞う",
+    );
+    let html = render("voice", "audio/wav", b"RIFF", &[cue]);
+
+    assert!(html.contains("src=\"data:audio/wav;base64,UklGRg==\""));
+    assert!(html.contains("<tr data-start=\"0.500\" data-end=\"1.500\">"));
+    assert!(html.contains("<td>&lt;砂塵&gt;<br>舞う</td>"));
+}
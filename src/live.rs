@@ -0,0 +1,164 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use clap::Args;
+use voicepeak_srt_core::{parse, SrtBlock};
+
+/// 1日の長さ
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 再生に合わせて今の字幕をテキストファイルに書き込む(OBSのテキストソースで読み込む)
+#[derive(Args, Debug)]
+pub struct LiveArgs {
+    /// 表示する字幕(srt・vtt)
+    subtitles: PathBuf,
+    /// 今の字幕を書き込むテキストファイル
+    #[arg(short, long, default_value = "./caption.txt")]
+    output_path: PathBuf,
+    /// 再生を始める時刻(HH:MM・HH:MM:SS、過ぎていれば途中から始める)。なければEnterで始める
+    #[arg(long, value_parser = parse_clock)]
+    start_at: Option<Duration>,
+    /// --start-atの時刻のUTCからの時差
+    #[arg(long, default_value = "+09:00", value_parser = parse_utc_offset, allow_hyphen_values = true)]
+    utc_offset: i64,
+}
+
+pub fn run(args: &LiveArgs) -> crate::CliResult {
+    let cues = parse::read_subtitle_file(&args.subtitles)?;
+    let length = cues.iter().map(|cue| cue.end).max().unwrap_or_default();
+    write_caption(&args.output_path, "")?;
+
+    // 始める時刻からどれだけ過ぎているか
+    let elapsed = match args.start_at {
+        Some(start_at) => {
+            let now = now_of_day(args.utc_offset);
+            if start_at > now {
+                eprintln!("{}秒後に始めます", (start_at - now).as_secs());
+                thread::sleep(start_at - now);
+                Duration::ZERO
+            } else if now - start_at < length {
+                now - start_at
+            } else {
+                return Err("--start-atの時刻から字幕の長さより過ぎています".into());
+            }
+        }
+        None => {
+            eprintln!("Enterで始めます");
+            io::stdin().read_line(&mut String::new())?;
+            Duration::ZERO
+        }
+    };
+    let started = Instant::now() - elapsed;
+
+    let mut current = None;
+    loop {
+        let elapsed = started.elapsed();
+        let cue = cue_at(&cues, elapsed);
+        if cue.map(|cue| cue.index) != current {
+            current = cue.map(|cue| cue.index);
+            write_caption(&args.output_path, cue.map_or("", |cue| &cue.text))?;
+        }
+        match next_change(&cues, elapsed) {
+            Some(at) => thread::sleep(at - elapsed),
+            None => break,
+        }
+    }
+    write_caption(&args.output_path, "")?;
+    Ok(())
+}
+
+/// その時点で表示する字幕
+fn cue_at(cues: &[SrtBlock], elapsed: Duration) -> Option<&SrtBlock> {
+    cues.iter()
+        .find(|cue| cue.start <= elapsed && elapsed < cue.end)
+}
+
+/// その時点より後で表示が変わる時点
+fn next_change(cues: &[SrtBlock], elapsed: Duration) -> Option<Duration> {
+    cues.iter()
+        .flat_map(|cue| [cue.start, cue.end])
+        .filter(|&at| at > elapsed)
+        .min()
+}
+
+/// OBSが書きかけを読まないように、別のファイルに書いてから置き換える
+fn write_caption(path: &Path, text: &str) -> io::Result<()> {
+    let part = path.with_extension("part");
+    fs::write(&part, text)?;
+    fs::rename(&part, path)
+}
+
+/// 時差を含めた今の時刻(0時からの時間)
+fn now_of_day(utc_offset: i64) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = (now.as_secs() as i64 + utc_offset).rem_euclid(DAY.as_secs() as i64);
+    Duration::from_secs(seconds as u64) + Duration::from_nanos(now.subsec_nanos() as u64)
+}
+
+/// HH:MMかHH:MM:SSの時刻を読む
+fn parse_clock(text: &str) -> Result<Duration, String> {
+    let parts: Vec<u64> = text
+        .split(':')
+        .map(|part| {
+            part.parse()
+                .map_err(|_| format!("時刻ではありません: {}", text))
+        })
+        .collect::<Result<_, _>>()?;
+    let (hours, minutes, seconds) = match parts[..] {
+        [hours, minutes] => (hours, minutes, 0),
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        _ => return Err(format!("HH:MMかHH:MM:SSで指定してください: {}", text)),
+    };
+    if hours >= 24 || minutes >= 60 || seconds >= 60 {
+        return Err(format!("時刻ではありません: {}", text));
+    }
+    Ok(Duration::from_secs(hours * 3600 + minutes * 60 + seconds))
+}
+
+/// +HH:MMか-HH:MMの時差を秒にする
+fn parse_utc_offset(text: &str) -> Result<i64, String> {
+    let (sign, rest) = match text.split_at_checked(1) {
+        Some(("+", rest)) => (1, rest),
+        Some(("-", rest)) => (-1, rest),
+        _ => return Err(format!("+09:00のように指定してください: {}", text)),
+    };
+    let clock = parse_clock(rest)?;
+    Ok(sign * clock.as_secs() as i64)
+}
+
+#[test]
+fn test_cue_at() {
+    let cues = [
+        SrtBlock::new(1, Duration::from_secs(1), Duration::from_secs(2), "a"),
+        SrtBlock::new(2, Duration::from_secs(3), Duration::from_secs(4), "b"),
+    ];
+    assert!(cue_at(&cues, Duration::ZERO).is_none());
+    assert_eq!(
+        cue_at(&cues, Duration::from_millis(1500)).unwrap().text,
+        "a"
+    );
+    assert!(cue_at(&cues, Duration::from_millis(2500)).is_none());
+    assert_eq!(
+        next_change(&cues, Duration::from_secs(2)),
+        Some(Duration::from_secs(3))
+    );
+    assert_eq!(next_change(&cues, Duration::from_secs(4)), None);
+}
+
+#[test]
+fn test_parse_clock() {
+    assert_eq!(
+        parse_clock("21:30"),
+        Ok(Duration::from_secs(21 * 3600 + 30 * 60))
+    );
+    assert_eq!(parse_clock("00:00:05"), Ok(Duration::from_secs(5)));
+    assert!(parse_clock("24:00").is_err());
+    assert_eq!(parse_utc_offset("+09:00"), Ok(9 * 3600));
+    assert_eq!(parse_utc_offset("-05:30"), Ok(-(5 * 3600 + 30 * 60)));
+}
@@ -1,235 +1,195 @@
-use std::{
-    fs::{self, File},
-    io::Write,
-    ops::Add,
-    path::Path,
-    time::Duration,
-};
-
-use clap::Parser;
-
+#[cfg(feature = "script")]
+mod script;
+#[cfg(feature = "serve")]
+mod serve;
+#[cfg(feature = "upload")]
+mod upload;
+
+mod align;
+mod audio;
+mod burn;
+mod clean;
+mod convert;
+mod crosscheck;
+mod diff;
+mod ffmpeg;
+mod ffprobe;
+mod generate;
+mod html_preview;
+mod live;
+mod manifest;
+mod mecab;
+mod merge;
+mod mux;
+mod presets;
+mod render_preview;
+mod resync;
+mod rpc;
+mod shift;
+mod split;
+mod synthesize;
+mod validate;
+mod watch;
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use voicepeak_srt_core::Error;
+
+/// サブコマンドの結果
+type CliResult = Result<(), Box<dyn std::error::Error>>;
+
+/// Voicepeakが書き出したwavとtxtから字幕を作成する
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
-    #[arg(short, long)]
-    input_path: String,
-    #[arg(short, long, default_value = "./subtitles.srt")]
-    output_path: String,
+#[command(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    generate: Option<generate::GenerateArgs>,
 }
 
-#[derive(Debug, PartialEq)]
-struct SrtBlock {
-    index: usize,
-    start_time_string: String,
-    end_time_string: String,
-    text: String,
+#[derive(Subcommand, Debug)]
+enum Command {
+    Generate(Box<generate::GenerateArgs>),
+    Validate(validate::ValidateArgs),
+    Manifest(manifest::ManifestArgs),
+    Align(align::AlignArgs),
+    Synthesize(synthesize::SynthesizeArgs),
+    Burn(burn::BurnArgs),
+    RenderPreview(render_preview::RenderPreviewArgs),
+    Mux(mux::MuxArgs),
+    Live(live::LiveArgs),
+    Presets(presets::PresetsArgs),
+    Crosscheck(crosscheck::CrosscheckArgs),
+    Merge(merge::MergeArgs),
+    Convert(convert::ConvertArgs),
+    Shift(shift::ShiftArgs),
+    Split(split::SplitArgs),
+    Resync(resync::ResyncArgs),
+    Diff(diff::DiffArgs),
+    Clean(clean::CleanArgs),
+    Watch(watch::WatchArgs),
+    Rpc(rpc::RpcArgs),
+    #[cfg(feature = "serve")]
+    Serve(serve::ServeArgs),
+    #[cfg(feature = "upload")]
+    Upload(upload::UploadArgs),
 }
 
-fn main() {
+fn main() -> ExitCode {
     // コマンドライン引数から音声とテキストが入ったパスを受け取る
-    let args = Args::parse();
-    let input_path = Path::new(&args.input_path);
-    let output_path = Path::new(&args.output_path);
-
-    // wavとtxtを取り出す
-    let files = extract_wav_and_txt(input_path);
-
-    // srtのブロック情報を作成する
-    let srt_blocks = make_srt_blocks(files);
-
-    // srtファイル作成
-    make_srt(srt_blocks, output_path);
-}
-
-fn extract_wav_and_txt(path: &Path) -> Vec<std::path::PathBuf> {
-    // パスが存在しなければ異常終了
-    // パスの中にwavまたはtxtが入っていなければ異常終了
-    let files: Vec<std::path::PathBuf> = fs::read_dir(path)
-        .expect("パスが存在しません")
-        .filter_map(Result::ok)
-        .filter(|entry| {
-            let path = entry.path();
-            path.is_file()
-                && match path.extension() {
-                    Some(ext) => ext == "wav" || ext == "txt",
-                    None => false,
-                }
-        })
-        .map(|entry| entry.path())
-        .collect();
-
-    let extensions: Vec<&str> = files
-        .iter()
-        .map(|p| p.extension().unwrap().to_str().unwrap())
-        .collect();
-
-    // パスの中にwavが入っていなければ異常終了
-    let n_wav = extensions
-        .iter()
-        .filter(|ext| **ext == "wav")
-        .collect::<Vec<&&str>>()
-        .len();
-    if n_wav == 0 {
-        panic!("wavが存在しません");
-    };
-
-    // パスの中にtxtが入っていなければ異常終了
-    let n_txt = extensions
-        .iter()
-        .filter(|ext| **ext == "txt")
-        .collect::<Vec<&&str>>()
-        .len();
-    if n_txt == 0 {
-        panic!("txtが存在しません");
+    let cli = Cli::parse();
+
+    // サブコマンドがなければ字幕を作成する
+    let result = match (&cli.command, &cli.generate) {
+        (Some(Command::Generate(args)), _) => generate::run(args),
+        (None, Some(args)) => generate::run(args),
+        (Some(Command::Validate(args)), _) => validate::run(args),
+        (Some(Command::Manifest(args)), _) => manifest::run(args),
+        (Some(Command::Align(args)), _) => align::run(args),
+        (Some(Command::Synthesize(args)), _) => synthesize::run(args),
+        (Some(Command::Burn(args)), _) => burn::run(args),
+        (Some(Command::RenderPreview(args)), _) => render_preview::run(args),
+        (Some(Command::Mux(args)), _) => mux::run(args),
+        (Some(Command::Live(args)), _) => live::run(args),
+        (Some(Command::Presets(args)), _) => presets::run(args),
+        (Some(Command::Crosscheck(args)), _) => crosscheck::run(args),
+        (Some(Command::Merge(args)), _) => merge::run(args),
+        (Some(Command::Convert(args)), _) => convert::run(args),
+        (Some(Command::Shift(args)), _) => shift::run(args),
+        (Some(Command::Split(args)), _) => split::run(args),
+        (Some(Command::Resync(args)), _) => resync::run(args),
+        (Some(Command::Diff(args)), _) => diff::run(args),
+        (Some(Command::Clean(args)), _) => clean::run(args),
+        (Some(Command::Watch(args)), _) => watch::run(args),
+        (Some(Command::Rpc(args)), _) => rpc::run(args),
+        #[cfg(feature = "serve")]
+        (Some(Command::Serve(args)), _) => serve::run(args),
+        #[cfg(feature = "upload")]
+        (Some(Command::Upload(args)), _) => upload::run(args),
+        (None, None) => unreachable!(),
     };
 
-    // wavとtxtが同数でなければ異常終了
-    if n_wav != n_txt {
-        panic!("wavとtxtの数が合いません");
-    }
-
-    files
-}
-
-fn make_srt_blocks(files: Vec<std::path::PathBuf>) -> Vec<SrtBlock> {
-    let mut blocks: Vec<SrtBlock> = Vec::new();
-    let mut total_time = Duration::from_secs_f64(0.);
-
-    // 連番を回しつつwavとtxtから情報を抜き出す
-    for i in 0.. {
-        // ファイル検索用連番取得
-        let seq_char = format!("{:03}", i);
-
-        // 対象ブロックのファイル抽出
-        let target_files: Vec<&std::path::PathBuf> = files
-            .iter()
-            .filter(|f| {
-                f.file_name()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .starts_with(&seq_char)
-            })
-            .collect();
-
-        // ファイルを取得できなくなった時点で終了
-        if target_files.len() == 0 {
-            break;
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            report(e.as_ref());
+            ExitCode::FAILURE
         }
-
-        // wavから開始と終了時間取得
-        let wav_path = target_files
-            .iter()
-            .find(|p| p.extension().unwrap() == "wav")
-            .unwrap();
-        let mut inp_file = File::open(Path::new(wav_path)).unwrap();
-        let (header, data) = wav::read(&mut inp_file).unwrap();
-
-        let start_time_string = format!(
-            "{:02}:{:02}:{:02},{:03}",
-            total_time.as_secs() / 3600,
-            (total_time.as_secs() % 3600) / 60,
-            total_time.as_secs() % 60,
-            total_time.subsec_millis()
-        );
-
-        let wav_duration = Duration::from_secs_f64(
-            data.try_into_sixteen().unwrap().len() as f64 / header.sampling_rate as f64,
-        );
-        let end_time_duration = total_time.add(wav_duration);
-        let end_time_string = format!(
-            "{:02}:{:02}:{:02},{:03}",
-            end_time_duration.as_secs() / 3600,
-            (end_time_duration.as_secs() % 3600) / 60,
-            end_time_duration.as_secs() % 60,
-            end_time_duration.subsec_millis()
-        );
-
-        total_time = total_time.add(wav_duration);
-
-        // txtからテキスト取得
-        let txt_path = target_files
-            .iter()
-            .find(|p| p.extension().unwrap() == "txt")
-            .unwrap();
-        let text = fs::read_to_string(txt_path).unwrap();
-
-        blocks.push(SrtBlock {
-            index: i + 1,
-            start_time_string,
-            end_time_string,
-            text,
-        });
     }
-
-    blocks
 }
 
-fn make_srt(srt_blocks: Vec<SrtBlock>, path: &Path) {
-    let mut output_srt = String::new();
-
-    // 書き出し用文字列作成
-    for block in srt_blocks {
-        output_srt.push_str(&format!(
-            "{}\n{} --> {}\n{}\n\n",
-            block.index, block.start_time_string, block.end_time_string, block.text
-        ));
+/// エラーの内容と対処方法を表示する
+fn report(e: &(dyn std::error::Error + 'static)) {
+    eprintln!("エラー: {}", e);
+    let mut source = e.source();
+    while let Some(s) = source {
+        eprintln!("  原因: {}", s);
+        source = s.source();
+    }
+    if let Some(hint) = e.downcast_ref::<Error>().and_then(hint) {
+        eprintln!("ヒント: {}", hint);
     }
-
-    // 書き出し
-    let mut file = File::create(path).unwrap();
-    let _ = file.write_all(output_srt.trim_end().as_bytes());
-}
-
-#[test]
-fn test_extract_wav_and_txt_ok() {
-    let path = Path::new("./voice");
-    extract_wav_and_txt(path);
-}
-
-#[test]
-#[should_panic(expected = "パスが存在しません")]
-fn test_extract_wav_and_txt_no_exits_path() {
-    let path = Path::new("no/exits/path/");
-    let _ = extract_wav_and_txt(path);
-}
-
-#[test]
-#[should_panic(expected = "wavが存在しません")]
-fn test_extract_wav_and_txt_no_wav() {
-    let path = Path::new("test_resource/no_wav");
-    extract_wav_and_txt(path);
-}
-
-#[test]
-#[should_panic(expected = "txtが存在しません")]
-fn test_extract_wav_and_txt_no_txt() {
-    let path = Path::new("test_resource/no_txt");
-    extract_wav_and_txt(path);
-}
-
-#[test]
-#[should_panic(expected = "wavとtxtの数が合いません")]
-fn test_extract_wav_and_txt_no_match() {
-    let path = Path::new("test_resource/not_match");
-    extract_wav_and_txt(path);
 }
 
-#[test]
-fn test_make_srt_blocks_ok() {
-    let path = Path::new("./voice");
-    let files = extract_wav_and_txt(path);
-    let srt_blocks = make_srt_blocks(files);
-
-    let correct = vec!(
-        SrtBlock { index: 1, start_time_string: "00:00:00,000".to_string(), end_time_string: "00:00:07,288".to_string(), text: "時は第三次中東戦争と第四次中東戦争の間の1973年2月初旬".to_string() },
-        SrtBlock { index: 2, start_time_string: "00:00:07,288".to_string(), end_time_string: "00:00:13,722".to_string(), text: "エジプトを盟主とする中東アラブ諸国とイスラエルは、とてもピリピリした状態にありました".to_string() },
-        SrtBlock { index: 3, start_time_string: "00:00:13,722".to_string(), end_time_string: "00:00:22,488".to_string(), text: "砂塵舞うベンガジ空港を飛び立ち、リビアン・アラブ航空114便は地中海を渡ってエジプトの首都カイロへ向かいます".to_string() },
-        SrtBlock { index: 4, start_time_string: "00:00:22,488".to_string(), end_time_string: "00:00:31,547".to_string(), text: "コックピットにはフランス人機長、その右隣にフランス人航空機関士、後ろにはリビア人副操縦士が乗っていました".to_string() },
-    );
-
-    assert_eq!(correct[0], srt_blocks[0]);
-    assert_eq!(correct[1], srt_blocks[1]);
-    assert_eq!(correct[2], srt_blocks[2]);
-    assert_eq!(correct[3], srt_blocks[3]);
+/// エラーごとの対処方法
+fn hint(e: &Error) -> Option<&'static str> {
+    match e {
+        Error::PathNotFound(_) => {
+            Some("--input-pathにVoicepeakの書き出し先フォルダを指定してください")
+        }
+        Error::MissingWav => {
+            Some("Voicepeakの書き出し設定で音声(wav)を出力しているか確認してください")
+        }
+        Error::MissingTxt => {
+            Some("Voicepeakの書き出し設定で「テキストファイルを出力」を有効にしてください")
+        }
+        Error::CountMismatch { .. }
+        | Error::UnpairedSequence { .. }
+        | Error::UnpairedSequences { .. } => {
+            Some("同じ連番のwavとtxtが1つずつあるか確認してください")
+        }
+        Error::SequenceGap { .. } => {
+            Some("抜けている連番のファイルを書き出し直すか、連番を振り直してください")
+        }
+        Error::WavDecode { .. } => Some(
+            "wavが壊れていないか、書き出し直して確認してください(16bit以外の形式は--allow-ffprobeで長さを取得できます)",
+        ),
+        Error::TxtEncoding { .. } => {
+            Some("txtをUTF-8で保存し直すか、--presetで書き出したソフトを指定してください")
+        }
+        Error::DuplicateSequence { .. } => Some(
+            "古いファイルを削除するか、--newest-duplicateで最も新しいファイルを使ってください",
+        ),
+        Error::EmptyText { .. } => Some(
+            "テキストを入力して書き出し直すか、--empty-text skipで空のブロックを飛ばしてください",
+        ),
+        Error::NotNormalized { .. } => Some("--nfcを指定してテキストを正規化してください"),
+        Error::ImplausibleDuration { .. } => {
+            Some("wavとtxtの組み合わせが正しいか、合成後にtxtを編集していないか確認してください")
+        }
+        Error::SilentClip { .. } => Some(
+            "Voicepeakで書き出しに失敗していないか確認し、その連番の音声を書き出し直してください",
+        ),
+        Error::CueCountMismatch { .. } => {
+            Some("字幕のブロックを結合・分割していないか、音声のフォルダーが正しいか確認してください")
+        }
+        Error::AudioFormatMismatch { .. } => {
+            Some("すべてのwavをモノラルかステレオのどちらかに揃えて書き出してください")
+        }
+        Error::InvalidPattern { .. } => Some(
+            "--patternには(?P<seq>\\d+)のように名前付きキャプチャseqを含む正規表現を指定してください",
+        ),
+        Error::InvalidPreset { .. } => {
+            Some("--preset-fileには[{\"name\": …, \"pattern\": …}]の形式のJSONを指定してください")
+        }
+        _ => None,
+    }
 }
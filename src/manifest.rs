@@ -0,0 +1,23 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use voicepeak_srt_core::{DirSource, Manifest, OutputFile, WavDurationProvider};
+
+/// 入力ファイルのサイズ・ハッシュ・長さをJSONに書き出す
+#[derive(Args, Debug)]
+pub struct ManifestArgs {
+    #[arg(short, long)]
+    input_path: PathBuf,
+    #[arg(short, long, default_value = "./manifest.json")]
+    output_path: PathBuf,
+}
+
+pub fn run(args: &ManifestArgs) -> crate::CliResult {
+    let manifest = Manifest::from_source(&DirSource::new(&args.input_path), &WavDurationProvider)?;
+
+    let mut file = OutputFile::create(&args.output_path)?;
+    manifest.write_json(&mut file)?;
+    file.commit()?;
+
+    Ok(())
+}
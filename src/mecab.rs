@@ -0,0 +1,83 @@
+//! MeCabによる形態素解析
+
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+use voicepeak_srt_core::furigana::{self, Morpheme};
+
+/// `mecab`を1回だけ起動してすべてのテキストを単語に分け、テキストごとに読みを返す
+pub fn analyze_all(mecab: &str, texts: &[&str]) -> io::Result<Vec<Vec<Morpheme>>> {
+    let mut child = Command::new(mecab)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                io::Error::new(e.kind(), format!("MeCabが見つかりません({})", mecab))
+            }
+            _ => e,
+        })?;
+    // 最後の行にも改行を付けないとMeCabは解析しない
+    let input: String = texts
+        .iter()
+        .flat_map(|text| text.split('\n'))
+        .map(|line| format!("{}\n", line.trim_end_matches('\r')))
+        .collect();
+    // 出力を読みながら書き込まないと、パイプが詰まって止まる
+    let mut stdin = child.stdin.take().expect("標準入力はパイプにしている");
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let output = child.wait_with_output()?;
+    writer.join().expect("書き込みのスレッドは終わっている")?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "MeCabで解析できません: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    split_output(&String::from_utf8_lossy(&output.stdout), texts)
+        .ok_or_else(|| io::Error::other("MeCabの出力の行数がテキストと合いません"))
+}
+
+/// MeCabの出力を、入力の1行ごとのEOSで区切ってテキストごとに分ける
+fn split_output(output: &str, texts: &[&str]) -> Option<Vec<Vec<Morpheme>>> {
+    let mut sentences = Vec::new();
+    let mut sentence = String::new();
+    for line in output.lines() {
+        sentence.push_str(line);
+        sentence.push('\n');
+        if line == "EOS" {
+            sentences.push(std::mem::take(&mut sentence));
+        }
+    }
+    let lines: usize = texts.iter().map(|text| text.split('\n').count()).sum();
+    if sentences.len() != lines {
+        return None;
+    }
+
+    let mut sentences = sentences.into_iter();
+    let morphemes = texts
+        .iter()
+        .map(|text| {
+            let output: String = sentences.by_ref().take(text.split('\n').count()).collect();
+            furigana::parse_mecab(&output)
+        })
+        .collect();
+    Some(morphemes)
+}
+
+#[test]
+fn test_split_output() {
+    let output = "砂塵\t名詞,一般,*,*,*,*,砂塵,サジン,サジン\nEOS\n舞う\t動詞,自立,*,*,五段・ワ行促音便,基本形,舞う,マウ,マウ\nEOS\nEOS\n";
+    let morphemes = split_output(output, &["砂塵\n舞う", ""]).unwrap();
+
+    assert_eq!(2, morphemes.len());
+    let surfaces: Vec<&str> = morphemes[0].iter().map(|m| m.surface.as_str()).collect();
+    assert_eq!(vec!["砂塵", "\n", "舞う"], surfaces);
+    assert!(morphemes[1].is_empty());
+    assert_eq!(None, split_output(output, &["砂塵"]));
+}
@@ -0,0 +1,108 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::Args;
+use voicepeak_srt_core::{parse, write_subtitle_file, SrtBlock};
+
+/// 複数の字幕ファイルをつなげて1つにする(番号は振り直す)
+#[derive(Args, Debug)]
+pub struct MergeArgs {
+    /// つなげる字幕ファイル(srtまたはvtt)を並べる順に指定する
+    #[arg(required = true)]
+    parts: Vec<PathBuf>,
+    /// 書き出す字幕(拡張子がvttならWebVTT、それ以外はsrt)
+    #[arg(short, long, default_value = "./merged.srt")]
+    output_path: PathBuf,
+    /// 各ファイルの開始時刻(ファイルと同じ数だけ指定する。省略時は前のファイルの最後の字幕の後に続ける)
+    #[arg(long, value_delimiter = ',', value_parser = parse_time)]
+    offsets: Vec<Duration>,
+    /// 開始時刻を省略したとき、前のファイルとの間に入れる時間(ミリ秒)
+    #[arg(long, default_value_t = 0)]
+    gap_ms: u64,
+}
+
+pub fn run(args: &MergeArgs) -> crate::CliResult {
+    if !args.offsets.is_empty() && args.offsets.len() != args.parts.len() {
+        return Err(format!(
+            "--offsetsの数({})がファイルの数({})と違います",
+            args.offsets.len(),
+            args.parts.len()
+        )
+        .into());
+    }
+
+    let parts = args
+        .parts
+        .iter()
+        .map(|path| parse::read_subtitle_file(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let offsets = match args.offsets.is_empty() {
+        true => auto_offsets(&parts, Duration::from_millis(args.gap_ms)),
+        false => args.offsets.clone(),
+    };
+    let merged = merge(parts, &offsets);
+
+    eprintln!(
+        "{}個のファイルから{}ブロックをつなげました",
+        args.parts.len(),
+        merged.len()
+    );
+    write_subtitle_file(merged, &args.output_path)?;
+    Ok(())
+}
+
+/// `HH:MM:SS.mmm`などの時刻を読み込む
+pub fn parse_time(text: &str) -> Result<Duration, String> {
+    parse::parse_timestamp(text).ok_or_else(|| format!("時刻の形式が正しくありません: {}", text))
+}
+
+/// 各ファイルを前のファイルの最後の字幕の終わりから続ける開始時刻
+fn auto_offsets(parts: &[Vec<SrtBlock>], gap: Duration) -> Vec<Duration> {
+    let mut offsets = Vec::with_capacity(parts.len());
+    let mut offset = Duration::ZERO;
+    for part in parts {
+        offsets.push(offset);
+        if let Some(last) = part.iter().map(|block| block.end).max() {
+            offset += last + gap;
+        }
+    }
+    offsets
+}
+
+/// 各ファイルの時刻をずらしてつなげ、番号を1から振り直す
+fn merge(parts: Vec<Vec<SrtBlock>>, offsets: &[Duration]) -> Vec<SrtBlock> {
+    parts
+        .into_iter()
+        .zip(offsets)
+        .flat_map(|(part, &offset)| {
+            part.into_iter().map(move |mut block| {
+                voicepeak_srt_core::transform::offset(&mut block, offset);
+                block
+            })
+        })
+        .enumerate()
+        .map(|(i, mut block)| {
+            block.index = i + 1;
+            block
+        })
+        .collect()
+}
+
+#[test]
+fn test_merge_auto_offsets() {
+    let part = |end: u64| {
+        vec![SrtBlock::new(
+            1,
+            Duration::from_secs(1),
+            Duration::from_secs(end),
+            "a",
+        )]
+    };
+    let parts = vec![part(3), part(2)];
+    let offsets = auto_offsets(&parts, Duration::from_secs(1));
+    assert_eq!(vec![Duration::ZERO, Duration::from_secs(4)], offsets);
+
+    let merged = merge(parts, &offsets);
+    assert_eq!(2, merged[1].index);
+    assert_eq!(Duration::from_secs(5), merged[1].start);
+    assert_eq!(Duration::from_secs(6), merged[1].end);
+}
@@ -0,0 +1,167 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use clap::Args;
+
+use crate::ffmpeg;
+
+/// ffmpegで字幕(と音声)をMP4・MKVに入れる
+#[derive(Args, Debug)]
+pub struct MuxArgs {
+    /// 元の動画(映像と音声はそのままコピーする)
+    video: PathBuf,
+    /// 入れる字幕(srt・vtt・ass)
+    #[arg(short, long, default_value = "./subtitles.srt")]
+    subtitles: PathBuf,
+    /// 追加する音声(--concat-audioで書き出した音声など)
+    #[arg(short, long)]
+    audio: Option<PathBuf>,
+    /// 元の動画の音声を入れず、--audioの音声だけにする
+    #[arg(long, requires = "audio")]
+    replace_audio: bool,
+    /// 書き出す動画(拡張子がmkvならMatroska、それ以外はMP4)
+    #[arg(short, long, default_value = "./muxed.mp4")]
+    output_path: PathBuf,
+    /// 字幕と追加する音声の言語(ISO 639-2、例: jpn・eng)
+    #[arg(long, default_value = "jpn")]
+    language: String,
+    /// 字幕トラックの名前
+    #[arg(long)]
+    title: Option<String>,
+    /// ffmpegのパス
+    #[arg(long, default_value = "ffmpeg")]
+    ffmpeg: String,
+}
+
+pub fn run(args: &MuxArgs) -> crate::CliResult {
+    for path in [Some(&args.subtitles), args.audio.as_ref()]
+        .into_iter()
+        .flatten()
+    {
+        if !path.exists() {
+            return Err(format!("ファイルがありません: {}", path.display()).into());
+        }
+    }
+
+    let mkv = is_mkv(&args.output_path);
+    let mut command = Command::new(&args.ffmpeg);
+    command
+        .args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(&args.video)
+        .arg("-i")
+        .arg(&args.subtitles);
+    if let Some(audio) = &args.audio {
+        command.arg("-i").arg(audio);
+    }
+
+    command.args(["-map", "0:v"]);
+    command.args(audio_args(
+        args.audio.is_some(),
+        args.replace_audio,
+        mkv,
+        &args.language,
+    ));
+    command.args(["-map", "1:s", "-c:v", "copy"]);
+    command
+        .arg("-c:s")
+        .arg(subtitle_codec(&args.subtitles, mkv))
+        .arg("-metadata:s:s:0")
+        .arg(format!("language={}", args.language));
+    if let Some(title) = &args.title {
+        command
+            .arg("-metadata:s:s:0")
+            .arg(format!("title={}", title));
+    }
+    command.arg(&args.output_path);
+
+    ffmpeg::run(&args.ffmpeg, &mut command, "多重化")
+}
+
+/// 音声トラックの割り当てとコーデック、追加する音声の言語の引数
+///
+/// 元の動画の音声トラックの数は分からないため、追加する音声を最初の音声トラックにする
+fn audio_args(added: bool, replace_audio: bool, mkv: bool, language: &str) -> Vec<String> {
+    let mut args: Vec<String> = Vec::new();
+    if added {
+        args.extend(["-map".into(), "2:a".into()]);
+    }
+    if !replace_audio {
+        args.extend(["-map".into(), "0:a?".into()]);
+    }
+    // 元の動画の音声はコピーし、MP4はwavを入れられないため追加する音声だけAACにする
+    args.extend(["-c:a".into(), "copy".into()]);
+    if added {
+        if !mkv {
+            args.extend(["-c:a:0", "aac", "-b:a:0", "192k"].map(String::from));
+        }
+        args.extend(["-metadata:s:a:0".into(), format!("language={}", language)]);
+    }
+    args
+}
+
+/// 書き出す動画がMatroskaか
+fn is_mkv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mkv"))
+}
+
+/// 出力形式に入れられる字幕のコーデック
+fn subtitle_codec(subtitles: &Path, mkv: bool) -> &'static str {
+    let ext = subtitles
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    match (mkv, ext.as_deref()) {
+        (false, _) => "mov_text",
+        (true, Some("ass")) => "ass",
+        (true, Some("vtt")) => "webvtt",
+        (true, _) => "srt",
+    }
+}
+
+#[test]
+fn test_subtitle_codec() {
+    assert_eq!("mov_text", subtitle_codec(Path::new("a.ass"), false));
+    assert_eq!("ass", subtitle_codec(Path::new("a.ASS"), true));
+    assert_eq!(
+        "srt",
+        subtitle_codec(Path::new("a.srt"), is_mkv(Path::new("out.mkv")))
+    );
+}
+
+#[test]
+fn test_audio_args() {
+    assert_eq!(
+        vec![
+            "-map",
+            "2:a",
+            "-map",
+            "0:a?",
+            "-c:a",
+            "copy",
+            "-c:a:0",
+            "aac",
+            "-b:a:0",
+            "192k",
+            "-metadata:s:a:0",
+            "language=eng"
+        ],
+        audio_args(true, false, false, "eng")
+    );
+    assert_eq!(
+        vec![
+            "-map",
+            "2:a",
+            "-c:a",
+            "copy",
+            "-metadata:s:a:0",
+            "language=jpn"
+        ],
+        audio_args(true, true, true, "jpn")
+    );
+    assert_eq!(
+        vec!["-map", "0:a?", "-c:a", "copy"],
+        audio_args(false, false, false, "jpn")
+    );
+}
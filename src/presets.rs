@@ -0,0 +1,79 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Subcommand};
+use voicepeak_srt_core::preset::InputPreset;
+
+/// 入力のプリセットを扱う
+#[derive(Args, Debug)]
+pub struct PresetsArgs {
+    #[command(subcommand)]
+    command: PresetsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum PresetsCommand {
+    /// 使えるプリセットの一覧を表示する
+    List {
+        /// 独自のプリセットを書いたJSON
+        #[arg(long)]
+        preset_file: Option<PathBuf>,
+    },
+}
+
+pub fn run(args: &PresetsArgs) -> crate::CliResult {
+    match &args.command {
+        PresetsCommand::List { preset_file } => {
+            for preset in load(preset_file.as_deref())? {
+                println!("{}\t{}", preset.name, preset.description);
+                println!("\t{}", preset.pattern.as_str());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 独自のプリセット(あれば)と組み込みのプリセットを読み込む
+///
+/// 同じ名前があれば独自のプリセットを優先する
+pub fn load(preset_file: Option<&Path>) -> Result<Vec<InputPreset>, Box<dyn std::error::Error>> {
+    let mut presets = match preset_file {
+        Some(path) => {
+            let file = File::open(path).map_err(|e| {
+                format!(
+                    "プリセットの設定を読み込めません({}): {}",
+                    path.display(),
+                    e
+                )
+            })?;
+            InputPreset::from_json(file)?
+        }
+        None => Vec::new(),
+    };
+    presets.extend(InputPreset::builtin());
+    Ok(presets)
+}
+
+/// 名前でプリセットを探す
+pub fn find(
+    name: &str,
+    preset_file: Option<&Path>,
+) -> Result<InputPreset, Box<dyn std::error::Error>> {
+    let presets = load(preset_file)?;
+    match InputPreset::find(&presets, name) {
+        Some(preset) => Ok(preset.clone()),
+        None => Err(format!(
+            "プリセットがありません: {}(`presets list`で一覧を表示できます)",
+            name
+        )
+        .into()),
+    }
+}
+
+#[test]
+fn test_find() {
+    assert_eq!("voicevox", find("voicevox", None).unwrap().name);
+    assert!(find("unknown", None).is_err());
+}
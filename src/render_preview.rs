@@ -0,0 +1,94 @@
+use std::{fs, path::PathBuf, process::Command, time::Duration};
+
+use clap::Args;
+use voicepeak_srt_core::parse;
+
+use crate::{burn, ffmpeg};
+
+/// 字幕を1ブロックずつ画像にし、はみ出す行がないかを確かめる
+///
+/// ffmpegで無地の画面に字幕を焼き込み、ブロックの中央の時刻の1フレームをpngで書き出す
+#[derive(Args, Debug)]
+pub struct RenderPreviewArgs {
+    /// 画像にする字幕(srt・vtt・ass、assならスタイルもそのまま使う)
+    subtitles: PathBuf,
+    /// 画像を書き出すフォルダー(ブロックの番号を名前にする)
+    #[arg(short, long, default_value = "./preview")]
+    output_dir: PathBuf,
+    /// srt・vttに適用するASSのスタイル(例: "FontName=Noto Sans JP,FontSize=24,MarginV=40")
+    #[arg(long)]
+    style: Option<String>,
+    /// 画像の大きさ
+    #[arg(long, default_value = "1920x1080")]
+    size: String,
+    /// 背景の色
+    #[arg(long, default_value = "black")]
+    background: String,
+    /// 画面の端からこの割合(%)の内側にセーフエリアの枠を描く(0なら描かない)
+    #[arg(long, default_value_t = 5.)]
+    safe_margin: f64,
+    /// ffmpegのパス
+    #[arg(long, default_value = "ffmpeg")]
+    ffmpeg: String,
+}
+
+pub fn run(args: &RenderPreviewArgs) -> crate::CliResult {
+    if !(0. ..50.).contains(&args.safe_margin) {
+        return Err("--safe-marginは0以上50未満にしてください".into());
+    }
+    let blocks = parse::read_subtitle_file(&args.subtitles)?;
+    fs::create_dir_all(&args.output_dir)?;
+
+    let subtitles = burn::subtitle_filter(&args.subtitles, args.style.as_deref());
+    for block in &blocks {
+        let output_path = args.output_dir.join(format!("{:04}.png", block.index));
+        let mut command = Command::new(&args.ffmpeg);
+        command
+            .args(["-hide_banner", "-loglevel", "error", "-y"])
+            .args(["-f", "lavfi", "-i"])
+            .arg(format!("color=c={}:s={}:d=1", args.background, args.size))
+            .arg("-vf")
+            .arg(preview_filter(
+                &subtitles,
+                block.start + block.end.saturating_sub(block.start) / 2,
+                args.safe_margin,
+            ))
+            .args(["-frames:v", "1"])
+            .arg(&output_path);
+        ffmpeg::run(&args.ffmpeg, &mut command, "画像の書き出し")?;
+    }
+    eprintln!(
+        "{}枚の画像を書き出しました: {}",
+        blocks.len(),
+        args.output_dir.display()
+    );
+
+    Ok(())
+}
+
+/// 1フレームだけの無地の映像を`time`の時刻にずらし、セーフエリアの枠と字幕を描くフィルター
+fn preview_filter(subtitles: &str, time: Duration, safe_margin: f64) -> String {
+    let mut filters = vec![format!("setpts=PTS+{:.3}/TB", time.as_secs_f64())];
+    if safe_margin > 0. {
+        let ratio = safe_margin / 100.;
+        filters.push(format!(
+            "drawbox=x=iw*{0}:y=ih*{0}:w=iw*{1}:h=ih*{1}:color=yellow@0.5:t=2",
+            ratio,
+            1. - ratio * 2.
+        ));
+    }
+    filters.push(subtitles.to_string());
+    filters.join(",")
+}
+
+#[test]
+fn test_preview_filter() {
+    assert_eq!(
+        "setpts=PTS+1.500/TB,drawbox=x=iw*0.05:y=ih*0.05:w=iw*0.9:h=ih*0.9:color=yellow@0.5:t=2,ass=sub.ass",
+        preview_filter("ass=sub.ass", Duration::from_millis(1500), 5.)
+    );
+    assert_eq!(
+        "setpts=PTS+0.000/TB,subtitles=sub.srt",
+        preview_filter("subtitles=sub.srt", Duration::ZERO, 0.)
+    );
+}
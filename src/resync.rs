@@ -0,0 +1,98 @@
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use clap::Args;
+use voicepeak_srt_core::{parse, write_subtitle_file, SrtBlock, SubtitleBuilder};
+
+use crate::presets;
+
+/// 合成し直した音声に合わせて既存の字幕の時刻を作り直す(手で直したテキストは残す)
+#[derive(Args, Debug)]
+pub struct ResyncArgs {
+    /// 時刻を合わせ直す字幕ファイル(srtまたはvtt)
+    subtitles: PathBuf,
+    /// 合成し直した音声のフォルダー(連番は字幕を作ったときと同じにする)
+    #[arg(short, long)]
+    input_path: String,
+    /// 入力のプリセット
+    #[arg(long, default_value = "voicepeak")]
+    preset: String,
+    /// 独自のプリセットを書いたJSON
+    #[arg(long)]
+    preset_file: Option<PathBuf>,
+    /// 音声の間に入れる無音(ミリ秒)
+    #[arg(long, default_value_t = 0)]
+    gap_ms: u64,
+    /// 書き出す字幕(省略時は元のファイルを上書きする)
+    #[arg(short, long)]
+    output_path: Option<PathBuf>,
+}
+
+pub fn run(args: &ResyncArgs) -> crate::CliResult {
+    let edited = parse::read_subtitle_file(&args.subtitles)?;
+    let preset = presets::find(&args.preset, args.preset_file.as_deref())?;
+    let blocks = SubtitleBuilder::from_dir(&args.input_path)
+        .preset(&preset)
+        .gap(Duration::from_millis(args.gap_ms))
+        .build()?;
+
+    if blocks.len() != edited.len() {
+        eprintln!(
+            "警告: 字幕のブロック数({})と音声の数({})が違います。番号が同じブロックだけテキストを引き継ぎます",
+            edited.len(),
+            blocks.len()
+        );
+    }
+    let (blocks, kept) = keep_text(blocks, edited);
+    eprintln!("{}ブロックのテキストを引き継ぎました", kept);
+
+    let output = args.output_path.as_ref().unwrap_or(&args.subtitles);
+    write_subtitle_file(blocks, output)?;
+    Ok(())
+}
+
+/// 作り直したブロックのテキストを、番号が同じ既存のブロックのものにする
+///
+/// 引き継いだブロックの数を返す
+fn keep_text(blocks: Vec<SrtBlock>, edited: Vec<SrtBlock>) -> (Vec<SrtBlock>, usize) {
+    let mut texts: HashMap<usize, String> = edited
+        .into_iter()
+        .map(|block| (block.index, block.text))
+        .collect();
+    let mut kept = 0;
+    let blocks = blocks
+        .into_iter()
+        .map(|mut block| {
+            if let Some(text) = texts.remove(&block.index) {
+                block.text = text;
+                kept += 1;
+            }
+            block
+        })
+        .collect();
+    (blocks, kept)
+}
+
+#[test]
+fn test_keep_text() {
+    let blocks = vec![
+        SrtBlock::new(1, Duration::ZERO, Duration::from_secs(2), "元の一つ目"),
+        SrtBlock::new(
+            2,
+            Duration::from_secs(2),
+            Duration::from_secs(5),
+            "元の二つ目",
+        ),
+    ];
+    let edited = vec![SrtBlock::new(
+        1,
+        Duration::ZERO,
+        Duration::from_secs(1),
+        "直した一つ目",
+    )];
+    let (blocks, kept) = keep_text(blocks, edited);
+
+    assert_eq!(1, kept);
+    assert_eq!("直した一つ目", blocks[0].text);
+    assert_eq!(Duration::from_secs(2), blocks[0].end);
+    assert_eq!("元の二つ目", blocks[1].text);
+}
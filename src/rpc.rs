@@ -0,0 +1,177 @@
+use std::{
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::Args;
+use serde_json::{json, Value};
+use voicepeak_srt_core::{
+    parse::SubtitleFormat, write_subtitle_file, write_subtitles, FileListSource, SubtitleBuilder,
+};
+
+use crate::presets;
+
+/// 標準入力から1行1つのJSONの要求を読み、結果を標準出力に1行ずつ返す
+///
+/// エディターのプラグインなどから、起動したままの子プロセスとして使う。
+/// 要求は`{"id": 1, "method": "generate", "params": {"input": "./voice"}}`の形で、
+/// 結果は`{"id": 1, "result": ...}`か`{"id": 1, "error": {"message": "..."}}`で返す
+#[derive(Args, Debug)]
+pub struct RpcArgs {
+    /// 独自のプリセットを書いたJSON
+    #[arg(long)]
+    preset_file: Option<PathBuf>,
+}
+
+pub fn run(args: &RpcArgs) -> crate::CliResult {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, shutdown) = respond(args, &line);
+        serde_json::to_writer(&mut stdout, &response)?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+        if shutdown {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// 1行の要求に答える(`shutdown`なら終わる)
+fn respond(args: &RpcArgs, line: &str) -> (Value, bool) {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return (
+                error(Value::Null, format!("JSONを読み込めません: {}", e)),
+                false,
+            )
+        }
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let params = request.get("params").cloned().unwrap_or(json!({}));
+    let result = match request.get("method").and_then(Value::as_str) {
+        Some("generate") => generate(args, &params),
+        Some("presets") => list_presets(args),
+        Some("shutdown") => return (json!({ "id": id, "result": null }), true),
+        Some(method) => Err(format!("知らないメソッドです: {}", method)),
+        None => Err("methodがありません".to_string()),
+    };
+    match result {
+        Ok(result) => (json!({ "id": id, "result": result }), false),
+        Err(message) => (error(id, message), false),
+    }
+}
+
+fn error(id: Value, message: String) -> Value {
+    json!({ "id": id, "error": { "message": message } })
+}
+
+fn list_presets(args: &RpcArgs) -> Result<Value, String> {
+    let presets = presets::load(args.preset_file.as_deref()).map_err(|e| e.to_string())?;
+    Ok(presets
+        .iter()
+        .map(|preset| json!({ "name": preset.name, "description": preset.description }))
+        .collect())
+}
+
+/// 字幕を作成する
+///
+/// `input`(フォルダー)か`files`(wavの一覧、同じ名前のtxtも読む)を入力にする。
+/// `output`があればファイルに書き出してブロック数を、なければ`format`(既定はjson)で整形した字幕を返す
+fn generate(args: &RpcArgs, params: &Value) -> Result<Value, String> {
+    let string = |key: &str| params.get(key).and_then(Value::as_str);
+    let millis = |key: &str| {
+        params
+            .get(key)
+            .map(|value| {
+                value
+                    .as_u64()
+                    .map(Duration::from_millis)
+                    .ok_or_else(|| format!("{}は0以上の整数にしてください", key))
+            })
+            .transpose()
+    };
+
+    let builder = match (string("input"), params.get("files")) {
+        (Some(input), None) => SubtitleBuilder::from_dir(input),
+        (None, Some(files)) => {
+            let files = files
+                .as_array()
+                .and_then(|files| files.iter().map(Value::as_str).collect::<Option<Vec<_>>>())
+                .ok_or("filesはパスの配列にしてください")?;
+            SubtitleBuilder::from_source(FileListSource::new(with_texts(&files)))
+        }
+        _ => return Err("inputかfilesのどちらか一方を指定してください".to_string()),
+    };
+    let preset = presets::find(
+        string("preset").unwrap_or("voicepeak"),
+        args.preset_file.as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    let mut builder = builder.preset(&preset);
+    if let Some(gap) = millis("gap_ms")? {
+        builder = builder.gap(gap);
+    }
+    if let Some(offset) = millis("offset_ms")? {
+        builder = builder.offset(offset);
+    }
+    let blocks = builder.build().map_err(|e| e.to_string())?;
+
+    if let Some(output) = string("output") {
+        let count = blocks.len();
+        write_subtitle_file(blocks, Path::new(output)).map_err(|e| e.to_string())?;
+        return Ok(json!({ "count": count, "output": output }));
+    }
+    let format = match string("format") {
+        Some(format) => SubtitleFormat::from_extension(format)
+            .ok_or_else(|| format!("対応していない形式です: {}", format))?,
+        None => SubtitleFormat::Json,
+    };
+    if let SubtitleFormat::Json = format {
+        return serde_json::to_value(&blocks).map_err(|e| e.to_string());
+    }
+    let mut out = Vec::new();
+    write_subtitles(blocks, format, &mut out).map_err(|e| e.to_string())?;
+    Ok(Value::String(String::from_utf8_lossy(&out).into_owned()))
+}
+
+/// wavの一覧に、同じ名前のtxtがあれば加える
+fn with_texts(files: &[&str]) -> Vec<PathBuf> {
+    files
+        .iter()
+        .flat_map(|file| {
+            let text = Path::new(file).with_extension("txt");
+            let text = (text.is_file() && !files.contains(&text.to_string_lossy().as_ref()))
+                .then_some(text);
+            std::iter::once(PathBuf::from(file)).chain(text)
+        })
+        .collect()
+}
+
+#[test]
+fn test_respond() {
+    let args = RpcArgs { preset_file: None };
+    let (response, shutdown) = respond(
+        &args,
+        r#"{"id": 1, "method": "generate", "params": {"files": ["voice/000-voice.wav"]}}"#,
+    );
+    assert!(!shutdown);
+    assert_eq!(1, response["id"]);
+    assert_eq!(1, response["result"][0]["index"]);
+    assert!(response["result"][0]["text"]
+        .as_str()
+        .unwrap()
+        .starts_with("時は第三次中東戦争"));
+
+    let (response, _) = respond(&args, r#"{"id": "a", "method": "unknown"}"#);
+    assert_eq!("a", response["id"]);
+    assert!(response["error"]["message"].is_string());
+    assert!(respond(&args, r#"{"method": "shutdown"}"#).1);
+}
@@ -0,0 +1,142 @@
+//! rhaiスクリプトによる字幕ブロックの変換
+//!
+//! スクリプトには`transform(cue)`関数を定義する。`cue`は
+//! `#{ index, start_ms, end_ms, text }`のマップで、書き換えたマップを返すとそれが使われ、
+//! `()`を返すとそのブロックは取り除かれる。
+//!
+//! ```rhai
+//! fn transform(cue) {
+//!     if cue.text.starts_with("（") { return (); }
+//!     cue.text = "【ナレーター】" + cue.text;
+//!     cue
+//! }
+//! ```
+
+use std::{path::Path, time::Duration};
+
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use voicepeak_srt_core::{transform::Transform, SrtBlock};
+
+/// スクリプトの`transform`関数を呼び出す変換
+pub struct ScriptTransform {
+    engine: Engine,
+    ast: AST,
+}
+
+impl ScriptTransform {
+    /// スクリプトファイルを読み込む
+    pub fn from_file(path: &Path) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let engine = Engine::new();
+        let ast = engine.compile_file(path.to_path_buf())?;
+        Self::new(engine, ast)
+    }
+
+    fn new(engine: Engine, ast: AST) -> Result<Self, Box<rhai::EvalAltResult>> {
+        if !ast.iter_functions().any(|f| f.name == "transform") {
+            return Err("スクリプトにtransform(cue)関数がありません".into());
+        }
+        Ok(Self { engine, ast })
+    }
+
+    fn call(&self, block: &SrtBlock) -> Result<Option<SrtBlock>, Box<rhai::EvalAltResult>> {
+        let start_ms = block.start.as_millis() as i64;
+        let end_ms = block.end.as_millis() as i64;
+
+        let mut cue = Map::new();
+        cue.insert("index".into(), Dynamic::from(block.index as i64));
+        cue.insert("start_ms".into(), Dynamic::from(start_ms));
+        cue.insert("end_ms".into(), Dynamic::from(end_ms));
+        cue.insert("text".into(), Dynamic::from(block.text.clone()));
+
+        let result: Dynamic =
+            self.engine
+                .call_fn(&mut Scope::new(), &self.ast, "transform", (cue,))?;
+        if result.is_unit() {
+            return Ok(None);
+        }
+
+        let cue = result
+            .try_cast::<Map>()
+            .ok_or("transformはマップか()を返してください")?;
+        let get_int = |key: &str, default: i64| {
+            cue.get(key)
+                .and_then(|v| v.as_int().ok())
+                .unwrap_or(default)
+        };
+
+        // ミリ秒未満の精度を保つため、書き換えられた時刻だけを反映する
+        let mut block = block.clone();
+        block.index = get_int("index", block.index as i64).max(1) as usize;
+        if get_int("start_ms", start_ms) != start_ms {
+            block.start = Duration::from_millis(get_int("start_ms", start_ms).max(0) as u64);
+        }
+        if get_int("end_ms", end_ms) != end_ms {
+            block.end = Duration::from_millis(get_int("end_ms", end_ms).max(0) as u64);
+        }
+        if let Some(text) = cue.get("text") {
+            block.text = text.to_string();
+        }
+
+        Ok(Some(block))
+    }
+}
+
+impl Transform for ScriptTransform {
+    fn apply(&mut self, block: SrtBlock) -> Option<SrtBlock> {
+        match self.call(&block) {
+            Ok(block) => block,
+            Err(e) => {
+                // スクリプトのエラーでは処理を止めず、元のブロックを使う
+                eprintln!(
+                    "警告: ブロック{}のスクリプト実行に失敗しました: {}",
+                    block.index, e
+                );
+                Some(block)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn from_source(script: &str) -> ScriptTransform {
+    let engine = Engine::new();
+    let ast = engine.compile(script).unwrap();
+    ScriptTransform::new(engine, ast).unwrap()
+}
+
+#[cfg(test)]
+fn block(text: &str) -> SrtBlock {
+    SrtBlock::new(
+        1,
+        Duration::from_micros(1_000_500),
+        Duration::from_millis(2000),
+        text,
+    )
+}
+
+#[test]
+fn test_script_transform() {
+    let mut script = from_source(
+        r#"
+        fn transform(cue) {
+            if cue.text == "drop" { return (); }
+            cue.text = "[" + cue.text + "]";
+            cue.end_ms += 500;
+            cue
+        }
+        "#,
+    );
+
+    let result = script.apply(block("text")).unwrap();
+    assert_eq!("[text]", result.text);
+    assert_eq!(Duration::from_micros(1_000_500), result.start);
+    assert_eq!(Duration::from_millis(2500), result.end);
+    assert!(script.apply(block("drop")).is_none());
+}
+
+#[test]
+fn test_script_transform_without_function() {
+    let engine = Engine::new();
+    let ast = engine.compile("let a = 1;").unwrap();
+    assert!(ScriptTransform::new(engine, ast).is_err());
+}
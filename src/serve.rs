@@ -0,0 +1,487 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use clap::Args;
+use flate2::read::DeflateDecoder;
+use voicepeak_srt_core::{parse::SubtitleFormat, write_subtitles, SubtitleBuilder};
+
+use crate::presets;
+
+/// 受け付けるパス
+const ENDPOINT: &str = "/subtitles";
+
+/// 展開する一時フォルダーの連番(同時に来た要求で重ならないため)
+static REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+/// 送信が止まった接続を閉じるまでの時間
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// HTTPでwavとtxtのzipを受け取り、字幕を返す(Ctrl+Cで終わる)
+///
+/// `POST /subtitles?format=srt|vtt|ass|sbv|json&preset=voicepeak`にzipを送る
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// 待ち受けるアドレス
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// 独自のプリセットを書いたJSON
+    #[arg(long)]
+    preset_file: Option<PathBuf>,
+    /// 受け付けるzipの大きさの上限(MB)
+    #[arg(long, default_value_t = 512)]
+    max_size_mb: usize,
+    /// 同時に処理する要求の数(残りは前の要求が終わるまで待たせる)
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    workers: u16,
+}
+
+/// 要求に返すエラー
+struct HttpError {
+    status: &'static str,
+    message: String,
+}
+
+impl HttpError {
+    fn bad_request<S: Into<String>>(message: S) -> Self {
+        Self {
+            status: "400 Bad Request",
+            message: message.into(),
+        }
+    }
+}
+
+pub fn run(args: &ServeArgs) -> crate::CliResult {
+    let listener = TcpListener::bind(&args.listen)
+        .map_err(|e| format!("{}で待ち受けられません: {}", args.listen, e))?;
+    eprintln!(
+        "http://{}{}で待ち受けています(Ctrl+Cで終わります)",
+        args.listen, ENDPOINT
+    );
+    // 要求ごとにzipを丸ごと持つため、メモリを使い切らないよう決まった数のスレッドで処理する
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(0);
+    let receiver = Mutex::new(receiver);
+    thread::scope(|scope| {
+        for _ in 0..args.workers {
+            scope.spawn(|| loop {
+                let stream = receiver.lock().unwrap().recv();
+                let Ok(stream) = stream else {
+                    break;
+                };
+                if let Err(e) = handle(args, stream) {
+                    eprintln!("警告: 応答できません: {}", e);
+                }
+            });
+        }
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if sender.send(stream).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => eprintln!("警告: 接続を受け付けられません: {}", e),
+            }
+        }
+        drop(sender);
+    });
+    Ok(())
+}
+
+/// 1つの要求を読み、字幕かエラーを返す
+fn handle(args: &ServeArgs, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+    let response = respond(args, &mut reader, &mut stream);
+    match response {
+        Ok((content_type, body)) => write_response(&mut stream, "200 OK", content_type, &body),
+        Err(e) => {
+            eprintln!("エラー: {}", e.message);
+            let body = format!("{}\n", e.message);
+            write_response(
+                &mut stream,
+                e.status,
+                "text/plain; charset=utf-8",
+                body.as_bytes(),
+            )
+        }
+    }
+}
+
+fn respond<R: BufRead, W: Write>(
+    args: &ServeArgs,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<(&'static str, Vec<u8>), HttpError> {
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .map_err(|e| HttpError::bad_request(format!("要求を読み込めません: {}", e)))?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != ENDPOINT {
+        return Err(HttpError {
+            status: "404 Not Found",
+            message: format!("{}にPOSTしてください", ENDPOINT),
+        });
+    }
+    if method != "POST" {
+        return Err(HttpError {
+            status: "405 Method Not Allowed",
+            message: "POSTでzipを送ってください".to_string(),
+        });
+    }
+
+    // ヘッダーは本文の長さと100 Continueの要求だけを使う
+    let mut length = None;
+    let mut expect_continue = false;
+    loop {
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|e| HttpError::bad_request(format!("ヘッダーを読み込めません: {}", e)))?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            } else if name.eq_ignore_ascii_case("expect") {
+                expect_continue = value.trim().eq_ignore_ascii_case("100-continue");
+            }
+        }
+    }
+    let length = length.ok_or_else(|| HttpError {
+        status: "411 Length Required",
+        message: "Content-Lengthを付けてください".to_string(),
+    })?;
+    if length > args.max_size_mb * 1024 * 1024 {
+        return Err(HttpError {
+            status: "413 Payload Too Large",
+            message: format!("zipは{}MBまでです", args.max_size_mb),
+        });
+    }
+    let limit = args.max_size_mb * 1024 * 1024;
+    // curlは大きなファイルを送る前に100 Continueを待つ
+    if expect_continue {
+        writer
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+            .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    }
+    // Content-Lengthの分を先に確保せず、届いた分だけ持つ
+    let mut body = Vec::new();
+    reader
+        .take(length as u64)
+        .read_to_end(&mut body)
+        .map_err(|e| HttpError::bad_request(format!("zipを受け取れません: {}", e)))?;
+    if body.len() < length {
+        return Err(HttpError::bad_request("zipが途中で切れています"));
+    }
+
+    let mut format = SubtitleFormat::Srt;
+    let mut preset_name = "voicepeak".to_string();
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        let (key, value) = (percent_decode(key), percent_decode(value));
+        let value = value.as_str();
+        match key.as_str() {
+            "format" => {
+                format = SubtitleFormat::from_extension(value).ok_or_else(|| {
+                    HttpError::bad_request(format!("対応していない形式です: {}", value))
+                })?
+            }
+            "preset" => preset_name = value.to_string(),
+            _ => {}
+        }
+    }
+    let preset = presets::find(&preset_name, args.preset_file.as_deref())
+        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+
+    let dir = std::env::temp_dir().join(format!(
+        "voicepeak-srt-serve-{}-{}",
+        std::process::id(),
+        REQUESTS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = extract_zip(&body, &dir, limit)
+        .map_err(|e| match e {
+            ZipError::TooLarge => HttpError {
+                status: "413 Payload Too Large",
+                message: format!("展開すると{}MBを超えます", args.max_size_mb),
+            },
+            ZipError::Invalid(e) => HttpError::bad_request(format!("zipを展開できません: {}", e)),
+        })
+        .and_then(|()| {
+            let blocks = SubtitleBuilder::from_dir(&dir)
+                .preset(&preset)
+                .build()
+                .map_err(|e| HttpError {
+                    status: "422 Unprocessable Entity",
+                    message: format!("字幕を作れません: {}", e),
+                })?;
+            let mut out = Vec::new();
+            write_subtitles(blocks, format, &mut out).map_err(|e| HttpError {
+                status: "500 Internal Server Error",
+                message: format!("字幕を書き出せません: {}", e),
+            })?;
+            Ok((content_type(format), out))
+        });
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+/// URLのクエリの`%XX`と`+`を元の文字に戻す(不正な`%`はそのまま残す)
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                out.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn content_type(format: SubtitleFormat) -> &'static str {
+    match format {
+        SubtitleFormat::Vtt => "text/vtt; charset=utf-8",
+        SubtitleFormat::Json => "application/json",
+        _ => "text/plain; charset=utf-8",
+    }
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+/// zipを展開できない理由
+#[derive(Debug)]
+enum ZipError {
+    /// 展開した大きさの合計が上限を超える
+    TooLarge,
+    /// zipが壊れているか、対応していない
+    Invalid(String),
+}
+
+impl From<String> for ZipError {
+    fn from(message: String) -> Self {
+        Self::Invalid(message)
+    }
+}
+
+impl From<&str> for ZipError {
+    fn from(message: &str) -> Self {
+        Self::Invalid(message.to_string())
+    }
+}
+
+/// zipのwavとtxtを`dir`に展開する
+///
+/// フォルダーの階層は無視してファイル名だけで置く。対応する圧縮方式は無圧縮とDeflateのみ。
+/// 展開した大きさの合計が`limit`バイトを超えたら止める
+fn extract_zip(zip: &[u8], dir: &Path, limit: usize) -> Result<(), ZipError> {
+    let u16_at = |pos: usize| -> Result<usize, String> {
+        zip.get(pos..pos + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+            .ok_or_else(|| "途中で途切れています".to_string())
+    };
+    let u32_at = |pos: usize| -> Result<usize, String> {
+        zip.get(pos..pos + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or_else(|| "途中で途切れています".to_string())
+    };
+
+    // 末尾の中央ディレクトリの終わりを探す
+    let end = (0..zip.len().saturating_sub(21))
+        .rev()
+        .find(|&pos| zip[pos..].starts_with(b"PK\x05\x06"))
+        .ok_or("zipではありません")?;
+    let count = u16_at(end + 10)?;
+    let mut pos = u32_at(end + 16)?;
+
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let mut remaining = limit;
+    for _ in 0..count {
+        if zip.get(pos..pos + 4) != Some(b"PK\x01\x02") {
+            return Err("中央ディレクトリが壊れています".into());
+        }
+        let method = u16_at(pos + 10)?;
+        let compressed = u32_at(pos + 20)?;
+        let name_len = u16_at(pos + 28)?;
+        let extra_len = u16_at(pos + 30)?;
+        let comment_len = u16_at(pos + 32)?;
+        let local = u32_at(pos + 42)?;
+        let name = zip
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or("途中で途切れています")?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        pos += 46 + name_len + extra_len + comment_len;
+
+        // 「../」などでフォルダーの外に書かないよう、ファイル名だけを使う
+        let Some(file_name) = Path::new(&name).file_name() else {
+            continue;
+        };
+        let extension = Path::new(file_name).extension();
+        if name.ends_with('/')
+            || (extension != Some("wav".as_ref()) && extension != Some("txt".as_ref()))
+        {
+            continue;
+        }
+
+        let data_start = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+        let data = zip
+            .get(data_start..data_start + compressed)
+            .ok_or("途中で途切れています")?;
+        let contents = match method {
+            0 => data.to_vec(),
+            8 => {
+                // 上限を1バイトでも超えれば分かるだけ読む
+                let mut out = Vec::new();
+                DeflateDecoder::new(data)
+                    .take(remaining as u64 + 1)
+                    .read_to_end(&mut out)
+                    .map_err(|e| format!("{}: {}", name, e))?;
+                out
+            }
+            _ => return Err(format!("対応していない圧縮方式です({}): {}", method, name).into()),
+        };
+        remaining = remaining
+            .checked_sub(contents.len())
+            .ok_or(ZipError::TooLarge)?;
+        fs::write(dir.join(file_name), contents).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 無圧縮のzipを作る(テスト用)
+#[cfg(test)]
+fn stored_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = Vec::new();
+    let mut central = Vec::new();
+    for (name, data) in files {
+        let offset = zip.len() as u32;
+        let header = |signature: &[u8], central: bool| {
+            let mut out = signature.to_vec();
+            if central {
+                out.extend([20, 0]);
+            }
+            out.extend([20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            out.extend((data.len() as u32).to_le_bytes());
+            out.extend((data.len() as u32).to_le_bytes());
+            out.extend((name.len() as u16).to_le_bytes());
+            out.extend([0, 0]);
+            if central {
+                out.extend([0; 10]);
+                out.extend(offset.to_le_bytes());
+            }
+            out.extend(name.as_bytes());
+            out
+        };
+        zip.extend(header(b"PK\x03\x04", false));
+        zip.extend(*data);
+        central.extend(header(b"PK\x01\x02", true));
+    }
+    let central_offset = zip.len() as u32;
+    let central_len = central.len() as u32;
+    zip.extend(central);
+    zip.extend(b"PK\x05\x06\0\0\0\0");
+    zip.extend((files.len() as u16).to_le_bytes());
+    zip.extend((files.len() as u16).to_le_bytes());
+    zip.extend(central_len.to_le_bytes());
+    zip.extend(central_offset.to_le_bytes());
+    zip.extend([0, 0]);
+    zip
+}
+
+#[test]
+fn test_extract_zip() {
+    let zip = stored_zip(&[
+        ("voice/000-voice.txt", "こんにちは".as_bytes()),
+        ("../001-voice.txt", b"a"),
+        ("readme.md", b"b"),
+    ]);
+    let dir = std::env::temp_dir().join(format!("voicepeak-srt-zip-{}", std::process::id()));
+    extract_zip(&zip, &dir, 1024).unwrap();
+    let mut names: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    let text = fs::read_to_string(dir.join("000-voice.txt")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    // 階層は無視し、wavとtxt以外は展開しない
+    names.sort();
+    assert_eq!(vec!["000-voice.txt", "001-voice.txt"], names);
+    assert_eq!("こんにちは", text);
+    assert!(extract_zip(b"not a zip", &dir, 1024).is_err());
+}
+
+#[test]
+fn test_extract_zip_limit() {
+    use flate2::{write::DeflateEncoder, Compression};
+
+    // 小さく圧縮できる大きなファイルも、展開した大きさで数える
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&[b'a'; 4096]).unwrap();
+    let mut zip = stored_zip(&[("000-voice.txt", &encoder.finish().unwrap())]);
+    let central = zip.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+    zip[central + 10] = 8;
+
+    let dir = std::env::temp_dir().join(format!("voicepeak-srt-zip-limit-{}", std::process::id()));
+    let result = extract_zip(&zip, &dir, 1024);
+    assert!(matches!(result, Err(ZipError::TooLarge)));
+    extract_zip(&zip, &dir, 4096).unwrap();
+    assert_eq!(4096, fs::metadata(dir.join("000-voice.txt")).unwrap().len());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_percent_decode() {
+    assert_eq!("vtt", percent_decode("%76tt"));
+    assert_eq!(
+        "ゆっくり 霊夢",
+        percent_decode("%E3%82%86%E3%81%A3%E3%81%8F%E3%82%8A+霊夢")
+    );
+    assert_eq!("100%", percent_decode("100%"));
+    assert_eq!("%zz% 1", percent_decode("%zz%+1"));
+}
@@ -0,0 +1,279 @@
+use std::{fs, io::Write, path::PathBuf, time::Duration};
+
+use clap::Args;
+use voicepeak_srt_core::{
+    format_srt_time, format_vtt_time,
+    parse::{self, SubtitleFormat},
+    write_subtitle_file, OutputFile, SrtBlock,
+};
+
+use crate::merge::parse_time;
+
+/// 既存の字幕の時刻をずらす(イントロを足したときなど、音声から作り直さずに合わせ直す)
+#[derive(Args, Debug)]
+pub struct ShiftArgs {
+    /// 時刻をずらす字幕ファイル(srtまたはvtt)
+    subtitles: PathBuf,
+    /// ずらす時間(`-`を付けると前にずらす。例: `5.5`・`-00:01.200`・`+0:00:03`)
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_shift)]
+    by: Shift,
+    /// この時刻以降に始まるブロックだけをずらす
+    #[arg(long, value_parser = parse_time)]
+    after: Option<Duration>,
+    /// 書き出す字幕(省略時は元のファイルを上書きする)
+    #[arg(short, long)]
+    output_path: Option<PathBuf>,
+}
+
+/// ずらす向きと時間
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Shift {
+    backward: bool,
+    amount: Duration,
+}
+
+pub fn run(args: &ShiftArgs) -> crate::CliResult {
+    let output = args.output_path.as_ref().unwrap_or(&args.subtitles);
+    let after = args.after.unwrap_or_default();
+
+    // vttはヘッダーやNOTE・STYLEブロック、キューの設定を残すため、時刻の行だけを書き換える
+    let is_vtt = |path: &PathBuf| SubtitleFormat::from_path(path) == Some(SubtitleFormat::Vtt);
+    if is_vtt(&args.subtitles) && is_vtt(output) {
+        let text = fs::read_to_string(&args.subtitles)?;
+        let shifted = shift_vtt(&text, args.by, after)?;
+        report(&shifted.times, shifted.removed);
+        let mut file = OutputFile::create(output)?;
+        file.write_all(shifted.text.as_bytes())?;
+        file.commit()?;
+        return Ok(());
+    }
+
+    let blocks = parse::read_subtitle_file(&args.subtitles)?;
+    let count = blocks.len();
+    let shifted = shift(blocks, args.by, after);
+    let times: Vec<_> = shifted
+        .iter()
+        .map(|block| (block.start, block.end))
+        .collect();
+    report(&times, count - shifted.len());
+
+    write_subtitle_file(shifted, output)?;
+    Ok(())
+}
+
+/// 削除したブロックと、前のブロックに重なるブロックを警告する
+fn report(times: &[(Duration, Duration)], removed: usize) {
+    // 前にずらして0秒より前に収まったブロックは消える
+    if removed > 0 {
+        eprintln!("警告: 0秒より前にずれた{}ブロックを削除しました", removed);
+    }
+    for pair in times.windows(2) {
+        if pair[1].0 < pair[0].1 {
+            eprintln!(
+                "警告: {}のブロックが前のブロックに重なります",
+                format_srt_time(pair[1].0)
+            );
+        }
+    }
+}
+
+/// 符号付きの時間を読み込む
+fn parse_shift(text: &str) -> Result<Shift, String> {
+    let (backward, rest) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    // 秒だけの指定も受け付ける
+    let amount = match rest.parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs >= 0. => Duration::from_secs_f64(secs),
+        _ => parse_time(rest)?,
+    };
+    Ok(Shift { backward, amount })
+}
+
+impl Shift {
+    /// 時刻をずらす(0秒より前にはしない)
+    fn apply(&self, time: Duration) -> Duration {
+        match self.backward {
+            true => time.saturating_sub(self.amount),
+            false => time + self.amount,
+        }
+    }
+}
+
+/// `after`以降に始まるブロックの時刻をずらし、番号を振り直す
+fn shift(blocks: Vec<SrtBlock>, by: Shift, after: Duration) -> Vec<SrtBlock> {
+    blocks
+        .into_iter()
+        .filter_map(|mut block| {
+            if block.start >= after {
+                block.start = by.apply(block.start);
+                block.end = by.apply(block.end);
+            }
+            (!block.end.is_zero()).then_some(block)
+        })
+        .enumerate()
+        .map(|(i, mut block)| {
+            block.index = i + 1;
+            block
+        })
+        .collect()
+}
+
+/// 時刻をずらしたvtt
+#[derive(Debug)]
+struct ShiftedVtt {
+    text: String,
+    /// 残ったキューのずらした後の時刻
+    times: Vec<(Duration, Duration)>,
+    /// 0秒より前にずれて削除したキューの数
+    removed: usize,
+}
+
+/// vttのキューの時刻の行だけをずらす
+///
+/// ヘッダー、NOTE・STYLE・REGIONブロック、識別子とキューの設定はそのまま残す
+fn shift_vtt(text: &str, by: Shift, after: Duration) -> Result<ShiftedVtt, String> {
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut out: Vec<String> = Vec::new();
+    let mut times = Vec::new();
+    let mut removed = 0;
+    // ブロックは空行で区切られ、最初のブロックはヘッダー
+    let mut blocks: Vec<Vec<&str>> = vec![Vec::new()];
+    for line in text.lines() {
+        match line.trim().is_empty() {
+            true => blocks.push(Vec::new()),
+            false => blocks.last_mut().unwrap().push(line),
+        }
+    }
+
+    for (i, mut lines) in blocks.into_iter().enumerate() {
+        let first = lines.first().copied().unwrap_or_default();
+        let is_cue = i > 0
+            && !["NOTE", "STYLE", "REGION"]
+                .iter()
+                .any(|kw| first == *kw || first.starts_with(&format!("{} ", kw)));
+        // 識別子の行は省略されていてもよい
+        if let Some(timing_at) = lines
+            .iter()
+            .take(2)
+            .position(|line| line.contains("-->"))
+            .filter(|_| is_cue)
+        {
+            let line = lines[timing_at];
+            let (start_text, rest) = line.split_once("-->").unwrap();
+            let rest = rest.trim_start();
+            let end_text = rest.split_whitespace().next().unwrap_or_default();
+            let (Some(mut start), Some(mut end)) = (
+                parse::parse_timestamp(start_text),
+                parse::parse_timestamp(end_text),
+            ) else {
+                return Err(format!("タイムスタンプを読み込めません: {}", line));
+            };
+            let settings = &rest[end_text.len()..];
+            if start >= after {
+                start = by.apply(start);
+                end = by.apply(end);
+            }
+            if end.is_zero() {
+                removed += 1;
+                continue;
+            }
+            times.push((start, end));
+            let timing = format!(
+                "{} --> {}{}",
+                format_vtt_time(start),
+                format_vtt_time(end),
+                settings
+            );
+            lines[timing_at] = &timing;
+            out.push(lines.join(newline));
+            continue;
+        }
+        if !lines.is_empty() {
+            out.push(lines.join(newline));
+        }
+    }
+
+    let mut text = out.join(&format!("{0}{0}", newline));
+    text.push_str(newline);
+    Ok(ShiftedVtt {
+        text,
+        times,
+        removed,
+    })
+}
+
+#[test]
+fn test_parse_shift() {
+    assert_eq!(
+        Ok(Shift {
+            backward: true,
+            amount: Duration::from_millis(1500)
+        }),
+        parse_shift("-1.5")
+    );
+    assert_eq!(
+        Ok(Shift {
+            backward: false,
+            amount: Duration::from_secs(63)
+        }),
+        parse_shift("+01:03")
+    );
+    assert!(parse_shift("-abc").is_err());
+}
+
+#[test]
+fn test_shift_after() {
+    let blocks = vec![
+        SrtBlock::new(1, Duration::ZERO, Duration::from_secs(1), "a"),
+        SrtBlock::new(2, Duration::from_secs(2), Duration::from_secs(3), "b"),
+    ];
+    let forward = Shift {
+        backward: false,
+        amount: Duration::from_secs(5),
+    };
+    let shifted = shift(blocks.clone(), forward, Duration::from_secs(2));
+    assert_eq!(Duration::ZERO, shifted[0].start);
+    assert_eq!(Duration::from_secs(7), shifted[1].start);
+
+    let backward = Shift {
+        backward: true,
+        amount: Duration::from_secs(1),
+    };
+    let shifted = shift(blocks, backward, Duration::ZERO);
+    assert_eq!(1, shifted.len());
+    assert_eq!(1, shifted[0].index);
+    assert_eq!(Duration::from_secs(1), shifted[0].start);
+}
+
+#[test]
+fn test_shift_vtt() {
+    let text = "WEBVTT - タイトル\n\nNOTE 作成メモ\n\nSTYLE\n::cue { color: yellow }\n\nintro\n00:00.500 --> 00:01.000 line:0 align:start\n消える\n\n00:00:02.000 --> 00:00:03.500\n残る\n";
+    let backward = Shift {
+        backward: true,
+        amount: Duration::from_secs(1),
+    };
+    let shifted = shift_vtt(text, backward, Duration::ZERO).unwrap();
+
+    assert_eq!(
+        "WEBVTT - タイトル\n\nNOTE 作成メモ\n\nSTYLE\n::cue { color: yellow }\n\n00:00:01.000 --> 00:00:02.500\n残る\n",
+        shifted.text
+    );
+    assert_eq!(1, shifted.removed);
+    assert_eq!(
+        vec![(Duration::from_secs(1), Duration::from_millis(2500))],
+        shifted.times
+    );
+
+    // キューの設定と識別子は残す
+    let forward = Shift {
+        backward: false,
+        amount: Duration::from_secs(1),
+    };
+    let shifted = shift_vtt(text, forward, Duration::ZERO).unwrap();
+    assert!(shifted
+        .text
+        .contains("intro\n00:00:01.500 --> 00:00:02.000 line:0 align:start\n消える"));
+}
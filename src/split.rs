@@ -0,0 +1,127 @@
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::Args;
+use voicepeak_srt_core::{parse, write_subtitle_file, SrtBlock};
+
+use crate::merge::parse_time;
+
+/// 既存の字幕を指定の時刻か章の区切りで分け、それぞれ0秒から始まる字幕にする
+#[derive(Args, Debug)]
+pub struct SplitArgs {
+    /// 分ける字幕ファイル(srtまたはvtt)
+    subtitles: PathBuf,
+    /// 区切る時刻(`,`区切りで複数指定する)
+    #[arg(long, value_delimiter = ',', value_parser = parse_time, required_unless_present = "chapter_map")]
+    at: Vec<Duration>,
+    /// generateの--chapter-mapで書き出した章の一覧(章の開始時刻で区切り、章の名前をファイル名に付ける)
+    #[arg(long, conflicts_with = "at")]
+    chapter_map: Option<PathBuf>,
+    /// 書き出すフォルダー
+    #[arg(short, long, default_value = ".")]
+    output_dir: PathBuf,
+}
+
+pub fn run(args: &SplitArgs) -> crate::CliResult {
+    let blocks = parse::read_subtitle_file(&args.subtitles)?;
+
+    // 区切りの時刻と、分けたファイルに付ける名前(章の一覧では最初の章の開始時刻では区切らない)
+    let (cuts, names) = match &args.chapter_map {
+        Some(path) => {
+            let chapters = read_chapter_map(path)
+                .map_err(|e| format!("章の一覧を読み込めません({}): {}", path.display(), e))?;
+            let cuts: Vec<Duration> = chapters.iter().skip(1).map(|(cut, _)| *cut).collect();
+            let names: Vec<String> = chapters.into_iter().map(|(_, name)| name).collect();
+            (cuts, names)
+        }
+        None => (
+            args.at.clone(),
+            (1..=args.at.len() + 1)
+                .map(|i| format!("{:02}", i))
+                .collect(),
+        ),
+    };
+
+    std::fs::create_dir_all(&args.output_dir)?;
+    for (part, name) in split(blocks, &cuts).into_iter().zip(&names) {
+        let path = args.output_dir.join(part_name(&args.subtitles, name));
+        eprintln!("{}: {}ブロック", path.display(), part.len());
+        write_subtitle_file(part, &path)?;
+    }
+    Ok(())
+}
+
+/// 章の一覧から各章の開始時刻と名前を読み込む
+fn read_chapter_map(path: &Path) -> io::Result<Vec<(Duration, String)>> {
+    let map: serde_json::Value = serde_json::from_reader(io::BufReader::new(File::open(path)?))?;
+    let chapters = map["chapters"]
+        .as_array()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "chaptersがありません"))?;
+    Ok(chapters
+        .iter()
+        .map(|chapter| {
+            (
+                Duration::from_millis(chapter["offset_ms"].as_u64().unwrap_or_default()),
+                chapter["chapter"].as_str().unwrap_or_default().to_string(),
+            )
+        })
+        .collect())
+}
+
+/// `subtitles.srt`と`01`から`subtitles-01.srt`を作る
+fn part_name(path: &Path, name: &str) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, name, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, name),
+    }
+}
+
+/// 区切りの時刻でブロックを分け、区切りを0秒として番号も振り直す
+///
+/// 区切りをまたぐブロックは始まる側に入れ、終わりを区切りで切る
+fn split(blocks: Vec<SrtBlock>, cuts: &[Duration]) -> Vec<Vec<SrtBlock>> {
+    let mut cuts = cuts.to_vec();
+    cuts.sort();
+    let mut parts: Vec<Vec<SrtBlock>> = vec![Vec::new(); cuts.len() + 1];
+    for mut block in blocks {
+        let part = cuts.partition_point(|&cut| cut <= block.start);
+        let start = part.checked_sub(1).map_or(Duration::ZERO, |i| cuts[i]);
+        if let Some(&end) = cuts.get(part) {
+            block.end = block.end.min(end);
+        }
+        block.start -= start;
+        block.end -= start;
+        block.index = parts[part].len() + 1;
+        parts[part].push(block);
+    }
+    parts
+}
+
+#[test]
+fn test_split() {
+    let blocks = vec![
+        SrtBlock::new(1, Duration::ZERO, Duration::from_secs(2), "a"),
+        SrtBlock::new(2, Duration::from_secs(2), Duration::from_secs(6), "b"),
+        SrtBlock::new(3, Duration::from_secs(6), Duration::from_secs(7), "c"),
+    ];
+    let parts = split(blocks, &[Duration::from_secs(5)]);
+
+    assert_eq!(2, parts[0].len());
+    assert_eq!(Duration::from_secs(5), parts[0][1].end);
+    assert_eq!(1, parts[1][0].index);
+    assert_eq!(Duration::from_secs(1), parts[1][0].start);
+    assert_eq!(Duration::from_secs(2), parts[1][0].end);
+}
+
+#[test]
+fn test_part_name() {
+    assert_eq!(
+        "subtitles-ch01.vtt",
+        part_name(Path::new("./out/subtitles.vtt"), "ch01")
+    );
+}
@@ -0,0 +1,112 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use clap::Args;
+use voicepeak_srt_core::SubtitleBuilder;
+
+/// Voicepeakの1回の読み上げで扱える最大文字数
+const MAX_CHARS: usize = 140;
+
+/// 台本の1行ごとにVoicepeakで音声を書き出し、字幕まで作成する
+#[derive(Args, Debug)]
+pub struct SynthesizeArgs {
+    /// 台本(1行が1ブロック、空行は飛ばす)
+    script: PathBuf,
+    /// wavとtxtを書き出すフォルダー
+    #[arg(short, long, default_value = "./voice")]
+    output_dir: PathBuf,
+    /// 作成する字幕ファイル
+    #[arg(short, long, default_value = "./subtitles.srt")]
+    subtitles: PathBuf,
+    /// ナレーター名(例: "Japanese Female 1")
+    #[arg(short, long)]
+    narrator: Option<String>,
+    /// 感情(例: "happy=50,sad=20")
+    #[arg(short, long)]
+    emotion: Option<String>,
+    /// 話速(50〜200)
+    #[arg(long)]
+    speed: Option<u32>,
+    /// 音高(-300〜300)
+    #[arg(long, allow_negative_numbers = true)]
+    pitch: Option<i32>,
+    /// VoicepeakのCLIのパス
+    #[arg(long, default_value = "voicepeak")]
+    voicepeak: String,
+}
+
+pub fn run(args: &SynthesizeArgs) -> crate::CliResult {
+    let script = fs::read_to_string(&args.script)
+        .map_err(|e| format!("台本を読み込めません({}): {}", args.script.display(), e))?;
+    let lines: Vec<&str> = script
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    if lines.is_empty() {
+        return Err("台本にテキストがありません".into());
+    }
+    if let Some((n, line)) = lines
+        .iter()
+        .enumerate()
+        .find(|(_, line)| line.chars().count() > MAX_CHARS)
+    {
+        return Err(format!(
+            "{}行目が{}文字を超えています。分割してください: {}",
+            n + 1,
+            MAX_CHARS,
+            line
+        )
+        .into());
+    }
+
+    fs::create_dir_all(&args.output_dir)?;
+    for (seq, line) in lines.iter().enumerate() {
+        let wav = args.output_dir.join(format!("{:03}-voice.wav", seq));
+        eprintln!("[{}/{}] {}", seq + 1, lines.len(), line);
+        speak(args, line, &wav)?;
+        fs::write(wav.with_extension("txt"), line)?;
+    }
+
+    // 書き出した音声から字幕を作成する
+    SubtitleBuilder::from_dir(&args.output_dir).write_file(&args.subtitles)?;
+
+    Ok(())
+}
+
+/// Voicepeakで`text`を読み上げたwavを書き出す
+fn speak(args: &SynthesizeArgs, text: &str, wav: &Path) -> crate::CliResult {
+    let mut command = Command::new(&args.voicepeak);
+    command.arg("-s").arg(text).arg("-o").arg(wav);
+    if let Some(narrator) = &args.narrator {
+        command.arg("-n").arg(narrator);
+    }
+    if let Some(emotion) = &args.emotion {
+        command.arg("-e").arg(emotion);
+    }
+    if let Some(speed) = args.speed {
+        command.arg("--speed").arg(speed.to_string());
+    }
+    if let Some(pitch) = args.pitch {
+        command.arg("--pitch").arg(pitch.to_string());
+    }
+
+    let status = command
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => format!(
+                "Voicepeakが見つかりません({})。--voicepeakでパスを指定してください",
+                args.voicepeak
+            ),
+            _ => format!("Voicepeakを起動できません: {}", e),
+        })?;
+    if !status.success() || !wav.exists() {
+        return Err(format!("Voicepeakでの書き出しに失敗しました({}): {}", status, text).into());
+    }
+
+    Ok(())
+}
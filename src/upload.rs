@@ -0,0 +1,131 @@
+use std::{env, fs, path::PathBuf};
+
+use clap::{Args, Subcommand};
+
+/// 字幕のアップロード先
+const YOUTUBE_CAPTIONS_URL: &str =
+    "https://www.googleapis.com/upload/youtube/v3/captions?part=snippet&uploadType=multipart";
+
+/// アクセストークンを読む環境変数
+const TOKEN_ENV: &str = "YOUTUBE_ACCESS_TOKEN";
+
+/// multipartの区切り
+const BOUNDARY: &str = "voicepeak-srt-boundary";
+
+/// 作成した字幕を動画サイトにアップロードする
+#[derive(Args, Debug)]
+pub struct UploadArgs {
+    #[command(subcommand)]
+    target: UploadTarget,
+}
+
+#[derive(Subcommand, Debug)]
+enum UploadTarget {
+    /// YouTube Data APIで動画に字幕トラックを追加する
+    Youtube(YoutubeArgs),
+}
+
+#[derive(Args, Debug)]
+struct YoutubeArgs {
+    /// 字幕を追加する動画のID
+    video_id: String,
+    /// アップロードする字幕(srt・vtt)
+    #[arg(short, long, default_value = "./subtitles.srt")]
+    subtitles: PathBuf,
+    /// 字幕の言語(BCP-47)
+    #[arg(long, default_value = "ja")]
+    language: String,
+    /// 字幕トラックの名前
+    #[arg(long, default_value = "")]
+    name: String,
+    /// 下書きとしてアップロードする(視聴者には表示されない)
+    #[arg(long)]
+    draft: bool,
+    /// OAuthのアクセストークンを書いたファイル(なければ環境変数YOUTUBE_ACCESS_TOKENを使う)
+    #[arg(long)]
+    token_file: Option<PathBuf>,
+}
+
+pub fn run(args: &UploadArgs) -> crate::CliResult {
+    match &args.target {
+        UploadTarget::Youtube(args) => upload_youtube(args),
+    }
+}
+
+/// YouTubeに字幕トラックを追加する
+fn upload_youtube(args: &YoutubeArgs) -> crate::CliResult {
+    let token = match &args.token_file {
+        Some(path) => fs::read_to_string(path).map_err(|e| {
+            format!(
+                "アクセストークンを読み込めません({}): {}",
+                path.display(),
+                e
+            )
+        })?,
+        None => env::var(TOKEN_ENV).map_err(|_| {
+            format!(
+                "アクセストークンがありません。--token-fileか環境変数{}で指定してください",
+                TOKEN_ENV
+            )
+        })?,
+    };
+    let caption = fs::read(&args.subtitles)
+        .map_err(|e| format!("字幕を読み込めません({}): {}", args.subtitles.display(), e))?;
+
+    let metadata = serde_json::json!({
+        "snippet": {
+            "videoId": args.video_id,
+            "language": args.language,
+            "name": args.name,
+            "isDraft": args.draft,
+        }
+    });
+    let body = multipart_body(&metadata.to_string(), &caption);
+    let response = ureq::post(YOUTUBE_CAPTIONS_URL)
+        .set("Authorization", &format!("Bearer {}", token.trim()))
+        .set(
+            "Content-Type",
+            &format!("multipart/related; boundary={}", BOUNDARY),
+        )
+        .send_bytes(&body)
+        .map_err(|e| match e {
+            ureq::Error::Status(401 | 403, _) => format!(
+                "YouTubeに拒否されました({})。アクセストークンの期限とyoutube.force-sslの権限を確認してください",
+                e
+            ),
+            ureq::Error::Status(_, response) => format!(
+                "YouTubeへのアップロードに失敗しました: {}",
+                response.into_string().unwrap_or_default()
+            ),
+            e => format!("YouTubeに接続できません: {}", e),
+        })?;
+
+    let caption: serde_json::Value = serde_json::from_str(&response.into_string()?)?;
+    println!(
+        "字幕をアップロードしました: {}",
+        caption["id"].as_str().unwrap_or("")
+    );
+    Ok(())
+}
+
+/// メタデータと字幕をmultipart/relatedの本文にする
+fn multipart_body(metadata: &str, caption: &[u8]) -> Vec<u8> {
+    let mut body = format!(
+        "--{0}\r\nContent-Type: application/json; charset=UTF-8\r\n\r\n{1}\r\n\
+         --{0}\r\nContent-Type: application/octet-stream\r\n\r\n",
+        BOUNDARY, metadata
+    )
+    .into_bytes();
+    body.extend_from_slice(caption);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+    body
+}
+
+#[test]
+fn test_multipart_body() {
+    let body = String::from_utf8(multipart_body("{}", b"1\n")).unwrap();
+
+    assert!(body.starts_with("--voicepeak-srt-boundary\r\nContent-Type: application/json"));
+    assert!(body.contains("\r\n\r\n{}\r\n--voicepeak-srt-boundary\r\n"));
+    assert!(body.ends_with("\r\n\r\n1\n\r\n--voicepeak-srt-boundary--\r\n"));
+}
@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use voicepeak_srt_core::{
+    parse::{self, SubtitleFormat},
+    validate::{validate, ValidateOptions},
+};
+
+/// 既存の字幕ファイルを検査する
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    /// 検査する字幕ファイル(srtまたはvtt)
+    path: PathBuf,
+    /// 入力形式(省略時は拡張子と内容から判定)
+    #[arg(short, long, value_enum)]
+    format: Option<InputFormat>,
+    /// 1秒あたりの最大文字数(省略時は検査しない)
+    #[arg(long)]
+    max_cps: Option<f64>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum InputFormat {
+    Srt,
+    Vtt,
+}
+
+pub fn run(args: &ValidateArgs) -> crate::CliResult {
+    let blocks = match args.format {
+        Some(format) => {
+            let text = std::fs::read_to_string(&args.path)?;
+            let format = match format {
+                InputFormat::Srt => SubtitleFormat::Srt,
+                InputFormat::Vtt => SubtitleFormat::Vtt,
+            };
+            parse::parse_subtitles(&text, format)?
+        }
+        None => parse::read_subtitle_file(&args.path)?,
+    };
+
+    // VTTの識別子は任意のため番号は検査しない
+    let is_vtt = matches!(args.format, Some(InputFormat::Vtt))
+        || (args.format.is_none()
+            && SubtitleFormat::from_path(&args.path) == Some(SubtitleFormat::Vtt));
+    let options = ValidateOptions::new()
+        .max_cps(args.max_cps)
+        .check_numbering(!is_vtt);
+
+    let issues = validate(&blocks, &options);
+    for issue in &issues {
+        println!("{}", issue);
+    }
+
+    if issues.is_empty() {
+        println!("{}ブロックを検査しました。問題はありません", blocks.len());
+        Ok(())
+    } else {
+        Err(format!("{}件の問題が見つかりました", issues.len()).into())
+    }
+}
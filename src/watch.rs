@@ -0,0 +1,193 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use clap::Args;
+use voicepeak_srt_core::{preset::InputPreset, write_subtitle_file, SubtitleBuilder};
+
+use crate::presets;
+
+/// フォルダーを見張り、音声が書き出されるたびに字幕を作り直す(Ctrl+Cで終わる)
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    /// 見張るフォルダー(複数指定できる)
+    #[arg(required = true)]
+    folders: Vec<PathBuf>,
+    /// 各フォルダーに書き出す字幕のファイル名(拡張子で形式を決める)
+    #[arg(short, long, default_value = "subtitles.srt")]
+    output_name: String,
+    /// 入力のプリセット
+    #[arg(long, default_value = "voicepeak")]
+    preset: String,
+    /// 独自のプリセットを書いたJSON
+    #[arg(long)]
+    preset_file: Option<PathBuf>,
+    /// フォルダーを確かめる間隔(ミリ秒)
+    #[arg(long, default_value_t = 1000)]
+    interval_ms: u64,
+    /// 最後の変更からこれだけ経ってから作り直す(書き出しの途中で作らないため、ミリ秒)
+    #[arg(long, default_value_t = 3000)]
+    settle_ms: u64,
+    /// 作り直すたびにデスクトップに通知する
+    #[arg(long)]
+    notify: bool,
+}
+
+/// フォルダーのwavとtxtの名前・大きさ・更新日時
+type Fingerprint = Vec<(PathBuf, u64, Option<SystemTime>)>;
+
+/// 見張っているフォルダーの状態
+struct Folder {
+    path: PathBuf,
+    /// 最後に見た内容と、それに変わった時点
+    seen: Option<(Fingerprint, Instant)>,
+    /// 字幕を作った時点の内容
+    generated: Option<Fingerprint>,
+}
+
+pub fn run(args: &WatchArgs) -> crate::CliResult {
+    let preset = presets::find(&args.preset, args.preset_file.as_deref())?;
+    let settle = Duration::from_millis(args.settle_ms);
+    let mut folders: Vec<Folder> = args
+        .folders
+        .iter()
+        .map(|path| Folder {
+            path: path.clone(),
+            seen: None,
+            generated: None,
+        })
+        .collect();
+    eprintln!(
+        "{}個のフォルダーを見張っています(Ctrl+Cで終わります)",
+        folders.len()
+    );
+
+    loop {
+        for folder in &mut folders {
+            let current = fingerprint(&folder.path);
+            if folder.seen.as_ref().map(|(seen, _)| seen) != Some(&current) {
+                folder.seen = Some((current, Instant::now()));
+            }
+            let Some((seen, changed_at)) = &folder.seen else {
+                continue;
+            };
+            // 書き出しが落ち着いてから、wavがあるときだけ作り直す
+            let has_wav = seen.iter().any(|(path, _, _)| is_wav(path));
+            if folder.generated.as_ref() == Some(seen) || changed_at.elapsed() < settle || !has_wav
+            {
+                continue;
+            }
+            folder.generated = Some(seen.clone());
+            regenerate(args, &preset, &folder.path);
+        }
+        thread::sleep(Duration::from_millis(args.interval_ms));
+    }
+}
+
+/// フォルダーの字幕を作り直して結果を知らせる(失敗しても見張りは続ける)
+fn regenerate(args: &WatchArgs, preset: &InputPreset, folder: &Path) {
+    let output = folder.join(&args.output_name);
+    let result = SubtitleBuilder::from_dir(folder)
+        .preset(preset)
+        .build()
+        .and_then(|blocks| {
+            let count = blocks.len();
+            write_subtitle_file(blocks, &output)?;
+            Ok(count)
+        });
+    let (title, message) = match result {
+        Ok(count) => {
+            let message = format!("{}ブロック: {}", count, output.display());
+            eprintln!("字幕を作り直しました({})", message);
+            ("字幕を作り直しました", message)
+        }
+        Err(e) => {
+            let message = format!("{}: {}", folder.display(), e);
+            eprintln!("エラー: {}", message);
+            ("字幕を作れませんでした", message)
+        }
+    };
+    if args.notify {
+        notify(title, &message);
+    }
+}
+
+/// フォルダーのwavとtxtの一覧(読めなければ空)
+fn fingerprint(folder: &Path) -> Fingerprint {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let mut files: Fingerprint = entries
+        .filter_map(Result::ok)
+        .filter(|entry| is_wav(&entry.path()) || entry.path().extension() == Some("txt".as_ref()))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.path(), metadata.len(), metadata.modified().ok()))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn is_wav(path: &Path) -> bool {
+    path.extension() == Some("wav".as_ref())
+}
+
+/// デスクトップに通知する(通知できなくても続ける)
+fn notify(title: &str, message: &str) {
+    let mut command = if cfg!(target_os = "windows") {
+        // PowerShellの文字列ではシングルクォートを重ねてエスケープする
+        let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+        let script = format!(
+            "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+             $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+             $texts = $xml.GetElementsByTagName('text'); \
+             $texts[0].AppendChild($xml.CreateTextNode({})) > $null; \
+             $texts[1].AppendChild($xml.CreateTextNode({})) > $null; \
+             [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('voicepeak-srt').Show([Windows.UI.Notifications.ToastNotification]::new($xml))",
+            quote(title),
+            quote(message)
+        );
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command", &script]);
+        command
+    } else if cfg!(target_os = "macos") {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!(
+            "display notification {} with title {}",
+            quote(message),
+            quote(title)
+        );
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args([title, message]);
+        command
+    };
+    if let Err(e) = command.output() {
+        eprintln!("警告: 通知できません: {}", e);
+    }
+}
+
+#[test]
+fn test_fingerprint() {
+    let dir = std::env::temp_dir().join(format!("voicepeak-srt-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("000-voice.txt"), "a").unwrap();
+    fs::write(dir.join("subtitles.srt"), "").unwrap();
+    let before = fingerprint(&dir);
+    fs::write(dir.join("000-voice.txt"), "ab").unwrap();
+    let after = fingerprint(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+
+    // 字幕などwavとtxt以外のファイルは見ない
+    assert_eq!(1, before.len());
+    assert_ne!(before, after);
+    assert!(fingerprint(&dir).is_empty());
+}
@@ -0,0 +1,60 @@
+//! tokio向けの非同期API(`async`フィーチャー)
+//!
+//! wavの読み込みとタイミング計算はブロッキング用のスレッドで行い、executorのスレッドを塞がない
+
+use std::{io, path::PathBuf};
+
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{write_srt, Result, SrtBlock, SubtitleBuilder};
+
+/// ディレクトリ内のwavとtxtから字幕ブロックを作成する
+pub async fn make_srt_blocks_async<P: Into<PathBuf>>(path: P) -> Result<Vec<SrtBlock>> {
+    let path = path.into();
+    tokio::task::spawn_blocking(move || SubtitleBuilder::from_dir(path).build())
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// 字幕ブロックをsrt形式で非同期に書き出す
+pub async fn write_srt_async<W: AsyncWrite + Unpin>(
+    srt_blocks: Vec<SrtBlock>,
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut buf = Vec::new();
+    write_srt(srt_blocks, &mut buf)?;
+    writer.write_all(&buf).await?;
+    writer.flush().await
+}
+
+/// ディレクトリ内のwavとtxtからsrtファイルを非同期に作成する
+pub async fn generate_srt_async<P, Q>(input_path: P, output_path: Q) -> Result<()>
+where
+    P: Into<PathBuf>,
+    Q: Into<PathBuf>,
+{
+    let blocks = make_srt_blocks_async(input_path).await?;
+    let mut file = tokio::fs::File::create(output_path.into()).await?;
+    write_srt_async(blocks, &mut file).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_make_srt_blocks_async() {
+    let blocks = make_srt_blocks_async("../voice").await.unwrap();
+    assert_eq!(4, blocks.len());
+
+    let mut out = Vec::new();
+    write_srt_async(blocks, &mut out).await.unwrap();
+    assert_eq!(
+        std::fs::read_to_string("../subtitles.srt").unwrap(),
+        String::from_utf8(out).unwrap()
+    );
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_make_srt_blocks_async_no_exits_path() {
+    assert!(make_srt_blocks_async("no/exits/path/").await.is_err());
+}
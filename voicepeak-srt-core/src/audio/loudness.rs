@@ -0,0 +1,129 @@
+use std::f64::consts::PI;
+
+use super::WavFormat;
+
+/// ラウドネスの正規化(EBU R128)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum Loudness {
+    /// 音声ごとに目標のラウドネス(LUFS)に揃える
+    Clip(f64),
+    /// 全体が目標のラウドネス(LUFS)になるように同じ量だけ上げ下げする
+    Program(f64),
+}
+
+/// ITU-R BS.1770のKフィルター(高域シェルフ→ハイパス)
+struct KFilter {
+    coeffs: [([f64; 3], [f64; 3]); 2],
+    state: Vec<[[f64; 2]; 2]>,
+}
+
+impl KFilter {
+    fn new(format: WavFormat) -> Self {
+        let rate = format.sampling_rate as f64;
+
+        // 係数は48kHz以外でも同じ特性になるようにサンプリングレートから求める
+        let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (PI * f0 / rate).tan();
+        let vh = 10f64.powf(gain / 20.);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1. + k / q + k * k;
+        let shelf = (
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2. * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [1., 2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+        );
+
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (PI * f0 / rate).tan();
+        let a0 = 1. + k / q + k * k;
+        let high_pass = (
+            [1., -2., 1.],
+            [1., 2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+        );
+
+        Self {
+            coeffs: [shelf, high_pass],
+            state: vec![[[0.; 2]; 2]; format.channels as usize],
+        }
+    }
+
+    fn process(&mut self, channel: usize, mut x: f64) -> f64 {
+        for ((b, a), s) in self.coeffs.iter().zip(&mut self.state[channel]) {
+            let y = b[0] * x + s[0];
+            s[0] = b[1] * x - a[1] * y + s[1];
+            s[1] = b[2] * x - a[2] * y;
+            x = y;
+        }
+        x
+    }
+}
+
+/// 400msのブロックごとの平均二乗(75%ずつ重ねる)を求める
+pub(crate) fn block_powers(samples: &[i16], format: WavFormat) -> Vec<f64> {
+    let channels = format.channels as usize;
+    let mut filter = KFilter::new(format);
+    let squares: Vec<f64> = samples
+        .chunks_exact(channels)
+        .map(|frame| {
+            frame
+                .iter()
+                .enumerate()
+                .map(|(c, &s)| filter.process(c, s as f64 / 32768.).powi(2))
+                .sum()
+        })
+        .collect();
+
+    let block = (format.sampling_rate as usize * 4) / 10;
+    let step = block / 4;
+    if block == 0 || squares.len() < block {
+        return Vec::new();
+    }
+    (0..=(squares.len() - block) / step)
+        .map(|i| squares[i * step..i * step + block].iter().sum::<f64>() / block as f64)
+        .collect()
+}
+
+/// ブロックの平均二乗からゲートをかけた統合ラウドネス(LUFS)を求める
+pub(crate) fn gated_loudness(powers: &[f64]) -> Option<f64> {
+    let lufs = |power: f64| -0.691 + 10. * power.log10();
+    let mean = |threshold: f64| {
+        let gated: Vec<f64> = powers
+            .iter()
+            .copied()
+            .filter(|&p| lufs(p) > threshold)
+            .collect();
+        (!gated.is_empty()).then(|| gated.iter().sum::<f64>() / gated.len() as f64)
+    };
+
+    // 無音に近いブロック(-70LUFS以下)を除き、さらに平均から10LU低いブロックを除く
+    let relative = lufs(mean(-70.)?) - 10.;
+    mean(relative.max(-70.)).map(lufs)
+}
+
+/// 統合ラウドネス(LUFS)を求める(短すぎるか無音の場合は`None`)
+pub fn integrated_loudness(samples: &[i16], format: WavFormat) -> Option<f64> {
+    gated_loudness(&block_powers(samples, format))
+}
+
+#[test]
+fn test_integrated_loudness() {
+    // 1kHz・-20dBFSの正弦波はおよそ-23LUFSになる
+    let format = WavFormat {
+        sampling_rate: 48000,
+        channels: 1,
+    };
+    let samples: Vec<i16> = (0..48000 * 2)
+        .map(|i| {
+            let t = i as f64 / 48000.;
+            ((2. * PI * 1000. * t).sin() * 0.1 * 32767.) as i16
+        })
+        .collect();
+    let loudness = integrated_loudness(&samples, format).unwrap();
+    assert!((loudness + 23.).abs() < 0.3, "{}", loudness);
+
+    assert_eq!(None, integrated_loudness(&vec![0; 48000], format));
+}
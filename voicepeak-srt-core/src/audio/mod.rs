@@ -0,0 +1,516 @@
+//! 音声の書き出し
+
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::{duration::read_wav, Error, InputSource, Result, SrtBlock};
+
+mod loudness;
+mod peaks;
+mod resample;
+mod silence;
+
+pub use loudness::{integrated_loudness, Loudness};
+pub use peaks::Peaks;
+pub use silence::SilenceCheck;
+
+/// 字幕と同じ時間軸に置かれた1つの音声
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Clip {
+    /// wavのパス
+    pub path: PathBuf,
+    /// 開始時刻
+    pub start: Duration,
+    /// 長さ
+    pub duration: Duration,
+}
+
+impl Clip {
+    /// 音声を作成する
+    pub fn new<P: Into<PathBuf>>(path: P, start: Duration, duration: Duration) -> Self {
+        Self {
+            path: path.into(),
+            start,
+            duration,
+        }
+    }
+}
+
+/// 編集済みの字幕の開始時刻に音声を置き直す
+///
+/// `clips`と`cues`は同じ順に対応させる。音声が次の字幕の開始時刻を越える場合は重ねて書き出される
+pub fn align_to_cues(clips: &[Clip], cues: &[SrtBlock]) -> Result<Vec<Clip>> {
+    if clips.len() != cues.len() {
+        return Err(Error::CueCountMismatch {
+            cues: cues.len(),
+            clips: clips.len(),
+        });
+    }
+
+    Ok(clips
+        .iter()
+        .zip(cues)
+        .map(|(clip, cue)| Clip::new(&clip.path, cue.start, clip.duration))
+        .collect())
+}
+
+/// 字幕ごとに書き出す音声のファイル名(`HH-MM-SS_mmm__テキスト.wav`)
+///
+/// ファイル名に使えない文字と改行は取り除き、テキストは先頭の40文字までにする
+pub fn cue_file_name(start: Duration, text: &str) -> String {
+    let secs = start.as_secs();
+    let text: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .filter(|c| !matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .take(40)
+        .collect();
+    format!(
+        "{:02}-{:02}-{:02}_{:03}__{}.wav",
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60,
+        start.subsec_millis(),
+        text.trim_end()
+    )
+}
+
+/// 書き出すwavの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavFormat {
+    /// サンプリングレート
+    pub sampling_rate: u32,
+    /// チャンネル数
+    pub channels: u16,
+}
+
+impl WavFormat {
+    /// 時刻をフレーム位置にする
+    pub fn frames(&self, time: Duration) -> u64 {
+        (time.as_secs_f64() * self.sampling_rate as f64).round() as u64
+    }
+}
+
+/// 16bit PCMのwavのヘッダーを書き出す
+pub fn write_wav_header<W: Write>(out: &mut W, format: WavFormat, frames: u64) -> io::Result<()> {
+    let block_align = format.channels as u32 * 2;
+    let data_len = u32::try_from(frames * block_align as u64)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "wavの上限(4GB)を超えています"))?;
+
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&wav::WAV_FORMAT_PCM.to_le_bytes())?;
+    out.write_all(&format.channels.to_le_bytes())?;
+    out.write_all(&format.sampling_rate.to_le_bytes())?;
+    out.write_all(&(format.sampling_rate * block_align).to_le_bytes())?;
+    out.write_all(&(block_align as u16).to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())
+}
+
+/// 音量を`gain`dB上げ下げする(範囲を超えたサンプルは切り詰める)
+fn apply_gain(samples: &mut [i16], gain: f64) {
+    let factor = 10f64.powf(gain / 20.);
+    for sample in samples {
+        *sample = (*sample as f64 * factor)
+            .round()
+            .clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+    }
+}
+
+/// 音声の先頭・末尾を`frames`フレームかけて直線的にフェードする
+fn fade_edges(
+    samples: &mut [i16],
+    format: WavFormat,
+    frames: usize,
+    fade_in: bool,
+    fade_out: bool,
+) {
+    let channels = format.channels as usize;
+    let len = samples.len() / channels;
+    // 短い音声では半分までにとどめる
+    let frames = frames.min(len / 2);
+    for i in 0..frames {
+        let factor = (i as f64 + 0.5) / frames as f64;
+        for c in 0..channels {
+            if fade_in {
+                let s = &mut samples[i * channels + c];
+                *s = (*s as f64 * factor).round() as i16;
+            }
+            if fade_out {
+                let s = &mut samples[(len - 1 - i) * channels + c];
+                *s = (*s as f64 * factor).round() as i16;
+            }
+        }
+    }
+}
+
+/// 開始位置に音声を重ねながら、確定した先頭から順に書き出す
+struct Mixer {
+    format: WavFormat,
+    total: u64,
+    /// 書き出し済みのフレーム数(`buf`の先頭の位置)
+    written: u64,
+    buf: Vec<i32>,
+}
+
+impl Mixer {
+    fn new(format: WavFormat, total: u64) -> Self {
+        Self {
+            format,
+            total,
+            written: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    /// `start`フレーム目から音声を重ねる(開始位置の順に呼ぶ)
+    fn add<W: Write>(&mut self, out: &mut W, start: u64, samples: &[i16]) -> io::Result<()> {
+        let channels = self.format.channels as usize;
+
+        // 次の音声が始まるまでの部分は書き出して構わない
+        let ready = (start.saturating_sub(self.written) as usize * channels).min(self.buf.len());
+        self.flush(out, ready)?;
+        if start > self.written {
+            let frames = (start - self.written).min(self.total - self.written);
+            write_silence(out, self.format, frames)?;
+            self.written += frames;
+        }
+
+        // 丸めで書き出し済みの位置より前から始まる分は先頭を削る
+        let skip =
+            ((self.written - start.min(self.written)) as usize * channels).min(samples.len());
+        let samples = &samples[skip..];
+        if self.buf.len() < samples.len() {
+            self.buf.resize(samples.len(), 0);
+        }
+        for (b, &s) in self.buf.iter_mut().zip(samples) {
+            *b += s as i32;
+        }
+        Ok(())
+    }
+
+    /// 先頭の`len`サンプルを書き出す(全体の長さを超える分は捨てる)
+    fn flush<W: Write>(&mut self, out: &mut W, len: usize) -> io::Result<()> {
+        let channels = self.format.channels as usize;
+        let limit = (self.total - self.written) as usize * channels;
+        let samples: Vec<i16> = self.buf[..len.min(limit)]
+            .iter()
+            .map(|&s| s.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+            .collect();
+        write_samples(out, &samples)?;
+        self.written += (samples.len() / channels) as u64;
+        self.buf.drain(..len);
+        Ok(())
+    }
+
+    /// 残りを書き出し、全体の長さまで無音で埋める
+    fn finish<W: Write>(mut self, out: &mut W) -> io::Result<()> {
+        self.flush(out, self.buf.len())?;
+        write_silence(out, self.format, self.total - self.written)
+    }
+}
+
+/// サンプルをリトルエンディアンで書き出す
+pub(crate) fn write_samples<W: Write>(out: &mut W, samples: &[i16]) -> io::Result<()> {
+    let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+    out.write_all(&bytes)
+}
+
+/// 無音を`frames`フレーム分書き出す
+pub(crate) fn write_silence<W: Write>(
+    out: &mut W,
+    format: WavFormat,
+    frames: u64,
+) -> io::Result<()> {
+    let chunk = vec![0; 8192];
+    let mut left = frames * format.channels as u64 * 2;
+    while left > 0 {
+        let n = left.min(chunk.len() as u64) as usize;
+        out.write_all(&chunk[..n])?;
+        left -= n as u64;
+    }
+    Ok(())
+}
+
+/// 音声をつなげるときの処理
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConcatOptions {
+    loudness: Option<Loudness>,
+    crossfade: Duration,
+    sampling_rate: Option<u32>,
+}
+
+impl ConcatOptions {
+    /// 何もしない設定
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ラウドネスを正規化する
+    pub fn loudness(mut self, loudness: Option<Loudness>) -> Self {
+        self.loudness = loudness;
+        self
+    }
+
+    /// 書き出すサンプリングレート(`None`か0なら最初のwavに合わせる)
+    ///
+    /// レートの異なるwavは変換してからつなげる
+    pub fn sampling_rate(mut self, sampling_rate: Option<u32>) -> Self {
+        self.sampling_rate = sampling_rate.filter(|&rate| rate > 0);
+        self
+    }
+
+    /// つなぎ目を`crossfade`かけてフェードする
+    ///
+    /// [`SubtitleBuilder::crossfade`](crate::SubtitleBuilder::crossfade)と同じ長さにすると、
+    /// 重ねた音声どうしがクロスフェードする
+    pub fn crossfade(mut self, crossfade: Duration) -> Self {
+        self.crossfade = crossfade;
+        self
+    }
+}
+
+/// 音声を開始時刻の位置に並べた1つのwavを書き出す
+///
+/// 音声の間は無音で埋めるため、字幕と同じ時間軸になる。
+/// サンプリングレートは最初のwavに揃え、チャンネル数はすべてのwavで同じでなければならない
+pub fn concat_wav<W: Write>(source: &dyn InputSource, clips: &[Clip], out: W) -> Result<()> {
+    concat_wav_with(source, clips, &ConcatOptions::default(), out)
+}
+
+/// [`concat_wav`]に音量の調整などの処理を加えて書き出す
+pub fn concat_wav_with<W: Write>(
+    source: &dyn InputSource,
+    clips: &[Clip],
+    options: &ConcatOptions,
+    mut out: W,
+) -> Result<()> {
+    let Some(first) = clips.first() else {
+        return Err(Error::MissingWav);
+    };
+
+    let decode_error = |path: &PathBuf| {
+        let path = path.clone();
+        move |source| Error::WavDecode { path, source }
+    };
+    let (header, _) = read_wav(source, &first.path).map_err(decode_error(&first.path))?;
+    let format = WavFormat {
+        sampling_rate: options.sampling_rate.unwrap_or(header.sampling_rate),
+        channels: header.channel_count,
+    };
+
+    // 全体の長さは最後に終わる音声に合わせる
+    let total = clips
+        .iter()
+        .map(|clip| format.frames(clip.start + clip.duration))
+        .max()
+        .unwrap_or(0);
+    write_wav_header(&mut out, format, total)?;
+
+    let mut clips: Vec<&Clip> = clips.iter().collect();
+    clips.sort_by_key(|clip| clip.start);
+
+    let load = |clip: &Clip| {
+        let (header, samples) = read_wav(source, &clip.path).map_err(decode_error(&clip.path))?;
+        if header.channel_count != format.channels {
+            return Err(Error::AudioFormatMismatch {
+                path: clip.path.clone(),
+            });
+        }
+        Ok(resample::resample(
+            &samples,
+            format.channels as usize,
+            header.sampling_rate,
+            format.sampling_rate,
+        ))
+    };
+
+    // 全体で揃える場合は、先にすべての音声を測ってから同じ量だけ上げ下げする
+    let program_gain = match options.loudness {
+        Some(Loudness::Program(target)) => {
+            let mut powers = Vec::new();
+            for clip in &clips {
+                powers.extend(loudness::block_powers(&load(clip)?, format));
+            }
+            loudness::gated_loudness(&powers).map(|measured| target - measured)
+        }
+        _ => None,
+    };
+
+    let last = clips.len() - 1;
+    let mut mixer = Mixer::new(format, total);
+    for (n, clip) in clips.into_iter().enumerate() {
+        let mut samples = load(clip)?;
+        let gain = match options.loudness {
+            Some(Loudness::Clip(target)) => {
+                integrated_loudness(&samples, format).map(|measured| target - measured)
+            }
+            _ => program_gain,
+        };
+        if let Some(gain) = gain {
+            apply_gain(&mut samples, gain);
+        }
+
+        // つなぎ目でクリックが出ないように、前後の音声と接する側をフェードする
+        let fade = format.frames(options.crossfade) as usize;
+        fade_edges(&mut samples, format, fade, n > 0, n < last);
+
+        mixer.add(&mut out, format.frames(clip.start), &samples)?;
+    }
+    mixer.finish(&mut out)?;
+
+    out.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_align_to_cues() {
+    let clips = [
+        Clip::new("1.wav", Duration::ZERO, Duration::from_secs(2)),
+        Clip::new("2.wav", Duration::from_secs(2), Duration::from_secs(1)),
+    ];
+    let cues = [
+        SrtBlock::new(1, Duration::from_secs(1), Duration::from_secs(3), "a"),
+        SrtBlock::new(2, Duration::from_secs(5), Duration::from_secs(6), "b"),
+    ];
+
+    let aligned = align_to_cues(&clips, &cues).unwrap();
+    assert_eq!(Duration::from_secs(1), aligned[0].start);
+    assert_eq!(Duration::from_secs(5), aligned[1].start);
+    assert_eq!(Duration::from_secs(1), aligned[1].duration);
+
+    assert!(matches!(
+        align_to_cues(&clips, &cues[..1]),
+        Err(Error::CueCountMismatch { cues: 1, clips: 2 })
+    ));
+}
+
+#[test]
+fn test_cue_file_name() {
+    assert_eq!(
+        "01-02-03_045__こんにちは 世界.wav",
+        cue_file_name(Duration::from_millis(3723045), "こんにちは\n世界")
+    );
+    assert_eq!(
+        "00-00-01_000__ab.wav",
+        cue_file_name(Duration::from_secs(1), "a/b?")
+    );
+}
+
+#[test]
+fn test_concat_wav() {
+    use std::path::Path;
+
+    let wav_bytes = |samples: Vec<i16>| {
+        let header = wav::Header::new(wav::WAV_FORMAT_PCM, 1, 10, 16);
+        let mut out = io::Cursor::new(Vec::new());
+        wav::write(header, &wav::BitDepth::Sixteen(samples), &mut out).unwrap();
+        out.into_inner()
+    };
+    let mut source = crate::MemorySource::new();
+    source.insert("a.wav", wav_bytes(vec![1; 10]));
+    source.insert("b.wav", wav_bytes(vec![2; 5]));
+    let clips = [
+        Clip::new("a.wav", Duration::ZERO, Duration::from_secs(1)),
+        Clip::new(
+            "b.wav",
+            Duration::from_millis(1500),
+            Duration::from_millis(500),
+        ),
+    ];
+
+    let mut out = io::Cursor::new(Vec::new());
+    concat_wav(&source, &clips, &mut out).unwrap();
+    out.set_position(0);
+    let (header, data) = wav::read(&mut out).unwrap();
+    let samples = data.try_into_sixteen().unwrap();
+
+    assert_eq!(10, header.sampling_rate);
+    assert_eq!(20, samples.len());
+    assert_eq!([1; 10], samples[..10]);
+    assert_eq!([0; 5], samples[10..15]);
+    assert_eq!([2; 5], samples[15..]);
+
+    source.insert("stereo.wav", {
+        let header = wav::Header::new(wav::WAV_FORMAT_PCM, 2, 10, 16);
+        let mut out = io::Cursor::new(Vec::new());
+        wav::write(header, &wav::BitDepth::Sixteen(vec![0; 4]), &mut out).unwrap();
+        out.into_inner()
+    });
+    let clips = [
+        Clip::new("a.wav", Duration::ZERO, Duration::from_secs(1)),
+        Clip::new(
+            Path::new("stereo.wav"),
+            Duration::from_secs(1),
+            Duration::from_millis(200),
+        ),
+    ];
+    assert!(matches!(
+        concat_wav(&source, &clips, io::sink()),
+        Err(Error::AudioFormatMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_concat_wav_crossfade() {
+    let header = wav::Header::new(wav::WAV_FORMAT_PCM, 1, 10, 16);
+    let mut bytes = io::Cursor::new(Vec::new());
+    wav::write(header, &wav::BitDepth::Sixteen(vec![1000; 10]), &mut bytes).unwrap();
+    let mut source = crate::MemorySource::new();
+    source.insert("a.wav", bytes.into_inner());
+
+    // 0.2秒重ねた2つの音声は、重なった部分で足し合わせるとほぼ元の音量になる
+    let clips = [
+        Clip::new("a.wav", Duration::ZERO, Duration::from_secs(1)),
+        Clip::new("a.wav", Duration::from_millis(800), Duration::from_secs(1)),
+    ];
+    let options = ConcatOptions::new().crossfade(Duration::from_millis(200));
+    let mut out = io::Cursor::new(Vec::new());
+    concat_wav_with(&source, &clips, &options, &mut out).unwrap();
+    out.set_position(0);
+    let samples = wav::read(&mut out).unwrap().1.try_into_sixteen().unwrap();
+
+    assert_eq!(18, samples.len());
+    assert_eq!(1000, samples[0]);
+    assert_eq!([1000, 1000], samples[8..10]);
+    assert_eq!(1000, samples[17]);
+}
+
+#[test]
+fn test_concat_wav_resample() {
+    let wav_bytes = |rate: u32, frames: usize| {
+        let header = wav::Header::new(wav::WAV_FORMAT_PCM, 1, rate, 16);
+        let mut out = io::Cursor::new(Vec::new());
+        wav::write(header, &wav::BitDepth::Sixteen(vec![100; frames]), &mut out).unwrap();
+        out.into_inner()
+    };
+    let mut source = crate::MemorySource::new();
+    source.insert("44k.wav", wav_bytes(44100, 4410));
+    source.insert("48k.wav", wav_bytes(48000, 4800));
+
+    // レートの異なるwavも長さを保ったまま最初のwavのレートでつなげる
+    let clips = [
+        Clip::new("44k.wav", Duration::ZERO, Duration::from_millis(100)),
+        Clip::new(
+            "48k.wav",
+            Duration::from_millis(100),
+            Duration::from_millis(100),
+        ),
+    ];
+    let mut out = io::Cursor::new(Vec::new());
+    concat_wav(&source, &clips, &mut out).unwrap();
+    out.set_position(0);
+    let (header, data) = wav::read(&mut out).unwrap();
+    assert_eq!(44100, header.sampling_rate);
+    assert_eq!(8820, data.try_into_sixteen().unwrap().len());
+}
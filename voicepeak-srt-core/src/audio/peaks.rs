@@ -0,0 +1,107 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{duration::read_wav, Error, InputSource, Result};
+
+/// 波形の表示に使うピーク(audiowaveformと同じ形式で書き出せる)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peaks {
+    /// 元の音声のサンプリングレート
+    pub sample_rate: u32,
+    /// 1ピークあたりのフレーム数
+    pub samples_per_pixel: u32,
+    /// ピークごとの最小値と最大値(全チャンネルをまとめる)
+    pub data: Vec<(i16, i16)>,
+}
+
+impl Peaks {
+    /// サンプルから`samples_per_pixel`フレームごとのピークを求める
+    pub fn from_samples(
+        samples: &[i16],
+        channels: u16,
+        sample_rate: u32,
+        samples_per_pixel: u32,
+    ) -> Self {
+        let size = (channels as usize * samples_per_pixel.max(1) as usize).max(1);
+        let data = samples
+            .chunks(size)
+            .map(|chunk| {
+                let min = chunk.iter().copied().min().unwrap_or(0);
+                let max = chunk.iter().copied().max().unwrap_or(0);
+                (min, max)
+            })
+            .collect();
+        Self {
+            sample_rate,
+            samples_per_pixel: samples_per_pixel.max(1),
+            data,
+        }
+    }
+
+    /// wavを読み込んでピークを求める
+    pub fn from_wav(source: &dyn InputSource, path: &Path, samples_per_pixel: u32) -> Result<Self> {
+        let (header, samples) = read_wav(source, path).map_err(|source| Error::WavDecode {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self::from_samples(
+            &samples,
+            header.channel_count,
+            header.sampling_rate,
+            samples_per_pixel,
+        ))
+    }
+
+    /// audiowaveformのJSON形式(version 2・16bit)で書き出す
+    pub fn write_json<W: Write>(&self, out: W) -> serde_json::Result<()> {
+        let data: Vec<i16> = self
+            .data
+            .iter()
+            .flat_map(|&(min, max)| [min, max])
+            .collect();
+        serde_json::to_writer(
+            out,
+            &serde_json::json!({
+                "version": 2,
+                "channels": 1,
+                "sample_rate": self.sample_rate,
+                "samples_per_pixel": self.samples_per_pixel,
+                "bits": 16,
+                "length": self.data.len(),
+                "data": data,
+            }),
+        )
+    }
+
+    /// audiowaveformのバイナリ形式(.dat、version 1・16bit)で書き出す
+    pub fn write_dat<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(&1i32.to_le_bytes())?;
+        out.write_all(&0u32.to_le_bytes())?;
+        out.write_all(&(self.sample_rate as i32).to_le_bytes())?;
+        out.write_all(&(self.samples_per_pixel as i32).to_le_bytes())?;
+        out.write_all(&(self.data.len() as u32).to_le_bytes())?;
+        for (min, max) in &self.data {
+            out.write_all(&min.to_le_bytes())?;
+            out.write_all(&max.to_le_bytes())?;
+        }
+        out.flush()
+    }
+}
+
+#[test]
+fn test_peaks() {
+    let peaks = Peaks::from_samples(&[1, -2, 3, 4, -5, 0, 7], 1, 10, 3);
+    assert_eq!(vec![(-2, 3), (-5, 4), (7, 7)], peaks.data);
+
+    let mut dat = Vec::new();
+    peaks.write_dat(&mut dat).unwrap();
+    assert_eq!(20 + 3 * 4, dat.len());
+
+    let mut json = Vec::new();
+    peaks.write_json(&mut json).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(3, json["length"]);
+    assert_eq!(-2, json["data"][0]);
+}
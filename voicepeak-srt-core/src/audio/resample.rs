@@ -0,0 +1,56 @@
+use std::f64::consts::PI;
+
+/// 片側のタップ数
+const TAPS: usize = 16;
+
+/// 窓付きsinc補間でサンプリングレートを変換する
+pub(crate) fn resample(samples: &[i16], channels: usize, from: u32, to: u32) -> Vec<i16> {
+    if from == to {
+        return samples.to_vec();
+    }
+
+    let frames = samples.len() / channels;
+    let ratio = from as f64 / to as f64;
+    let out_frames = (frames as f64 / ratio).round() as usize;
+
+    // 下げる場合は折り返しを防ぐため、変換先のナイキスト周波数で帯域を制限する
+    let cutoff = (1. / ratio).min(1.);
+    let half = (TAPS as f64 / cutoff).ceil() as isize;
+    let kernel = |x: f64| {
+        if x.abs() >= half as f64 {
+            return 0.;
+        }
+        let sinc = match x * cutoff {
+            0. => 1.,
+            y => (PI * y).sin() / (PI * y),
+        };
+        let window = 0.5 + 0.5 * (PI * x / half as f64).cos();
+        cutoff * sinc * window
+    };
+
+    let mut out = Vec::with_capacity(out_frames * channels);
+    for j in 0..out_frames {
+        let t = j as f64 * ratio;
+        let center = t.floor() as isize;
+        for c in 0..channels {
+            let mut sum = 0.;
+            for k in (center - half + 1).max(0)..=(center + half).min(frames as isize - 1) {
+                sum += samples[k as usize * channels + c] as f64 * kernel(t - k as f64);
+            }
+            out.push(sum.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16);
+        }
+    }
+    out
+}
+
+#[test]
+fn test_resample() {
+    // 長さはレートの比で変わり、一定の音はそのまま保たれる
+    let samples = vec![1000; 441 * 2];
+    let out = resample(&samples, 2, 44100, 48000);
+    assert_eq!(480 * 2, out.len());
+    assert!(out[200..800].iter().all(|&s| (s - 1000).abs() <= 2));
+
+    let out = resample(&samples, 2, 44100, 22050);
+    assert_eq!(221 * 2, out.len());
+}
@@ -0,0 +1,76 @@
+/// 無音かどうかを判定する区間のサンプル数(44.1kHzで約10ミリ秒)
+const WINDOW: usize = 441;
+
+/// ほとんど無音の音声(書き出しに失敗した音声など)を見つけるための基準
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct SilenceCheck {
+    /// これより小さい音量(dBFS)の区間を無音とみなす
+    pub threshold_db: f64,
+    /// 無音の区間がこの割合以上なら警告する
+    pub max_ratio: f64,
+}
+
+impl SilenceCheck {
+    /// `threshold_db`より小さい区間を無音とみなす
+    pub fn new(threshold_db: f64) -> Self {
+        Self {
+            threshold_db,
+            ..Self::default()
+        }
+    }
+
+    /// 警告する無音の割合を設定する
+    pub fn max_ratio(mut self, max_ratio: f64) -> Self {
+        self.max_ratio = max_ratio;
+        self
+    }
+
+    /// 無音の区間の割合と最大振幅を求める(すべて0ならデジタル無音)
+    pub fn measure(&self, samples: &[i16]) -> (f64, u16) {
+        let threshold = (i16::MAX as f64 * 10f64.powf(self.threshold_db / 20.)) as u16;
+        let peaks: Vec<u16> = samples
+            .chunks(WINDOW)
+            .map(|window| window.iter().map(|s| s.unsigned_abs()).max().unwrap_or(0))
+            .collect();
+        if peaks.is_empty() {
+            return (1., 0);
+        }
+        let silent = peaks.iter().filter(|&&peak| peak < threshold).count();
+        let peak = peaks.iter().copied().max().unwrap_or(0);
+        (silent as f64 / peaks.len() as f64, peak)
+    }
+
+    /// ほとんど無音なら、無音の割合と最大振幅を返す
+    pub fn check(&self, samples: &[i16]) -> Option<(f64, u16)> {
+        let (ratio, peak) = self.measure(samples);
+        (ratio >= self.max_ratio).then_some((ratio, peak))
+    }
+}
+
+impl Default for SilenceCheck {
+    /// -60dBFSより小さい区間が95%以上なら警告する
+    fn default() -> Self {
+        Self {
+            threshold_db: -60.,
+            max_ratio: 0.95,
+        }
+    }
+}
+
+#[test]
+fn test_silence_check() {
+    let check = SilenceCheck::default();
+    let voice: Vec<i16> = (0..44100)
+        .map(|i| ((i as f64 / 10.).sin() * 8000.) as i16)
+        .collect();
+    let mut mostly_silent = vec![0i16; 44100];
+    mostly_silent[100] = 8000;
+
+    assert_eq!(None, check.check(&voice));
+    assert_eq!(Some((1., 0)), check.check(&[0; 44100]));
+    assert_eq!(
+        Some(8000),
+        check.check(&mostly_silent).map(|(_, peak)| peak)
+    );
+}
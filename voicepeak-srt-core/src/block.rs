@@ -0,0 +1,75 @@
+use std::{path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// 字幕1ブロック分の情報
+///
+/// 時刻は`Duration`で保持し、文字列への整形は書き出し時にのみ行う
+///
+/// 今後フィールドが増えても互換性を保てるよう、ライブラリの外では`SrtBlock::new`で作成する
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SrtBlock {
+    /// 字幕番号(1始まり)
+    pub index: usize,
+    /// 開始時刻
+    pub start: Duration,
+    /// 終了時刻
+    pub end: Duration,
+    /// 表示するテキスト
+    pub text: String,
+    /// 話者(ファイル名から取り出せた場合のみ)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// 訳(2か国語の字幕で、元のテキストの下に表示する)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<String>,
+    /// 感情やスタイル(ファイル名か.vppから分かった場合のみ)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emotion: Option<String>,
+    /// 元になった音声のパス(音声から作成した場合のみ。書き出すファイルには含めない)
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+impl SrtBlock {
+    /// 字幕ブロックを作成する
+    pub fn new<S: Into<String>>(index: usize, start: Duration, end: Duration, text: S) -> Self {
+        Self {
+            index,
+            start,
+            end,
+            text: text.into(),
+            speaker: None,
+            translation: None,
+            emotion: None,
+            source: None,
+        }
+    }
+
+    /// 訳があれば元のテキストの下に重ねたテキスト(訳を別に扱えない形式で使う)
+    pub fn stacked_text(&self) -> String {
+        match &self.translation {
+            Some(translation) => format!("{}\n{}", self.text.trim_end(), translation),
+            None => self.text.clone(),
+        }
+    }
+
+    /// ブロックの表示時間
+    pub fn duration(&self) -> Duration {
+        self.end.saturating_sub(self.start)
+    }
+}
+
+/// 書き出したファイルから読み込み直しても同じになるよう、元の音声のパスは比べない
+impl PartialEq for SrtBlock {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+            && self.start == other.start
+            && self.end == other.end
+            && self.text == other.text
+            && self.speaker == other.speaker
+            && self.translation == other.translation
+            && self.emotion == other.emotion
+    }
+}
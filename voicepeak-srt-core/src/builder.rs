@@ -0,0 +1,581 @@
+use std::{io::Write, iter, path::Path, time::Duration};
+
+use crate::{
+    audio::{Clip, SilenceCheck},
+    diagnostics::{Diagnostics, WarningSink},
+    preset::InputPreset,
+    scan,
+    screenplay::ScreenplayLine,
+    timing::ClipSink,
+    transform::{self, Hooks, Transform},
+    validate::SpeechRate,
+    DirSource, DuplicatePolicy, DurationProvider, EmptyTextPolicy, Error, FilePattern, InputSource,
+    MultilinePolicy, OutputFile, Result, SrtBlock, SrtBlocks, SrtWriter, Strictness,
+    SubtitleWriter, TxtEncoding, WavDurationProvider,
+};
+
+/// 入力→変換→書き出しを組み立てるためのビルダー
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use voicepeak_srt_core::SubtitleBuilder;
+///
+/// SubtitleBuilder::from_dir("./voice")
+///     .offset(Duration::from_secs(5))
+///     .wrap(20)
+///     .write_file("./subtitles.srt")
+///     .unwrap();
+/// ```
+pub struct SubtitleBuilder {
+    input: Input,
+    provider: Box<dyn DurationProvider>,
+    pattern: FilePattern,
+    strictness: Strictness,
+    on_warning: WarningSink,
+    empty_text: EmptyTextPolicy,
+    multiline: MultilinePolicy,
+    encoding: TxtEncoding,
+    duplicates: DuplicatePolicy,
+    normalize: bool,
+    speech_rate: Option<SpeechRate>,
+    silence: Option<SilenceCheck>,
+    on_clip: Option<ClipSink>,
+    gap: Duration,
+    crossfade: Duration,
+    screenplay: Option<Vec<ScreenplayLine>>,
+    hooks: Hooks,
+    start_index: Option<usize>,
+    /// 入力のディレクトリ(音声の長さを並列に取得するときに使う)
+    #[cfg(feature = "parallel")]
+    dir: Option<std::path::PathBuf>,
+    #[cfg(feature = "parallel")]
+    parallel: Option<std::sync::Arc<dyn DurationProvider + Send + Sync>>,
+}
+
+enum Input {
+    Source(Box<dyn InputSource>),
+    Blocks(Vec<SrtBlock>),
+}
+
+impl SubtitleBuilder {
+    /// Voicepeakの書き出しディレクトリを入力にする
+    pub fn from_dir<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            #[cfg(feature = "parallel")]
+            dir: Some(path.as_ref().to_path_buf()),
+            ..Self::from_source(DirSource::new(path))
+        }
+    }
+
+    /// 任意の入力元を入力にする
+    pub fn from_source<S: InputSource + 'static>(source: S) -> Self {
+        Self {
+            input: Input::Source(Box::new(source)),
+            provider: Box::new(WavDurationProvider),
+            pattern: FilePattern::default(),
+            strictness: Strictness::Normal,
+            on_warning: Box::new(|_| {}),
+            empty_text: EmptyTextPolicy::default(),
+            multiline: MultilinePolicy::default(),
+            encoding: TxtEncoding::default(),
+            duplicates: DuplicatePolicy::default(),
+            normalize: false,
+            speech_rate: None,
+            silence: None,
+            on_clip: None,
+            gap: Duration::ZERO,
+            crossfade: Duration::ZERO,
+            screenplay: None,
+            hooks: Hooks::new(),
+            start_index: None,
+            #[cfg(feature = "parallel")]
+            dir: None,
+            #[cfg(feature = "parallel")]
+            parallel: None,
+        }
+    }
+
+    /// 作成済みのブロックを入力にする
+    pub fn from_blocks(blocks: Vec<SrtBlock>) -> Self {
+        Self {
+            input: Input::Blocks(blocks),
+            provider: Box::new(WavDurationProvider),
+            pattern: FilePattern::default(),
+            strictness: Strictness::Normal,
+            on_warning: Box::new(|_| {}),
+            empty_text: EmptyTextPolicy::default(),
+            multiline: MultilinePolicy::default(),
+            encoding: TxtEncoding::default(),
+            duplicates: DuplicatePolicy::default(),
+            normalize: false,
+            speech_rate: None,
+            silence: None,
+            on_clip: None,
+            gap: Duration::ZERO,
+            crossfade: Duration::ZERO,
+            screenplay: None,
+            hooks: Hooks::new(),
+            start_index: None,
+            #[cfg(feature = "parallel")]
+            dir: None,
+            #[cfg(feature = "parallel")]
+            parallel: None,
+        }
+    }
+
+    /// 音声の長さの取得方法を差し替える
+    pub fn duration_provider<P: DurationProvider + 'static>(mut self, provider: P) -> Self {
+        self.provider = Box::new(provider);
+        self
+    }
+
+    /// 音声の長さをCPUのコア数だけ並列に取得する
+    ///
+    /// [`duration_provider`](Self::duration_provider)の代わりに使う。
+    /// ディレクトリを入力にした場合だけ並列にし、それ以外は`provider`で順に取得する。
+    /// 字幕の順番は変わらない
+    #[cfg(feature = "parallel")]
+    pub fn parallel_duration_provider<P: DurationProvider + Send + Sync + 'static>(
+        mut self,
+        provider: P,
+    ) -> Self {
+        self.parallel = Some(std::sync::Arc::new(provider));
+        self
+    }
+
+    /// ファイル名から連番などを取り出すパターンを差し替える
+    pub fn pattern(mut self, pattern: FilePattern) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    /// プリセットのファイル名のパターンとtxtの文字コードを使う
+    pub fn preset(self, preset: &InputPreset) -> Self {
+        self.pattern(preset.pattern.clone())
+            .txt_encoding(preset.encoding)
+    }
+
+    /// 問題が見つかったときの扱いを変える
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    /// 警告(寛容モードで飛ばしたファイルを含む)を受け取る
+    pub fn on_warning<F: FnMut(Error) + 'static>(mut self, f: F) -> Self {
+        self.on_warning = Box::new(f);
+        self
+    }
+
+    /// 空のtxtの扱いを変える
+    pub fn empty_text(mut self, policy: EmptyTextPolicy) -> Self {
+        self.empty_text = policy;
+        self
+    }
+
+    /// 複数行のtxtの扱いを変える(既定は複数行のまま1つのブロックにする)
+    pub fn multiline(mut self, policy: MultilinePolicy) -> Self {
+        self.multiline = policy;
+        self
+    }
+
+    /// txtの文字コードを変える
+    pub fn txt_encoding(mut self, encoding: TxtEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// 同じ連番のファイルが複数あったときの扱いを変える
+    pub fn duplicates(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicates = policy;
+        self
+    }
+
+    /// 読み込んだテキストをNFCに正規化するか
+    ///
+    /// 正規化しない場合、正規化されていないテキストは警告する
+    pub fn normalize_nfc(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// 音声の長さがテキストから推定した長さと大きく違う連番を警告する
+    pub fn check_speech_rate(mut self, rate: Option<SpeechRate>) -> Self {
+        self.speech_rate = rate;
+        self
+    }
+
+    /// ほとんど無音の音声(書き出しに失敗した音声など)を警告する
+    ///
+    /// すべてのサンプルを読むため、音声が多いと時間がかかる
+    pub fn check_silence(mut self, check: Option<SilenceCheck>) -> Self {
+        self.silence = check;
+        self
+    }
+
+    /// 時刻を決めた音声を受け取る(字幕と同じ時間軸で音声を書き出すときに使う)
+    ///
+    /// 寛容モードでテキストを飛ばした連番の音声も含まれる
+    pub fn on_clip<F: FnMut(Clip) + 'static>(mut self, f: F) -> Self {
+        self.on_clip = Some(Box::new(f));
+        self
+    }
+
+    /// 音声の間に無音を入れる(以降の時刻と`on_clip`の開始時刻にも反映する)
+    pub fn gap(mut self, gap: Duration) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// 音声のつなぎ目を`crossfade`だけ重ねる(無音より長い分だけ後続の時刻が早まる)
+    ///
+    /// 重なった部分は後のブロックの字幕にする。音声は[`ConcatOptions::crossfade`](crate::audio::ConcatOptions::crossfade)で同じ長さを指定して書き出す
+    pub fn crossfade(mut self, crossfade: Duration) -> Self {
+        self.crossfade = crossfade;
+        self
+    }
+
+    /// txtの代わりに台本のセリフと話者を使う(セリフは音声と連番の順に対応させる)
+    pub fn screenplay(mut self, lines: Vec<ScreenplayLine>) -> Self {
+        self.screenplay = Some(lines);
+        self
+    }
+
+    /// ブロックの番号を`start`から振り直す
+    ///
+    /// 指定しなければwavとtxtからは1から振り、作成済みのブロックは元の番号のままにする
+    pub fn start_index(mut self, start: usize) -> Self {
+        self.start_index = Some(start);
+        self
+    }
+
+    /// 全ブロックの時刻をずらす
+    pub fn offset(self, offset: Duration) -> Self {
+        self.map(move |block| transform::offset(block, offset))
+    }
+
+    /// テキストを`width`文字で折り返す
+    pub fn wrap(self, width: usize) -> Self {
+        self.map(move |block| block.text = transform::wrap_text(&block.text, width))
+    }
+
+    /// ブロックを書き換える変換を追加する
+    pub fn map<F: FnMut(&mut SrtBlock) + 'static>(self, mut f: F) -> Self {
+        self.transform(move |mut block: SrtBlock| {
+            f(&mut block);
+            Some(block)
+        })
+    }
+
+    /// 条件を満たすブロックだけを残す
+    pub fn filter<F: FnMut(&SrtBlock) -> bool + 'static>(self, mut f: F) -> Self {
+        self.transform(move |block: SrtBlock| f(&block).then_some(block))
+    }
+
+    /// 任意の変換を追加する
+    ///
+    /// 変換は登録順に適用され、取り除かれたブロックの分だけ後続の番号が詰められる
+    pub fn transform<T: Transform + 'static>(mut self, transform: T) -> Self {
+        self.hooks.register(transform);
+        self
+    }
+
+    /// 変換を適用したブロックを作成する
+    pub fn build(self) -> Result<Vec<SrtBlock>> {
+        self.into_iter().collect()
+    }
+
+    /// 任意の書き出し先へ書き出す
+    pub fn write_with<S: SubtitleWriter + ?Sized>(self, writer: &mut S) -> Result<()> {
+        writer.write_header()?;
+        for block in self {
+            writer.write_cue(&block?)?;
+        }
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// srt形式で書き出す
+    pub fn write<W: Write>(self, writer: &mut W) -> Result<()> {
+        self.write_with(&mut SrtWriter::new(writer))
+    }
+
+    /// srtファイルとして書き出す
+    ///
+    /// 途中でエラーになった場合、既存のファイルは上書きされない
+    pub fn write_file<P: AsRef<Path>>(self, path: P) -> Result<()> {
+        let mut file = OutputFile::create(path)?;
+        self.write_with(&mut SrtWriter::new(&mut file))?;
+        file.commit()?;
+        Ok(())
+    }
+}
+
+impl IntoIterator for SubtitleBuilder {
+    type Item = Result<SrtBlock>;
+    type IntoIter = Box<dyn Iterator<Item = Result<SrtBlock>>>;
+
+    /// 変換を適用しながらブロックを1つずつ作成する
+    ///
+    /// 入力の読み込みに失敗した場合は最初の要素としてエラーを返す
+    fn into_iter(self) -> Self::IntoIter {
+        let blocks: Box<dyn Iterator<Item = Result<SrtBlock>>> = match self.input {
+            Input::Source(source) => {
+                let mut diagnostics = Diagnostics::new(self.strictness, self.on_warning);
+                let files = scan::scan(
+                    source.as_ref(),
+                    &self.pattern,
+                    self.duplicates,
+                    self.screenplay.is_some(),
+                    &mut diagnostics,
+                )
+                .and_then(|files| {
+                    // 台本のセリフは音声と順に対応させるため、数が合わなければエラー
+                    if let Some(lines) = &self.screenplay {
+                        let clips = files
+                            .iter()
+                            .filter(|f| {
+                                f.extension().is_some_and(|ext| ext == "wav")
+                                    && self.pattern.sequence(f).is_some()
+                            })
+                            .count();
+                        if clips != lines.len() {
+                            diagnostics.recover(Error::CueCountMismatch {
+                                cues: lines.len(),
+                                clips,
+                            })?;
+                        }
+                    }
+                    Ok(files)
+                });
+                // ディレクトリの音声の長さは先に並列で取得しておく
+                #[cfg(feature = "parallel")]
+                let provider: Box<dyn DurationProvider> = match self.parallel {
+                    Some(parallel) => {
+                        let prefetch = match (&files, &self.dir) {
+                            (Ok(files), Some(_)) => files.as_slice(),
+                            _ => &[],
+                        };
+                        Box::new(crate::prefetch::PrefetchedDurations::new(
+                            &DirSource::new(self.dir.unwrap_or_default()),
+                            prefetch,
+                            parallel,
+                        ))
+                    }
+                    None => self.provider,
+                };
+                #[cfg(not(feature = "parallel"))]
+                let provider = self.provider;
+                match files {
+                    Ok(files) => Box::new(
+                        SrtBlocks::new(files, source, provider)
+                            .with_pattern(self.pattern)
+                            .with_diagnostics(diagnostics)
+                            .with_empty_text(self.empty_text)
+                            .with_multiline(self.multiline)
+                            .with_encoding(self.encoding)
+                            .with_normalize(self.normalize)
+                            .with_speech_rate(self.speech_rate)
+                            .with_silence_check(self.silence)
+                            .with_clip_sink(self.on_clip)
+                            .with_gap(self.gap)
+                            .with_crossfade(self.crossfade)
+                            .with_screenplay(self.screenplay),
+                    ),
+                    Err(e) => Box::new(iter::once(Err(e))),
+                }
+            }
+            Input::Blocks(blocks) => Box::new(blocks.into_iter().map(Ok)),
+        };
+
+        let mut hooks = self.hooks;
+        // 元の番号から計算すると、振り直したブロックを再び入力にしたときに合わなくなる
+        let mut next_index = self.start_index;
+        Box::new(blocks.filter_map(move |block| match block {
+            Ok(block) => hooks.apply(block).map(|mut block| {
+                if let Some(index) = &mut next_index {
+                    block.index = *index;
+                    *index += 1;
+                }
+                Ok(block)
+            }),
+            Err(e) => Some(Err(e)),
+        }))
+    }
+}
+
+#[test]
+fn test_subtitle_builder() {
+    let blocks = SubtitleBuilder::from_dir("../voice")
+        .offset(Duration::from_secs(1))
+        .wrap(20)
+        .build()
+        .unwrap();
+
+    assert_eq!(4, blocks.len());
+    assert_eq!(Duration::from_secs(1), blocks[0].start);
+    assert!(blocks[1].text.lines().all(|l| l.chars().count() <= 20));
+}
+
+#[test]
+fn test_subtitle_builder_error() {
+    let result = SubtitleBuilder::from_dir("no/exits/path/").build();
+    assert!(matches!(result, Err(crate::Error::PathNotFound(_))));
+}
+
+#[test]
+fn test_subtitle_builder_filter() {
+    let blocks = SubtitleBuilder::from_dir("../voice")
+        .filter(|block| block.index != 2)
+        .build()
+        .unwrap();
+
+    assert_eq!(3, blocks.len());
+    assert_eq!(2, blocks[1].index);
+    assert!(blocks[1].text.starts_with("砂塵"));
+}
+
+#[test]
+fn test_subtitle_builder_lenient() {
+    let mut source = crate::MemorySource::new();
+    for name in ["000-a.wav", "001-a.wav"] {
+        source.insert(name, std::fs::read("../voice/000-voice.wav").unwrap());
+    }
+    source.insert("000-a.txt", b"text".to_vec());
+    let warnings = std::rc::Rc::new(std::cell::RefCell::new(0));
+    let counter = std::rc::Rc::clone(&warnings);
+    let blocks = SubtitleBuilder::from_source(source)
+        .strictness(Strictness::Lenient)
+        .on_warning(move |_| *counter.borrow_mut() += 1)
+        .build()
+        .unwrap();
+
+    assert_eq!(1, blocks.len());
+    assert_eq!(3, *warnings.borrow());
+}
+
+#[test]
+fn test_gap() {
+    let clips = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let blocks = SubtitleBuilder::from_dir("../voice")
+        .gap(Duration::from_millis(500))
+        .on_clip({
+            let clips = std::rc::Rc::clone(&clips);
+            move |clip| clips.borrow_mut().push(clip)
+        })
+        .build()
+        .unwrap();
+
+    let clips = clips.borrow();
+    assert_eq!(Duration::ZERO, blocks[0].start);
+    assert_eq!(blocks[0].end + Duration::from_millis(500), blocks[1].start);
+    for (block, clip) in blocks.iter().zip(clips.iter()) {
+        assert_eq!(block.start, clip.start);
+    }
+}
+
+#[test]
+fn test_crossfade() {
+    let plain = SubtitleBuilder::from_dir("../voice").build().unwrap();
+    let blocks = SubtitleBuilder::from_dir("../voice")
+        .crossfade(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let last = blocks.len() - 1;
+    for (n, (block, plain)) in blocks.iter().zip(&plain).enumerate() {
+        assert_eq!(
+            plain.start,
+            block.start + Duration::from_millis(100) * n as u32
+        );
+        if n < last {
+            assert_eq!(blocks[n + 1].start, block.end);
+        }
+    }
+    assert_eq!(
+        plain[last].end - plain[last].start,
+        blocks[last].end - blocks[last].start
+    );
+}
+
+#[test]
+fn test_screenplay() {
+    let lines = crate::screenplay::parse_screenplay("A: 一\nB: 二\n三\nA: 四");
+    let blocks = SubtitleBuilder::from_dir("../voice")
+        .screenplay(lines.clone())
+        .build()
+        .unwrap();
+
+    assert_eq!(4, blocks.len());
+    assert_eq!("二", blocks[1].text);
+    assert_eq!(Some("B"), blocks[1].speaker.as_deref());
+    assert_eq!(None, blocks[2].speaker);
+
+    let result = SubtitleBuilder::from_dir("../voice")
+        .screenplay(lines[..3].to_vec())
+        .build();
+    assert!(matches!(
+        result,
+        Err(Error::CueCountMismatch { cues: 3, clips: 4 })
+    ));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parallel_duration_provider() {
+    let sequential = SubtitleBuilder::from_dir("../voice").build().unwrap();
+    let parallel = SubtitleBuilder::from_dir("../voice")
+        .parallel_duration_provider(WavDurationProvider)
+        .build()
+        .unwrap();
+
+    assert_eq!(sequential, parallel);
+}
+
+#[test]
+fn test_start_index() {
+    let blocks = SubtitleBuilder::from_dir("../voice")
+        .filter(|block| block.index != 2)
+        .start_index(10)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        vec![10, 11, 12],
+        blocks.iter().map(|b| b.index).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_start_index_from_blocks() {
+    let blocks = SubtitleBuilder::from_dir("../voice")
+        .start_index(0)
+        .build()
+        .unwrap();
+    assert_eq!(0, blocks[0].index);
+
+    // 振り直したブロックを再び入力にしても番号は変わらない
+    let blocks = SubtitleBuilder::from_blocks(blocks).build().unwrap();
+    assert_eq!(
+        vec![0, 1, 2, 3],
+        blocks.iter().map(|b| b.index).collect::<Vec<_>>()
+    );
+    let dropped = SubtitleBuilder::from_blocks(blocks.clone())
+        .filter(|block| block.index != 1)
+        .build()
+        .unwrap();
+    assert_eq!(
+        vec![0, 1, 2],
+        dropped.iter().map(|b| b.index).collect::<Vec<_>>()
+    );
+    let blocks = SubtitleBuilder::from_blocks(blocks)
+        .start_index(0)
+        .filter(|block| block.index != 0)
+        .build()
+        .unwrap();
+    assert_eq!(
+        vec![0, 1, 2],
+        blocks.iter().map(|b| b.index).collect::<Vec<_>>()
+    );
+}
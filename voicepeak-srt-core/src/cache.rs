@@ -0,0 +1,142 @@
+//! 音声の長さのキャッシュ
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{DurationProvider, InputSource, OutputFile};
+
+/// 入力のディレクトリに置くキャッシュのファイル名
+pub const CACHE_FILE_NAME: &str = ".voicepeak-srt.cache";
+
+/// ファイル名・サイズ・更新日時ごとの音声の長さ
+///
+/// 一部のtxtを直したり音声を足したりして作り直すとき、変わっていないwavを読み直さずに済む
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use voicepeak_srt_core::{cache::DurationCache, SubtitleBuilder, WavDurationProvider};
+///
+/// let cache = Arc::new(DurationCache::load("./voice/.voicepeak-srt.cache"));
+/// SubtitleBuilder::from_dir("./voice")
+///     .duration_provider(DurationCache::provider(&cache, WavDurationProvider))
+///     .write_file("./subtitles.srt")
+///     .unwrap();
+/// cache.save().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct DurationCache {
+    path: PathBuf,
+    entries: Mutex<BTreeMap<String, CacheEntry>>,
+}
+
+/// キャッシュの1件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    modified: Duration,
+    duration: Duration,
+}
+
+impl DurationCache {
+    /// キャッシュを読み込む(ないか壊れていれば空にする)
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let entries = File::open(path.as_ref())
+            .ok()
+            .and_then(|file| serde_json::from_reader(io::BufReader::new(file)).ok())
+            .unwrap_or_default();
+        Self {
+            path: path.as_ref().to_path_buf(),
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// キャッシュを書き出す
+    pub fn save(&self) -> io::Result<()> {
+        let mut file = OutputFile::create(&self.path)?;
+        serde_json::to_writer(&mut file, &*self.entries.lock().unwrap())?;
+        file.commit()
+    }
+
+    /// キャッシュになければ`provider`で長さを取得する実装を作る
+    pub fn provider<P: DurationProvider>(cache: &Arc<Self>, provider: P) -> CachedDurations<P> {
+        CachedDurations {
+            cache: Arc::clone(cache),
+            provider,
+        }
+    }
+}
+
+/// キャッシュを使って音声の長さを取得する
+pub struct CachedDurations<P> {
+    cache: Arc<DurationCache>,
+    provider: P,
+}
+
+impl<P: DurationProvider> DurationProvider for CachedDurations<P> {
+    fn duration(&self, source: &dyn InputSource, path: &Path) -> io::Result<Duration> {
+        // 更新日時を取得できない入力元ではキャッシュしない
+        let (Some(name), Ok(modified)) = (path.file_name(), source.modified(path)) else {
+            return self.provider.duration(source, path);
+        };
+        let key = name.to_string_lossy().into_owned();
+        let size = source.open(path)?.seek(SeekFrom::End(0))?;
+        let modified = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        let cached = self.cache.entries.lock().unwrap().get(&key).cloned();
+        if let Some(entry) = cached.filter(|e| e.size == size && e.modified == modified) {
+            return Ok(entry.duration);
+        }
+
+        let duration = self.provider.duration(source, path)?;
+        self.cache.entries.lock().unwrap().insert(
+            key,
+            CacheEntry {
+                size,
+                modified,
+                duration,
+            },
+        );
+        Ok(duration)
+    }
+}
+
+#[test]
+fn test_duration_cache() {
+    use std::cell::Cell;
+
+    let dir = std::env::temp_dir().join(format!("voicepeak-srt-cache-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("../voice/000-voice.wav", dir.join("000-voice.wav")).unwrap();
+    let cache_path = dir.join(CACHE_FILE_NAME);
+
+    let calls = Cell::new(0);
+    let counting = |source: &dyn InputSource, path: &Path| {
+        calls.set(calls.get() + 1);
+        crate::WavDurationProvider.duration(source, path)
+    };
+    let source = crate::DirSource::new(&dir);
+    let wav = dir.join("000-voice.wav");
+
+    let cache = Arc::new(DurationCache::load(&cache_path));
+    let first = DurationCache::provider(&cache, &counting)
+        .duration(&source, &wav)
+        .unwrap();
+    cache.save().unwrap();
+
+    let cache = Arc::new(DurationCache::load(&cache_path));
+    let second = DurationCache::provider(&cache, &counting)
+        .duration(&source, &wav)
+        .unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(1, calls.get());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
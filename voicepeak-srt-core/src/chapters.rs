@@ -0,0 +1,211 @@
+//! 入力フォルダーの章の一覧(`chapters.txt`)と章マーカーの書き出し
+//!
+//! `chapters.txt`は1行に1章、`連番 章の名前`の形式で書く。章はその連番の音声から始まる。
+//!
+//! ```text
+//! 1 はじめに
+//! 12 本編
+//! ```
+
+use std::{
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{audio::Clip, parse::parse_error, Error, FilePattern, Result, SrtBlock, VttWriter};
+
+/// 章の一覧のファイル名(音声のtxtとしては扱わない)
+pub const CHAPTERS_FILE_NAME: &str = "chapters.txt";
+
+/// 章の一覧の1行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChapterTitle {
+    /// 章が始まる音声の連番
+    pub seq: usize,
+    /// 章の名前
+    pub title: String,
+}
+
+/// 時刻を決めた章
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    /// 章の名前
+    pub title: String,
+    /// 開始時刻
+    pub start: Duration,
+    /// 終了時刻(次の章の開始時刻か、最後の音声の終わり)
+    pub end: Duration,
+}
+
+/// `chapters.txt`を読み込む(空行と`#`で始まる行は読み飛ばす)
+pub fn parse_chapters(text: &str) -> Result<Vec<ChapterTitle>> {
+    let mut titles: Vec<ChapterTitle> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (seq, title) = line
+            .split_once(char::is_whitespace)
+            .and_then(|(seq, title)| Some((seq.parse().ok()?, title.trim())))
+            .ok_or_else(|| parse_error(i + 1, "「連番 章の名前」の形式ではありません"))?;
+        if titles.last().is_some_and(|last| last.seq >= seq) {
+            return Err(parse_error(i + 1, "章は連番の順に並べてください"));
+        }
+        titles.push(ChapterTitle {
+            seq,
+            title: title.to_string(),
+        });
+    }
+    Ok(titles)
+}
+
+/// 章の開始時刻を、その連番(なければ次の連番)の音声の開始時刻にする
+///
+/// 音声が始まらない章は取り除く。時刻は`offset`だけずらす
+pub fn place_chapters(
+    titles: &[ChapterTitle],
+    clips: &[Clip],
+    pattern: &FilePattern,
+    offset: Duration,
+) -> Vec<Chapter> {
+    let end = clips
+        .iter()
+        .map(|clip| clip.start + clip.duration)
+        .max()
+        .unwrap_or_default();
+    let placed: Vec<(&str, Duration)> = titles
+        .iter()
+        .filter_map(|title| {
+            let start = clips
+                .iter()
+                .filter(|clip| pattern.sequence(&clip.path).is_some_and(|s| s >= title.seq))
+                .map(|clip| clip.start)
+                .min()?;
+            Some((title.title.as_str(), start))
+        })
+        .collect();
+    placed
+        .iter()
+        .enumerate()
+        .map(|(i, &(title, start))| Chapter {
+            title: title.to_string(),
+            start: start + offset,
+            end: placed.get(i + 1).map_or(end, |&(_, next)| next) + offset,
+        })
+        .collect()
+}
+
+/// 入力フォルダーの`chapters.txt`を読み込む(なければ`None`)
+pub fn read_chapters(dir: &Path) -> Result<Option<Vec<ChapterTitle>>> {
+    let path = dir.join(CHAPTERS_FILE_NAME);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => parse_chapters(&text).map(Some),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(Error::Read { path, source }),
+    }
+}
+
+/// 章をWebVTTのチャプタートラックとして書き出す
+pub fn write_vtt_chapters<W: Write>(chapters: &[Chapter], writer: W) -> io::Result<()> {
+    let blocks = chapters
+        .iter()
+        .enumerate()
+        .map(|(i, chapter)| SrtBlock::new(i + 1, chapter.start, chapter.end, &chapter.title));
+    crate::write_cues(blocks, &mut VttWriter::new(writer))
+}
+
+/// 章をffmpegのメタデータ(`ffmpeg -i in -i chapters.txt -map_metadata 1`で読み込む)として書き出す
+pub fn write_ffmetadata<W: Write>(chapters: &[Chapter], mut writer: W) -> io::Result<()> {
+    // 特殊な意味を持つ文字はバックスラッシュでエスケープする
+    let escape = |text: &str| {
+        text.chars().fold(String::new(), |mut out, c| {
+            if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+                out.push('\\');
+            }
+            out.push(c);
+            out
+        })
+    };
+    writeln!(writer, ";FFMETADATA1")?;
+    for chapter in chapters {
+        writeln!(
+            writer,
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}",
+            chapter.start.as_millis(),
+            chapter.end.as_millis(),
+            escape(&chapter.title)
+        )?;
+    }
+    writer.flush()
+}
+
+/// 章をYouTubeの概要欄に貼るタイムスタンプ(`0:00 はじめに`)にする
+///
+/// YouTubeは最初の章が`0:00`から始まっていないとチャプターとして扱わない
+pub fn youtube_chapters(chapters: &[Chapter]) -> String {
+    let long = chapters
+        .iter()
+        .any(|chapter| chapter.start.as_secs() >= 3600);
+    chapters
+        .iter()
+        .map(|chapter| {
+            let secs = chapter.start.as_secs();
+            let time = match long {
+                true => format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60),
+                false => format!("{}:{:02}", secs / 60, secs % 60),
+            };
+            format!("{} {}\n", time, chapter.title)
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_chapters() {
+    let titles = parse_chapters("# 章の一覧\n1 はじめに\n\n12\t本編 その1\n").unwrap();
+    assert_eq!(
+        vec![(1, "はじめに"), (12, "本編 その1")],
+        titles
+            .iter()
+            .map(|t| (t.seq, t.title.as_str()))
+            .collect::<Vec<_>>()
+    );
+    assert!(matches!(
+        parse_chapters("2 a\n1 b"),
+        Err(Error::Parse { line: 2, .. })
+    ));
+    assert!(parse_chapters("はじめに").is_err());
+}
+
+#[test]
+fn test_place_chapters() {
+    let clip =
+        |name: &str, secs| Clip::new(name, Duration::from_secs(secs), Duration::from_secs(5));
+    let clips = [
+        clip("001-voice.wav", 0),
+        clip("002-voice.wav", 5),
+        clip("004-voice.wav", 10),
+    ];
+    let titles = parse_chapters("1 はじめに\n3 本編\n9 おまけ").unwrap();
+    let chapters = place_chapters(&titles, &clips, &FilePattern::default(), Duration::ZERO);
+
+    // 連番3の音声はないため次の音声から始め、音声のない章は取り除く
+    assert_eq!(2, chapters.len());
+    assert_eq!(
+        (Duration::ZERO, Duration::from_secs(10)),
+        (chapters[0].start, chapters[0].end)
+    );
+    assert_eq!(
+        (Duration::from_secs(10), Duration::from_secs(15)),
+        (chapters[1].start, chapters[1].end)
+    );
+    assert_eq!("0:00 はじめに\n0:10 本編\n", youtube_chapters(&chapters));
+
+    let mut out = Vec::new();
+    write_ffmetadata(&chapters[..1], &mut out).unwrap();
+    assert_eq!(
+        ";FFMETADATA1\n\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=10000\ntitle=はじめに\n",
+        String::from_utf8(out).unwrap()
+    );
+}
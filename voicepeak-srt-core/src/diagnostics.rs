@@ -0,0 +1,57 @@
+use crate::{Error, Result};
+
+/// 問題が見つかったときの扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Strictness {
+    /// 続行できない問題だけをエラーにし、それ以外は警告にする
+    #[default]
+    Normal,
+    /// 警告も含めてすべての問題をエラーにする
+    Strict,
+    /// 揃っていない・読み込めないファイルを警告にして飛ばし、処理を続ける
+    Lenient,
+}
+
+/// 警告の通知先
+pub(crate) type WarningSink = Box<dyn FnMut(Error)>;
+
+/// 処理中に見つかった問題を`Strictness`に従って振り分ける
+pub(crate) struct Diagnostics {
+    strictness: Strictness,
+    sink: WarningSink,
+}
+
+impl Diagnostics {
+    pub(crate) fn new(strictness: Strictness, sink: WarningSink) -> Self {
+        Self { strictness, sink }
+    }
+
+    /// 処理は続けられる問題を報告する(厳格モードのみエラー)
+    pub(crate) fn warn(&mut self, e: Error) -> Result<()> {
+        match self.strictness {
+            Strictness::Strict => Err(e),
+            _ => {
+                (self.sink)(e);
+                Ok(())
+            }
+        }
+    }
+
+    /// 対象を飛ばせば続けられる問題を報告する(寛容モードのみ警告)
+    pub(crate) fn recover(&mut self, e: Error) -> Result<()> {
+        match self.strictness {
+            Strictness::Lenient => {
+                (self.sink)(e);
+                Ok(())
+            }
+            _ => Err(e),
+        }
+    }
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self::new(Strictness::Normal, Box::new(|_| {}))
+    }
+}
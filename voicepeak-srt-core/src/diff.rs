@@ -0,0 +1,248 @@
+//! 字幕ブロックの差分
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::{format_srt_time, SrtBlock};
+
+/// 差分の1行
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffLine<'a> {
+    /// 変わらないブロック
+    Same(&'a SrtBlock),
+    /// 削除されたブロック
+    Removed(&'a SrtBlock),
+    /// 追加されたブロック
+    Added(&'a SrtBlock),
+}
+
+/// 2つのブロック列の差分を求める
+///
+/// 番号は比較せず、時刻とテキストが同じブロックを同じものとみなす。
+/// 字幕ファイルに書き出される精度に合わせ、時刻はミリ秒単位、テキストは末尾の空白を除いて比べる
+pub fn diff_cues<'a>(old: &'a [SrtBlock], new: &'a [SrtBlock]) -> Vec<DiffLine<'a>> {
+    let same = |a: &SrtBlock, b: &SrtBlock| {
+        a.start.as_millis() == b.start.as_millis()
+            && a.end.as_millis() == b.end.as_millis()
+            && a.text.trim_end() == b.text.trim_end()
+    };
+
+    // 最長共通部分列の長さを後ろから求める
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if same(&old[i], &new[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && same(&old[i], &new[j]) {
+            lines.push(DiffLine::Same(&new[j]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // 削除を追加より先に並べる
+            lines.push(DiffLine::Removed(&old[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(&new[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// 同じ位置にあるブロックどうしの違い
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct CueChange {
+    /// ファイル内での位置(1始まり)
+    pub position: usize,
+    /// 旧ブロック(追加されたブロックでは`None`)
+    pub old: Option<SrtBlock>,
+    /// 新ブロック(削除されたブロックでは`None`)
+    pub new: Option<SrtBlock>,
+    /// テキストが変わったか
+    pub text_changed: bool,
+    /// 開始時刻の差(新−旧、ミリ秒)
+    pub start_delta_ms: i64,
+    /// 終了時刻の差(新−旧、ミリ秒)
+    pub end_delta_ms: i64,
+}
+
+/// 2つのブロック列を先頭から1つずつ比べ、違いのあるブロックを返す
+///
+/// 時刻の差が`tolerance`以下なら同じ時刻とみなす。テキストは末尾の空白を除いて比べる
+pub fn compare_cues(old: &[SrtBlock], new: &[SrtBlock], tolerance: Duration) -> Vec<CueChange> {
+    let millis = |time: Duration| time.as_millis() as i64;
+    let tolerance = millis(tolerance);
+    (0..old.len().max(new.len()))
+        .filter_map(|i| {
+            let (old, new) = (old.get(i), new.get(i));
+            let (start_delta_ms, end_delta_ms, text_changed) = match (old, new) {
+                (Some(old), Some(new)) => (
+                    millis(new.start) - millis(old.start),
+                    millis(new.end) - millis(old.end),
+                    old.text.trim_end() != new.text.trim_end(),
+                ),
+                _ => (0, 0, true),
+            };
+            let changed =
+                text_changed || start_delta_ms.abs() > tolerance || end_delta_ms.abs() > tolerance;
+            changed.then(|| CueChange {
+                position: i + 1,
+                old: old.cloned(),
+                new: new.cloned(),
+                text_changed,
+                start_delta_ms,
+                end_delta_ms,
+            })
+        })
+        .collect()
+}
+
+/// 差分をunified diff形式で表す(1ブロックを1行とする)
+///
+/// 変更がなければ空文字列を返す
+pub fn unified_diff(
+    old: &[SrtBlock],
+    new: &[SrtBlock],
+    old_name: &str,
+    new_name: &str,
+    context: usize,
+) -> String {
+    let lines = diff_cues(old, new);
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, DiffLine::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    // 変更箇所の前後`context`行をまとめてハンクにする
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunks {
+        // ハンクより前にある旧・新それぞれの行数から開始位置を求める
+        let count = |range: &[DiffLine], old_side: bool| {
+            range
+                .iter()
+                .filter(|line| match line {
+                    DiffLine::Same(_) => true,
+                    DiffLine::Removed(_) => old_side,
+                    DiffLine::Added(_) => !old_side,
+                })
+                .count()
+        };
+        // 行がない側は直前の行番号を開始位置とする
+        let range = |old_side: bool| {
+            let len = count(&lines[start..end], old_side);
+            let before = count(&lines[..start], old_side);
+            format!("{},{}", before + usize::from(len > 0), len)
+        };
+        let hunk = &lines[start..end];
+        out.push_str(&format!("@@ -{} +{} @@\n", range(true), range(false)));
+        for line in hunk {
+            let (mark, block) = match line {
+                DiffLine::Same(block) => (' ', block),
+                DiffLine::Removed(block) => ('-', block),
+                DiffLine::Added(block) => ('+', block),
+            };
+            out.push_str(&format!("{}{}\n", mark, cue_line(block)));
+        }
+    }
+    out
+}
+
+/// ブロックを差分表示用の1行にする
+fn cue_line(block: &SrtBlock) -> String {
+    format!(
+        "{} --> {} {}",
+        format_srt_time(block.start),
+        format_srt_time(block.end),
+        block.text.trim_end().replace('\n', " ")
+    )
+}
+
+#[test]
+fn test_diff_cues() {
+    let cue = |index, secs, text: &str| {
+        SrtBlock::new(
+            index,
+            Duration::from_secs(secs),
+            Duration::from_secs(secs + 1),
+            text,
+        )
+    };
+    let old = [cue(1, 0, "a"), cue(2, 1, "b"), cue(3, 2, "c")];
+    let new = [
+        cue(1, 0, "a"),
+        cue(2, 1, "B"),
+        cue(3, 2, "c"),
+        cue(4, 3, "d"),
+    ];
+
+    assert_eq!(
+        "--- old.srt\n+++ new.srt\n@@ -1,3 +1,4 @@\n \
+         00:00:00,000 --> 00:00:01,000 a\n\
+         -00:00:01,000 --> 00:00:02,000 b\n\
+         +00:00:01,000 --> 00:00:02,000 B\n \
+         00:00:02,000 --> 00:00:03,000 c\n\
+         +00:00:03,000 --> 00:00:04,000 d\n",
+        unified_diff(&old, &new, "old.srt", "new.srt", 1)
+    );
+    assert_eq!("", unified_diff(&old, &old, "old.srt", "new.srt", 1));
+
+    let mut precise = old.clone();
+    precise[0].end += Duration::from_micros(300);
+    assert_eq!("", unified_diff(&old, &precise, "old.srt", "new.srt", 1));
+}
+
+#[test]
+fn test_compare_cues() {
+    let cue = |millis, text: &str| {
+        SrtBlock::new(
+            1,
+            Duration::from_millis(millis),
+            Duration::from_millis(millis + 1000),
+            text,
+        )
+    };
+    let old = [cue(0, "a"), cue(1000, "b"), cue(2000, "c")];
+    let new = [cue(10, "a"), cue(1500, "b"), cue(2000, "C")];
+    let changes = compare_cues(&old, &new[..], Duration::from_millis(20));
+
+    assert_eq!(2, changes.len());
+    assert_eq!(
+        (2, 500, false),
+        (
+            changes[0].position,
+            changes[0].start_delta_ms,
+            changes[0].text_changed
+        )
+    );
+    assert!(changes[1].text_changed);
+
+    let removed = compare_cues(&old, &new[..2], Duration::from_millis(1000));
+    assert_eq!(1, removed.len());
+    assert_eq!(None, removed[0].new);
+}
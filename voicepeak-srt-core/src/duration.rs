@@ -0,0 +1,349 @@
+use std::{
+    io::{self, SeekFrom},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{InputSource, ReadSeek};
+
+/// 音声ファイルの長さを取得する
+///
+/// wav以外の読み込み方法(ffprobeやマニフェストなど)を使いたい場合はこれを実装して差し替える
+pub trait DurationProvider {
+    /// 入力元`source`にある`path`の長さを返す
+    fn duration(&self, source: &dyn InputSource, path: &Path) -> io::Result<Duration>;
+}
+
+impl<F: Fn(&dyn InputSource, &Path) -> io::Result<Duration>> DurationProvider for F {
+    fn duration(&self, source: &dyn InputSource, path: &Path) -> io::Result<Duration> {
+        self(source, path)
+    }
+}
+
+/// wavファイルのヘッダーから長さを取得する(既定の実装)
+///
+/// サンプルはデコードせず、fmtチャンクとdataチャンクの長さから求める。
+/// 4GBを超えるRF64(BW64)と、bextなどのチャンクを含むBroadcast Waveも読める
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WavDurationProvider;
+
+impl DurationProvider for WavDurationProvider {
+    fn duration(&self, source: &dyn InputSource, path: &Path) -> io::Result<Duration> {
+        let mut reader = source.open(path)?;
+        let chunks = check_chunks(&mut reader)?;
+        let format = read_format(&mut reader, chunks.fmt)?;
+        check_format(&format)?;
+        check_pcm16(&format)?;
+
+        // ステレオなどではチャンネル数分のサンプルで1フレームになる
+        let frames = chunks.data_len / (format.channel_count as u64 * 2);
+        if frames == 0 {
+            return Err(invalid_data("音声データが空です".to_string()));
+        }
+        Ok(Duration::from_secs_f64(
+            frames as f64 / format.sampling_rate as f64,
+        ))
+    }
+}
+
+/// リニアPCMの形式
+const WAV_FORMAT_PCM: u16 = 0x0001;
+/// WAVE_FORMAT_EXTENSIBLE(24bit以上やマルチチャンネルで使われる)
+const WAV_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// 長さの計算に使うfmtチャンクの値
+struct WavFormat {
+    tag: u16,
+    channel_count: u16,
+    sampling_rate: u32,
+    bits_per_sample: u16,
+}
+
+/// チャンクの位置
+struct Chunks {
+    /// fmtチャンクの中身の位置
+    fmt: u64,
+    /// dataチャンクの中身の位置
+    data: u64,
+    /// dataチャンクの長さ
+    data_len: u64,
+}
+
+/// RF64で、実際の長さをds64チャンクに書いたことを示すチャンクの長さ
+const RF64_SIZE_IN_DS64: u64 = 0xFFFF_FFFF;
+
+/// fmtチャンクを読む
+fn read_format(reader: &mut dyn ReadSeek, pos: u64) -> io::Result<WavFormat> {
+    let mut fmt = [0; 16];
+    reader.seek(SeekFrom::Start(pos))?;
+    reader
+        .read_exact(&mut fmt)
+        .map_err(|_| invalid_data("fmtチャンクが短すぎます".to_string()))?;
+    Ok(WavFormat {
+        tag: u16::from_le_bytes([fmt[0], fmt[1]]),
+        channel_count: u16::from_le_bytes([fmt[2], fmt[3]]),
+        sampling_rate: u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]),
+        bits_per_sample: u16::from_le_bytes([fmt[14], fmt[15]]),
+    })
+}
+
+/// 16bitのwavを検査して読み込む
+pub(crate) fn read_wav(
+    source: &dyn InputSource,
+    path: &Path,
+) -> io::Result<(wav::Header, Vec<i16>)> {
+    let mut inp_file = source.open(path)?;
+    let chunks = check_chunks(&mut inp_file)?;
+    let format = read_format(&mut inp_file, chunks.fmt)?;
+    check_format(&format)?;
+    check_pcm16(&format)?;
+
+    // RF64はwavクレートで読めないため、dataチャンクのサンプルを直接読む
+    inp_file.seek(SeekFrom::Start(chunks.data))?;
+    let mut remaining = chunks.data_len - chunks.data_len % 2;
+    let mut samples = Vec::with_capacity((remaining / 2) as usize);
+    let mut buf = vec![0; 64 * 1024];
+    while remaining > 0 {
+        let len = remaining.min(buf.len() as u64) as usize;
+        inp_file.read_exact(&mut buf[..len])?;
+        samples.extend(
+            buf[..len]
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]])),
+        );
+        remaining -= len as u64;
+    }
+
+    // 途中で切れたwavなどでデータが空だと、以降の時刻がすべてずれるためエラーにする
+    if samples.is_empty() {
+        return Err(invalid_data("音声データが空です".to_string()));
+    }
+
+    let header = wav::Header::new(
+        format.tag,
+        format.channel_count,
+        format.sampling_rate,
+        format.bits_per_sample,
+    );
+    Ok((header, samples))
+}
+
+/// RIFFのチャンクがファイルに収まっているか確認し、fmtとdataの位置を返す
+///
+/// 途中で切れたwavでも時刻がずれたまま読み進めないよう、先に確認する
+fn check_chunks(reader: &mut dyn ReadSeek) -> io::Result<Chunks> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut riff = [0; 12];
+    reader
+        .read_exact(&mut riff)
+        .map_err(|_| invalid_data("wavのヘッダーがありません".to_string()))?;
+    let rf64 = matches!(&riff[0..4], b"RF64" | b"BW64");
+    if !(rf64 || &riff[0..4] == b"RIFF") || &riff[8..12] != b"WAVE" {
+        return Err(invalid_data("RIFF形式のwavではありません".to_string()));
+    }
+
+    let mut fmt = None;
+    let mut data = None;
+    // RF64では4GBを超えるdataチャンクの長さをds64チャンクに書く
+    let mut ds64_data_len = None;
+    let mut pos = 12;
+    while pos + 8 <= len {
+        let mut chunk = [0; 8];
+        reader.read_exact(&mut chunk)?;
+        let mut size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+        if let (b"data", Some(data_len), RF64_SIZE_IN_DS64) = (&chunk[0..4], ds64_data_len, size) {
+            size = data_len;
+        }
+        // ds64の長さはファイルに書かれた値のため、足して溢れないか確かめる
+        if pos
+            .checked_add(8)
+            .and_then(|end| end.checked_add(size))
+            .is_none_or(|end| end > len)
+        {
+            return Err(invalid_data(format!(
+                "{}チャンクが途中で切れています",
+                String::from_utf8_lossy(&chunk[0..4]).trim_end()
+            )));
+        }
+        match &chunk[0..4] {
+            b"ds64" if rf64 => {
+                // riffSize・dataSize・sampleCountの順に64bitで並ぶ
+                let mut ds64 = [0; 16];
+                reader
+                    .read_exact(&mut ds64)
+                    .map_err(|_| invalid_data("ds64チャンクが短すぎます".to_string()))?;
+                ds64_data_len = Some(u64::from_le_bytes(ds64[8..16].try_into().unwrap()));
+            }
+            b"fmt " => fmt = Some(pos + 8),
+            b"data" => data = Some((pos + 8, size)),
+            _ => {}
+        }
+
+        // チャンクは2バイト境界に揃えられる
+        pos += 8 + size + size % 2;
+        reader.seek(SeekFrom::Start(pos))?;
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+    let Some(fmt) = fmt else {
+        return Err(invalid_data("fmtチャンクがありません".to_string()));
+    };
+    let Some((data, data_len)) = data else {
+        return Err(invalid_data("dataチャンクがありません".to_string()));
+    };
+    if rf64 && ds64_data_len.is_none() {
+        return Err(invalid_data("RF64のds64チャンクがありません".to_string()));
+    }
+
+    Ok(Chunks {
+        fmt,
+        data,
+        data_len,
+    })
+}
+
+/// 長さの計算に使うヘッダーの値が正しいか確認する
+fn check_format(header: &WavFormat) -> io::Result<()> {
+    if header.sampling_rate == 0 {
+        return Err(invalid_data("サンプリングレートが0です".to_string()));
+    }
+    if header.channel_count == 0 {
+        return Err(invalid_data("チャンネル数が0です".to_string()));
+    }
+    if !matches!(header.bits_per_sample, 8 | 16 | 24 | 32) {
+        return Err(invalid_data(format!(
+            "ビット深度が正しくありません({}bit)",
+            header.bits_per_sample
+        )));
+    }
+
+    Ok(())
+}
+
+/// 16bitのリニアPCMか確認する
+fn check_pcm16(format: &WavFormat) -> io::Result<()> {
+    if !matches!(format.tag, WAV_FORMAT_PCM | WAV_FORMAT_EXTENSIBLE) {
+        return Err(invalid_data(format!(
+            "PCMのwavではありません(形式{:#06x})",
+            format.tag
+        )));
+    }
+    if format.bits_per_sample != 16 {
+        return Err(invalid_data(format!(
+            "16bitのwavではありません({}bit)",
+            format.bits_per_sample
+        )));
+    }
+
+    Ok(())
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[test]
+fn test_wav_duration_provider() {
+    let duration = WavDurationProvider
+        .duration(
+            &crate::DirSource::new("../voice"),
+            Path::new("../voice/000-voice.wav"),
+        )
+        .unwrap();
+    assert_eq!(7288, duration.as_millis());
+}
+
+#[test]
+fn test_wav_duration_provider_invalid_header() {
+    let wav_bytes = |sampling_rate: u32, samples: Vec<i16>| {
+        let header = wav::Header::new(wav::WAV_FORMAT_PCM, 1, sampling_rate, 16);
+        let mut out = io::Cursor::new(Vec::new());
+        wav::write(header, &wav::BitDepth::Sixteen(samples), &mut out).unwrap();
+        out.into_inner()
+    };
+    let mut source = crate::MemorySource::new();
+    source.insert("zero_rate.wav", wav_bytes(0, vec![0; 100]));
+    source.insert("empty.wav", wav_bytes(44100, Vec::new()));
+    source.insert("ok.wav", wav_bytes(100, vec![0; 50]));
+    let mut truncated = wav_bytes(100, vec![0; 50]);
+    truncated.truncate(60);
+    source.insert("truncated.wav", truncated);
+
+    let error = |name: &str| {
+        WavDurationProvider
+            .duration(&source, Path::new(name))
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!("サンプリングレートが0です", error("zero_rate.wav"));
+    assert_eq!("音声データが空です", error("empty.wav"));
+    assert_eq!("dataチャンクが途中で切れています", error("truncated.wav"));
+    assert_eq!(
+        500,
+        WavDurationProvider
+            .duration(&source, Path::new("ok.wav"))
+            .unwrap()
+            .as_millis()
+    );
+}
+
+#[test]
+fn test_wav_duration_provider_stereo() {
+    let header = wav::Header::new(wav::WAV_FORMAT_PCM, 2, 100, 16);
+    let mut out = io::Cursor::new(Vec::new());
+    wav::write(header, &wav::BitDepth::Sixteen(vec![0; 200]), &mut out).unwrap();
+    let mut source = crate::MemorySource::new();
+    source.insert("stereo.wav", out.into_inner());
+
+    let duration = WavDurationProvider
+        .duration(&source, Path::new("stereo.wav"))
+        .unwrap();
+    assert_eq!(Duration::from_secs(1), duration);
+}
+
+#[test]
+fn test_wav_duration_provider_rf64_and_bext() {
+    // RIFFをRF64にし、ds64とbextのチャンクを入れたwavを作る
+    let header = wav::Header::new(wav::WAV_FORMAT_PCM, 1, 100, 16);
+    let mut out = io::Cursor::new(Vec::new());
+    wav::write(header, &wav::BitDepth::Sixteen(vec![7; 50]), &mut out).unwrap();
+    let riff = out.into_inner();
+    let data = riff.windows(4).position(|w| w == b"data").unwrap();
+
+    let mut ds64 = b"ds64".to_vec();
+    ds64.extend_from_slice(&28u32.to_le_bytes());
+    ds64.extend_from_slice(&0u64.to_le_bytes());
+    ds64.extend_from_slice(&100u64.to_le_bytes());
+    ds64.extend_from_slice(&50u64.to_le_bytes());
+    ds64.extend_from_slice(&0u32.to_le_bytes());
+    let mut bext = b"bext".to_vec();
+    bext.extend_from_slice(&4u32.to_le_bytes());
+    bext.extend_from_slice(b"desc");
+
+    let mut rf64 = b"RF64".to_vec();
+    rf64.extend_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+    rf64.extend_from_slice(b"WAVE");
+    rf64.extend_from_slice(&ds64);
+    rf64.extend_from_slice(&riff[12..data]);
+    rf64.extend_from_slice(&bext);
+    rf64.extend_from_slice(b"data");
+    rf64.extend_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+    rf64.extend_from_slice(&riff[data + 8..]);
+    let mut source = crate::MemorySource::new();
+    source.insert("rf64.wav", rf64.clone());
+
+    let duration = WavDurationProvider
+        .duration(&source, Path::new("rf64.wav"))
+        .unwrap();
+    assert_eq!(Duration::from_millis(500), duration);
+    let (_, samples) = read_wav(&source, Path::new("rf64.wav")).unwrap();
+    assert_eq!(vec![7; 50], samples);
+
+    // ds64のdataSizeが大きすぎても溢れずにエラーにする
+    rf64[28..36].copy_from_slice(&u64::MAX.to_le_bytes());
+    source.insert("huge.wav", rf64);
+    let result = WavDurationProvider.duration(&source, Path::new("huge.wav"));
+    assert_eq!(io::ErrorKind::InvalidData, result.unwrap_err().kind());
+}
@@ -0,0 +1,109 @@
+//! Voicepeakの感情パラメーターの読み込み
+//!
+//! .vpp(Voicepeakのプロジェクト、中身はJSON)か書き出した情報のJSONから、ブロックごとの
+//! 感情パラメーター(happy・sad・angryなどの強さ)を読み込み、いちばん強い感情を求める。
+//! バージョンによって構造が異なるため、ブロックの中の`emotion`(または`emotions`)という
+//! 数値のオブジェクトを探す。
+
+use serde_json::Value;
+
+/// 1ブロック分の感情パラメーター(0〜100)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmotionParams {
+    /// 感情の名前と強さ(読み込んだ順)
+    pub values: Vec<(String, f64)>,
+}
+
+impl EmotionParams {
+    /// `threshold`より強い感情のうち、いちばん強いもの
+    pub fn dominant(&self, threshold: f64) -> Option<&str> {
+        self.values
+            .iter()
+            .filter(|(_, value)| *value > threshold)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// .vppか書き出した情報のJSONから、ブロックごとの感情パラメーターを読み込む
+///
+/// ブロックの一覧は`project.blocks`・`blocks`・ルートの配列の順に探す。
+/// ファイル全体が0〜1の小数で書かれていれば100倍する
+pub fn parse_emotions(json: &str) -> serde_json::Result<Vec<EmotionParams>> {
+    // .vppの末尾にはNUL文字が付いていることがある
+    let root: Value = serde_json::from_str(json.trim_end_matches(['\0', '\n', '\r', ' ']))?;
+    let blocks = root
+        .pointer("/project/blocks")
+        .or_else(|| root.get("blocks"))
+        .unwrap_or(&root)
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let mut emotions: Vec<EmotionParams> = blocks.iter().map(block_emotions).collect();
+
+    // 1か0だけのブロックもあるため、目盛りはブロックごとではなくファイル全体で決める
+    let fraction = emotions
+        .iter()
+        .flat_map(|params| &params.values)
+        .all(|(_, value)| (0. ..=1.).contains(value));
+    if fraction {
+        for (_, value) in emotions.iter_mut().flat_map(|params| &mut params.values) {
+            *value *= 100.;
+        }
+    }
+    Ok(emotions)
+}
+
+fn block_emotions(block: &Value) -> EmotionParams {
+    let Some(object) = find_emotion_object(block) else {
+        return EmotionParams::default();
+    };
+    EmotionParams {
+        values: object
+            .iter()
+            .filter_map(|(name, value)| Some((name.clone(), value.as_f64()?)))
+            .collect(),
+    }
+}
+
+/// `emotion`か`emotions`という名前の、数値を持つオブジェクトを深さ優先で探す
+fn find_emotion_object(value: &Value) -> Option<&serde_json::Map<String, Value>> {
+    match value {
+        Value::Object(object) => {
+            for key in ["emotion", "emotions"] {
+                if let Some(Value::Object(found)) = object.get(key) {
+                    if found.values().any(Value::is_number) {
+                        return Some(found);
+                    }
+                }
+            }
+            object.values().find_map(find_emotion_object)
+        }
+        Value::Array(items) => items.iter().find_map(find_emotion_object),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_parse_emotions() {
+    let vpp = r#"{"project": {"blocks": [
+        {"narrator": {"key": "Japanese Female 1"}, "s-list": [{"text": "こんにちは", "emotion": {"happy": 0.6, "sad": 0.1}}]},
+        {"emotion": {"happy": 0, "angry": 0}},
+        {"text": "感情の指定なし"}
+    ]}}"#;
+    let emotions = parse_emotions(&format!("{}\0", vpp)).unwrap();
+
+    assert_eq!(3, emotions.len());
+    assert_eq!(Some("happy"), emotions[0].dominant(30.));
+    assert_eq!(None, emotions[0].dominant(70.));
+    assert_eq!(None, emotions[1].dominant(0.));
+    assert_eq!(EmotionParams::default(), emotions[2]);
+
+    let exported = parse_emotions(
+        r#"[{"emotions": {"sad": 80, "happy": 20}}, {"emotions": {"happy": 1, "sad": 0}}]"#,
+    )
+    .unwrap();
+    assert_eq!(Some("sad"), exported[0].dominant(0.));
+    // 0〜100のファイルの1は1のまま
+    assert_eq!(None, exported[1].dominant(30.));
+}
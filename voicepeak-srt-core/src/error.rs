@@ -0,0 +1,192 @@
+use std::{io, path::PathBuf, time::Duration};
+
+/// 字幕作成時のエラー
+///
+/// 今後エラーの種類が増えても互換性を保てるよう`non_exhaustive`にしている
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// 入力のパスが存在しない(読み込めない)
+    #[error("パスが存在しません")]
+    PathNotFound(#[source] io::Error),
+    /// wavが1つもない
+    #[error("wavが存在しません")]
+    MissingWav,
+    /// txtが1つもない
+    #[error("txtが存在しません")]
+    MissingTxt,
+    /// wavとtxtの数が一致しない
+    #[error("wavとtxtの数が合いません(wav: {wav}, txt: {txt})")]
+    CountMismatch {
+        /// wavの数
+        wav: usize,
+        /// txtの数
+        txt: usize,
+    },
+    /// 連番に対応するwavまたはtxtの片方しかない
+    #[error("連番{seq:03}のwavとtxtが揃っていません")]
+    UnpairedSequence {
+        /// 揃っていない連番
+        seq: usize,
+    },
+    /// wavとtxtの片方しかない連番がある
+    #[error("{}", format_unpaired(wav_only, txt_only))]
+    UnpairedSequences {
+        /// wavしかない連番
+        wav_only: Vec<usize>,
+        /// txtしかない連番
+        txt_only: Vec<usize>,
+    },
+    /// 途中の連番のファイルがない
+    #[error("連番が抜けています: {}", format_sequences(missing))]
+    SequenceGap {
+        /// 抜けている連番
+        missing: Vec<usize>,
+    },
+    /// txtが空(空白のみ)
+    #[error("連番{seq:03}のtxtが空です")]
+    EmptyText {
+        /// 空だった連番
+        seq: usize,
+    },
+    /// 同じ連番のwavまたはtxtが複数ある
+    #[error("同じ連番のファイルが複数あります: {}", format_sequences(seqs))]
+    DuplicateSequence {
+        /// 重複している連番
+        seqs: Vec<usize>,
+    },
+    /// 重複している連番のうち最も新しいファイルを使った
+    #[error("連番{seq:03}が重複しているため、最も新しい{}を使います", kept.display())]
+    DuplicateResolved {
+        /// 重複していた連番
+        seq: usize,
+        /// 使ったファイル
+        kept: PathBuf,
+    },
+    /// テキストがNFCに正規化されていない(macOSで書き出したファイルなど)
+    #[error("連番{seq:03}のテキストがNFCに正規化されていません")]
+    NotNormalized {
+        /// 正規化されていなかった連番
+        seq: usize,
+    },
+    /// 音声の長さがテキストの長さと合わない(組み合わせの間違いや合成後の編集など)
+    #[error(
+        "連番{seq:03}の音声の長さ({:.1}秒)がテキストから推定される長さ({:.1}秒)と大きく異なります",
+        duration.as_secs_f64(),
+        expected.as_secs_f64()
+    )]
+    ImplausibleDuration {
+        /// 疑わしい連番
+        seq: usize,
+        /// 音声の長さ
+        duration: Duration,
+        /// テキストから推定した長さ
+        expected: Duration,
+    },
+    /// 音声がほとんど無音(Voicepeakの書き出しの失敗など)
+    #[error(
+        "連番{seq:03}の音声がほとんど無音です(無音の区間{:.0}%・最大振幅{peak}): {}",
+        ratio * 100.,
+        path.display()
+    )]
+    SilentClip {
+        /// 無音の連番
+        seq: usize,
+        /// 無音の音声
+        path: PathBuf,
+        /// 無音の区間の割合
+        ratio: f64,
+        /// 最大振幅(0ならデジタル無音)
+        peak: u16,
+    },
+    /// 連番を取り出せないファイルがある
+    #[error("連番を取り出せないファイルを無視しました: {}", path.display())]
+    IgnoredFile {
+        /// 無視したファイル
+        path: PathBuf,
+    },
+    /// wavを読み込めない
+    #[error("wavを読み込めません: {}", path.display())]
+    WavDecode {
+        /// wavのパス
+        path: PathBuf,
+        /// 読み込み時のエラー
+        #[source]
+        source: io::Error,
+    },
+    /// txtが指定の文字コード(既定はUTF-8)ではない
+    #[error("txtの文字コードが違います: {}", path.display())]
+    TxtEncoding {
+        /// txtのパス
+        path: PathBuf,
+    },
+    /// 字幕ファイルのブロック数と音声の数が合わない
+    #[error("字幕のブロック数({cues})と音声の数({clips})が一致しません")]
+    CueCountMismatch {
+        /// 字幕のブロック数
+        cues: usize,
+        /// 音声の数
+        clips: usize,
+    },
+    /// 他のwavとチャンネル数が異なる
+    #[error("他のwavとチャンネル数が異なります: {}", path.display())]
+    AudioFormatMismatch {
+        /// 形式の異なるwav
+        path: PathBuf,
+    },
+    /// ファイルを読み込めない
+    #[error("ファイルを読み込めません: {}", path.display())]
+    Read {
+        /// ファイルのパス
+        path: PathBuf,
+        /// 読み込み時のエラー
+        #[source]
+        source: io::Error,
+    },
+    /// ファイル名のパターンが正しくない
+    #[error("ファイル名のパターンが正しくありません: {message}")]
+    InvalidPattern {
+        /// 内容
+        message: String,
+    },
+    /// プリセットの設定が正しくない
+    #[error("プリセットの設定が正しくありません: {message}")]
+    InvalidPreset {
+        /// 内容
+        message: String,
+    },
+    /// 字幕ファイルを読み込めない
+    #[error("{line}行目: {message}")]
+    Parse {
+        /// 問題のある行(1始まり)
+        line: usize,
+        /// 内容
+        message: String,
+    },
+    /// 書き出しに失敗した
+    #[error("書き出しに失敗しました")]
+    Write(#[from] io::Error),
+}
+
+/// 連番を`000, 001`の形式で並べる
+fn format_sequences(seqs: &[usize]) -> String {
+    seqs.iter()
+        .map(|seq| format!("{:03}", seq))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 片方しかない連番をまとめる
+fn format_unpaired(wav_only: &[usize], txt_only: &[usize]) -> String {
+    let mut parts = Vec::new();
+    if !wav_only.is_empty() {
+        parts.push(format!("txtがない連番: {}", format_sequences(wav_only)));
+    }
+    if !txt_only.is_empty() {
+        parts.push(format!("wavがない連番: {}", format_sequences(txt_only)));
+    }
+    format!("wavとtxtが揃っていません({})", parts.join(" / "))
+}
+
+/// 字幕作成時の`Result`
+pub type Result<T> = std::result::Result<T, Error>;
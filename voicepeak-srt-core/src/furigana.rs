@@ -0,0 +1,261 @@
+//! 振り仮名の付与
+//!
+//! 形態素解析の結果(MeCabの出力など)か読みの辞書から単語ごとの読みを求め、
+//! 難しい漢字を含む単語にだけ読みを添える。
+
+/// 読みを求めた単語
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Morpheme {
+    /// 表記
+    pub surface: String,
+    /// 読み(ひらがな、分からなければ`None`)
+    pub reading: Option<String>,
+}
+
+/// 振り仮名を付ける漢字
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum RubyTarget {
+    /// 常用漢字にない漢字だけ
+    #[default]
+    Rare,
+    /// すべての漢字
+    All,
+}
+
+impl RubyTarget {
+    /// 単語に振り仮名を付けるか
+    pub fn matches(&self, surface: &str) -> bool {
+        match self {
+            Self::Rare => surface.chars().any(is_rare_kanji),
+            Self::All => surface.chars().any(is_kanji),
+        }
+    }
+}
+
+/// 振り仮名の書き方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum RubyStyle {
+    /// `漢字(かんじ)`
+    #[default]
+    Brackets,
+    /// ASSのタグで小さくした`漢字（かんじ）`
+    Ass {
+        /// 読みの文字の大きさ
+        font_size: u32,
+    },
+}
+
+/// 漢字か
+pub fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' | '々')
+        || ('\u{20000}'..='\u{2FFFF}').contains(&c)
+}
+
+/// 常用漢字表(2010年)の2136字(音読みの順、1行が1つの読み)
+const JOYO_KANJI: &str = include_str!("joyo_kanji.txt");
+
+/// 常用漢字表で使ってよいとされる、表の字体とは別の字体
+const JOYO_VARIANTS: &str = "叱剥頬填";
+
+/// 常用漢字にない漢字か
+pub fn is_rare_kanji(c: char) -> bool {
+    is_kanji(c) && c != '々' && !JOYO_KANJI.contains(c) && !JOYO_VARIANTS.contains(c)
+}
+
+/// カタカナをひらがなにする
+pub fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            c => c,
+        })
+        .collect()
+}
+
+/// MeCab(IPA辞書などの既定の出力形式)の結果を読み込む
+///
+/// 読みは素性の8番目(カタカナ)を使う。`EOS`は改行にする
+pub fn parse_mecab(output: &str) -> Vec<Morpheme> {
+    let mut morphemes: Vec<Morpheme> = Vec::new();
+    let lines: Vec<&str> = output.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        if *line == "EOS" {
+            // 最後の行の終わりは改行にしない
+            if i + 1 < lines.len() {
+                morphemes.push(Morpheme {
+                    surface: "\n".to_string(),
+                    reading: None,
+                });
+            }
+            continue;
+        }
+        let Some((surface, features)) = line.split_once('\t') else {
+            continue;
+        };
+        let reading = features
+            .split(',')
+            .nth(7)
+            .filter(|reading| *reading != "*")
+            .map(to_hiragana);
+        morphemes.push(Morpheme {
+            surface: surface.to_string(),
+            reading,
+        });
+    }
+    morphemes
+}
+
+/// 単語と読みの辞書(1行に`表記<TAB>よみ`か`表記,よみ`)
+#[derive(Debug, Clone, Default)]
+pub struct ReadingDictionary {
+    /// 長い表記から順に並べる(最長一致で探す)
+    entries: Vec<(String, String)>,
+}
+
+impl ReadingDictionary {
+    /// 辞書を読み込む(空行と`#`で始まる行は読み飛ばす)
+    pub fn parse(text: &str) -> Self {
+        let mut entries: Vec<(String, String)> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once(['\t', ',']))
+            .map(|(surface, reading)| (surface.trim().to_string(), to_hiragana(reading.trim())))
+            .filter(|(surface, reading)| !surface.is_empty() && !reading.is_empty())
+            .collect();
+        entries.sort_by_key(|(surface, _)| std::cmp::Reverse(surface.chars().count()));
+        Self { entries }
+    }
+
+    /// テキストを辞書にある単語とそれ以外に分ける
+    pub fn segment(&self, text: &str) -> Vec<Morpheme> {
+        let mut morphemes: Vec<Morpheme> = Vec::new();
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            match self
+                .entries
+                .iter()
+                .find(|(surface, _)| rest.starts_with(surface.as_str()))
+            {
+                Some((surface, reading)) => {
+                    morphemes.push(Morpheme {
+                        surface: surface.clone(),
+                        reading: Some(reading.clone()),
+                    });
+                    rest = &rest[surface.len()..];
+                }
+                None => {
+                    // 辞書にない文字は前の単語につなげる
+                    match morphemes.last_mut() {
+                        Some(last) if last.reading.is_none() => last.surface.push(c),
+                        _ => morphemes.push(Morpheme {
+                            surface: c.to_string(),
+                            reading: None,
+                        }),
+                    }
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        morphemes
+    }
+}
+
+/// 振り仮名を付けたテキストにする
+///
+/// 送り仮名など表記と読みで共通する前後のかなには読みを付けない
+pub fn add_furigana(morphemes: &[Morpheme], target: RubyTarget, style: RubyStyle) -> String {
+    let mut out = String::new();
+    for morpheme in morphemes {
+        let reading = match &morpheme.reading {
+            Some(reading) if target.matches(&morpheme.surface) => reading,
+            _ => {
+                out.push_str(&morpheme.surface);
+                continue;
+            }
+        };
+        let surface: Vec<char> = morpheme.surface.chars().collect();
+        let reading: Vec<char> = reading.chars().collect();
+        let kana = |c: &char| !is_kanji(*c);
+        let prefix = surface
+            .iter()
+            .zip(&reading)
+            .take_while(|(s, r)| kana(s) && s == r)
+            .count();
+        let suffix = surface[prefix..]
+            .iter()
+            .rev()
+            .zip(reading[prefix..].iter().rev())
+            .take_while(|(s, r)| kana(s) && s == r)
+            .count();
+        let base: String = surface[prefix..surface.len() - suffix].iter().collect();
+        let ruby: String = reading[prefix..reading.len() - suffix].iter().collect();
+        let head: String = surface[..prefix].iter().collect();
+        let tail: String = surface[surface.len() - suffix..].iter().collect();
+        // 表記がかなだけになった、または読みが残らなければそのまま
+        if ruby.is_empty() || base == ruby {
+            out.push_str(&morpheme.surface);
+            continue;
+        }
+        out.push_str(&head);
+        out.push_str(&base);
+        match style {
+            RubyStyle::Brackets => out.push_str(&format!("({})", ruby)),
+            RubyStyle::Ass { font_size } => {
+                out.push_str(&format!("{{\\fs{}}}（{}）{{\\fs}}", font_size, ruby))
+            }
+        }
+        out.push_str(&tail);
+    }
+    out
+}
+
+#[test]
+fn test_is_rare_kanji() {
+    assert_eq!(2136, JOYO_KANJI.chars().filter(|c| is_kanji(*c)).count());
+    assert!(!is_rare_kanji('亜'));
+    assert!(!is_rare_kanji('腕'));
+    // 第2水準でも常用漢字なら付けない
+    assert!(!is_rare_kanji('嗅'));
+    assert!(!is_rare_kanji('叱'));
+    // 第1水準でも常用漢字でなければ付ける
+    assert!(is_rare_kanji('塵'));
+    assert!(is_rare_kanji('弌'));
+    assert!(is_rare_kanji('𠮷'));
+    assert!(!is_rare_kanji('あ'));
+}
+
+#[test]
+fn test_parse_mecab() {
+    let output = "砂塵\t名詞,一般,*,*,*,*,砂塵,サジン,サジン\n舞う\t動詞,自立,*,*,五段・ワ行促音便,基本形,舞う,マウ,マウ\nEOS\n";
+    let morphemes = parse_mecab(output);
+
+    assert_eq!(2, morphemes.len());
+    assert_eq!(Some("さじん"), morphemes[0].reading.as_deref());
+    assert_eq!(
+        "砂塵(さじん)舞(ま)う",
+        add_furigana(&morphemes, RubyTarget::All, RubyStyle::Brackets)
+    );
+    // 常用漢字だけの単語には付けない
+    assert_eq!(
+        "砂塵(さじん)舞う",
+        add_furigana(&morphemes, RubyTarget::Rare, RubyStyle::Brackets)
+    );
+}
+
+#[test]
+fn test_reading_dictionary() {
+    let dictionary = ReadingDictionary::parse("# 読み\n贔屓\tひいき\n贔\tひ\n");
+    let morphemes = dictionary.segment("贔屓にする");
+
+    assert_eq!(
+        "贔屓{\\fs24}（ひいき）{\\fs}にする",
+        add_furigana(
+            &morphemes,
+            RubyTarget::Rare,
+            RubyStyle::Ass { font_size: 24 }
+        )
+    );
+}
@@ -0,0 +1,89 @@
+//! Voicepeakが書き出した.wavと.txtから字幕を作成するためのライブラリ
+//!
+//! よく使う型は[`prelude`]からまとめて読み込める。
+//!
+//! ```no_run
+//! use voicepeak_srt_core::prelude::*;
+//!
+//! fn main() -> Result<()> {
+//!     SubtitleBuilder::from_dir("./voice")
+//!         .filter(|block| !block.text.is_empty())
+//!         .write_file("./subtitles.srt")
+//! }
+//! ```
+//!
+//! # 互換性
+//!
+//! このクレートはセマンティックバージョニングに従う。ルートと[`prelude`]から公開している項目は
+//! マイナーバージョンアップで壊さない。[`Error`]と[`SrtBlock`]は`non_exhaustive`のため、
+//! バリアントやフィールドの追加は互換性のある変更として扱う。
+#![warn(missing_docs)]
+
+#[cfg(feature = "async")]
+pub mod async_api;
+pub mod audio;
+mod block;
+mod builder;
+pub mod cache;
+pub mod chapters;
+mod diagnostics;
+pub mod diff;
+mod duration;
+pub mod emotion;
+mod error;
+pub mod furigana;
+mod manifest;
+mod metadata;
+pub mod parse;
+mod pattern;
+#[cfg(feature = "parallel")]
+mod prefetch;
+pub mod preset;
+mod project;
+mod scan;
+pub mod screenplay;
+mod source;
+mod timeline;
+mod timing;
+pub mod transform;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod writer;
+
+pub use block::SrtBlock;
+pub use builder::SubtitleBuilder;
+pub use diagnostics::Strictness;
+pub use duration::{DurationProvider, WavDurationProvider};
+pub use error::{Error, Result};
+pub use manifest::{Manifest, ManifestEntry};
+pub use metadata::{CueMetadata, Metadata};
+pub use pattern::FilePattern;
+pub use project::Project;
+pub use scan::{
+    extract_wav_and_txt, extract_wav_and_txt_from, extract_wav_and_txt_with, DuplicatePolicy,
+};
+pub use source::{DirSource, FileListSource, InputSource, MemorySource, ReadSeek};
+pub use timeline::{Timeline, TimelineEntry};
+pub use timing::{
+    decode_shift_jis, make_srt_blocks, srt_blocks, srt_blocks_from_source, srt_blocks_with,
+    EmptyTextPolicy, MultilinePolicy, SrtBlocks, TxtEncoding,
+};
+pub use writer::{
+    format_ass_time, format_srt_time, format_vtt_time, make_srt, write_cues, write_srt,
+    write_subtitle_file, write_subtitles, AssWriter, CueAlign, CueIdentifier, CuePosition,
+    EncodedWriter, HourOverflow, JsonWriter, LineEnding, MltWriter, NewlineWriter, OutputEncoding,
+    OutputFile, Positions, RegionWriter, SbvWriter, SrtWriter, SubtitleWriter, TimestampFormat,
+    VttWriter,
+};
+
+/// よく使う型とトレイトをまとめたモジュール
+///
+/// `use voicepeak_srt_core::prelude::*;`で読み込む
+pub mod prelude {
+    pub use crate::transform::Transform;
+    pub use crate::{
+        DirSource, DurationProvider, Error, FilePattern, InputSource, MemorySource, Project,
+        Result, SrtBlock, Strictness, SubtitleBuilder, SubtitleWriter, WavDurationProvider,
+    };
+}
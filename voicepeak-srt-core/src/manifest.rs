@@ -0,0 +1,138 @@
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{extract_wav_and_txt_from, DurationProvider, Error, InputSource, Result};
+
+/// 入力ファイルの一覧とチェックサム
+///
+/// 字幕を作成したときと同じ音声を使っているかを共同作業者が確認するために使う
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Manifest {
+    /// 入力ファイル(ファイル名順)
+    pub files: Vec<ManifestEntry>,
+}
+
+/// マニフェストに記録する1ファイル分の情報
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ManifestEntry {
+    /// ファイル名
+    pub path: PathBuf,
+    /// サイズ(バイト)
+    pub size: u64,
+    /// SHA-256(16進数)
+    pub sha256: String,
+    /// 音声の長さ(wavのみ)
+    pub duration: Option<Duration>,
+}
+
+impl Manifest {
+    /// 入力元のwavとtxtからマニフェストを作成する
+    pub fn from_source(source: &dyn InputSource, provider: &dyn DurationProvider) -> Result<Self> {
+        let mut files = extract_wav_and_txt_from(source)?;
+        files.sort();
+
+        let files = files
+            .iter()
+            .map(|path| {
+                let (size, sha256) = hash_file(source, path)?;
+                let duration = match path.extension() {
+                    Some(ext) if ext == "wav" => {
+                        Some(provider.duration(source, path).map_err(|source| {
+                            Error::WavDecode {
+                                path: path.to_path_buf(),
+                                source,
+                            }
+                        })?)
+                    }
+                    _ => None,
+                };
+
+                Ok(ManifestEntry {
+                    path: path.file_name().map(PathBuf::from).unwrap_or_default(),
+                    size,
+                    sha256,
+                    duration,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { files })
+    }
+
+    /// JSONからマニフェストを読み込む
+    pub fn from_json<R: Read>(reader: R) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+
+    /// マニフェストをJSONで書き出す
+    pub fn write_json<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+}
+
+/// ファイルのサイズとSHA-256を求める
+fn hash_file(source: &dyn InputSource, path: &Path) -> Result<(u64, String)> {
+    let read_error = |e| Error::Read {
+        path: path.to_path_buf(),
+        source: e,
+    };
+
+    let mut reader = source.open(path).map_err(read_error)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0; 8192];
+    let mut size = 0;
+    loop {
+        let n = reader.read(&mut buf).map_err(read_error)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok((size, sha256))
+}
+
+#[test]
+fn test_manifest_from_dir() {
+    let manifest = Manifest::from_source(
+        &crate::DirSource::new("../voice"),
+        &crate::WavDurationProvider,
+    )
+    .unwrap();
+
+    assert_eq!(8, manifest.files.len());
+    assert_eq!(Path::new("000-voice.txt"), manifest.files[0].path);
+    assert_eq!(None, manifest.files[0].duration);
+    assert_eq!(7288, manifest.files[1].duration.unwrap().as_millis());
+    assert!(manifest.files.iter().all(|f| f.sha256.len() == 64));
+}
+
+#[test]
+fn test_manifest_hash() {
+    let mut source = crate::MemorySource::new();
+    source.insert("000-a.wav", b"abc".to_vec());
+    source.insert("000-a.txt", b"abc".to_vec());
+    let provider = |_: &dyn InputSource, _: &Path| Ok(Duration::from_secs(1));
+    let manifest = Manifest::from_source(&source, &provider).unwrap();
+
+    assert_eq!(3, manifest.files[1].size);
+    assert_eq!(
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        manifest.files[1].sha256
+    );
+    assert_eq!(Some(Duration::from_secs(1)), manifest.files[1].duration);
+}
@@ -0,0 +1,100 @@
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{FilePattern, SrtBlock};
+
+/// 字幕のテキストとは別に書き出す、ブロックごとの情報
+///
+/// 動画編集ソフトのスクリプトなど、字幕を読み込む他のツールで話者や元の音声を使うためのもの
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Metadata {
+    /// ブロックごとの情報(字幕と同じ順)
+    pub cues: Vec<CueMetadata>,
+}
+
+/// 1ブロック分の情報
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CueMetadata {
+    /// 字幕番号
+    pub index: usize,
+    /// 開始時刻(ミリ秒)
+    pub start_ms: u64,
+    /// 終了時刻(ミリ秒)
+    pub end_ms: u64,
+    /// 話者
+    pub speaker: Option<String>,
+    /// 感情やスタイル(ファイル名のパターンの`emotion`か.vppから分かった場合のみ)
+    pub emotion: Option<String>,
+    /// 元になった音声のパス
+    pub source: Option<PathBuf>,
+    /// 元のファイル名の連番
+    pub sequence: Option<usize>,
+}
+
+impl Metadata {
+    /// 字幕ブロックと、その音声のファイル名から情報を集める
+    pub fn from_blocks(blocks: &[SrtBlock], pattern: &FilePattern) -> Self {
+        let cues = blocks
+            .iter()
+            .map(|block| {
+                let source = block.source.as_deref();
+                CueMetadata {
+                    index: block.index,
+                    start_ms: block.start.as_millis() as u64,
+                    end_ms: block.end.as_millis() as u64,
+                    speaker: block
+                        .speaker
+                        .clone()
+                        .or_else(|| source.and_then(|path| pattern.speaker(path))),
+                    emotion: block
+                        .emotion
+                        .clone()
+                        .or_else(|| source.and_then(|path| pattern.emotion(path))),
+                    source: block.source.clone(),
+                    sequence: source.and_then(|path| pattern.sequence(path)),
+                }
+            })
+            .collect();
+        Self { cues }
+    }
+
+    /// JSONから読み込む
+    pub fn from_json<R: Read>(reader: R) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+
+    /// JSONで書き出す
+    pub fn write_json<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+}
+
+#[test]
+fn test_metadata_from_blocks() {
+    use std::time::Duration;
+
+    let mut block = SrtBlock::new(
+        1,
+        Duration::from_millis(500),
+        Duration::from_millis(1500),
+        "こんにちは",
+    );
+    block.source = Some("voice/003_ずんだもん（あまあま）_こんにちは.wav".into());
+    let metadata = Metadata::from_blocks(&[block], &FilePattern::voicevox());
+
+    let cue = &metadata.cues[0];
+    assert_eq!(500, cue.start_ms);
+    assert_eq!(Some("ずんだもん"), cue.speaker.as_deref());
+    assert_eq!(Some("あまあま"), cue.emotion.as_deref());
+    assert_eq!(Some(3), cue.sequence);
+
+    let mut json = Vec::new();
+    metadata.write_json(&mut json).unwrap();
+    assert_eq!(metadata, Metadata::from_json(json.as_slice()).unwrap());
+}
@@ -0,0 +1,96 @@
+use super::{parse_error, parse_timestamp, strip_bom};
+use crate::{Result, SrtBlock};
+
+/// `Format:`行がない場合のEventsの列
+const DEFAULT_FORMAT: [&str; 10] = [
+    "Layer", "Start", "End", "Style", "Name", "MarginL", "MarginR", "MarginV", "Effect", "Text",
+];
+
+/// ASS(SSA)形式の字幕を読み込む
+///
+/// `[Events]`の`Dialogue`行だけを読み、`{\an8}`などの上書きタグは取り除く。
+/// `Name`列は話者にする
+pub fn parse_ass(text: &str) -> Result<Vec<SrtBlock>> {
+    let mut blocks = Vec::new();
+    let mut in_events = false;
+    let mut format: Vec<String> = DEFAULT_FORMAT.iter().map(|s| s.to_string()).collect();
+
+    for (i, line) in strip_bom(text).lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[Events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(fields) = line.strip_prefix("Format:") {
+            format = fields.split(',').map(|f| f.trim().to_string()).collect();
+            continue;
+        }
+        let Some(fields) = line.strip_prefix("Dialogue:") else {
+            continue;
+        };
+
+        // テキストには`,`が含まれうるため、最後の列は残りをすべて含める
+        let values: Vec<&str> = fields.splitn(format.len(), ',').collect();
+        let field = |name: &str| {
+            format
+                .iter()
+                .position(|f| f == name)
+                .and_then(|i| values.get(i))
+                .map(|v| v.trim())
+        };
+        let (Some(start), Some(end)) = (
+            field("Start").and_then(parse_timestamp),
+            field("End").and_then(parse_timestamp),
+        ) else {
+            return Err(parse_error(i + 1, "タイムスタンプを読み込めません"));
+        };
+
+        let mut block = SrtBlock::new(
+            blocks.len() + 1,
+            start,
+            end,
+            ass_to_plain(values.get(format.len() - 1).copied().unwrap_or_default()),
+        );
+        block.speaker = field("Name")
+            .filter(|name| !name.is_empty())
+            .map(str::to_string);
+        blocks.push(block);
+    }
+
+    Ok(blocks)
+}
+
+/// ASSのテキストから上書きタグを取り除き、改行を戻す
+fn ass_to_plain(text: &str) -> String {
+    let mut plain = String::new();
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '{' => in_tag = true,
+            '}' if in_tag => in_tag = false,
+            _ if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain
+        .replace("\\N", "\n")
+        .replace("\\n", "\n")
+        .replace("\\h", " ")
+}
+
+#[test]
+fn test_parse_ass() {
+    let text = "[Script Info]\nTitle: test\n\n[Events]\n\
+                Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                Comment: 0,0:00:00.00,0:00:01.00,Default,,0,0,0,,メモ\n\
+                Dialogue: 0,0:00:01.23,0:00:03.00,Default,A,0,0,0,,{\\an8}一行目\\N二行目, 続き\n";
+    let blocks = parse_ass(text).unwrap();
+
+    assert_eq!(1, blocks.len());
+    assert_eq!(1230, blocks[0].start.as_millis());
+    assert_eq!("一行目\n二行目, 続き", blocks[0].text);
+    assert_eq!(Some("A"), blocks[0].speaker.as_deref());
+}
@@ -0,0 +1,208 @@
+//! 既存の字幕ファイルの読み込み
+
+use std::{path::Path, time::Duration};
+
+use crate::{Error, Project, Result, SrtBlock};
+
+mod ass;
+mod sbv;
+mod srt;
+mod vtt;
+
+pub use ass::parse_ass;
+pub use sbv::parse_sbv;
+pub use srt::parse_srt;
+pub use vtt::parse_vtt;
+
+/// 読み書きできる字幕の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SubtitleFormat {
+    /// SubRip
+    Srt,
+    /// WebVTT
+    Vtt,
+    /// Advanced SubStation Alpha
+    Ass,
+    /// YouTubeのSBV
+    Sbv,
+    /// [`Project`]のJSON
+    Json,
+}
+
+impl SubtitleFormat {
+    /// 拡張子から形式を判定する
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(path.extension()?.to_str()?)
+    }
+
+    /// 拡張子(`srt`など、大文字小文字は問わない)から形式を判定する
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::Vtt),
+            "ass" | "ssa" => Some(Self::Ass),
+            "sbv" => Some(Self::Sbv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// 内容から形式を判定する(`WEBVTT`で始まればVTT、どれにも当てはまらなければSRT)
+    pub fn detect(text: &str) -> Self {
+        let text = strip_bom(text).trim_start();
+        let first_line = text.lines().next().unwrap_or_default();
+        let is_sbv_timing = first_line.split_once(',').is_some_and(|(start, end)| {
+            parse_timestamp(start).is_some() && parse_timestamp(end).is_some()
+        });
+        if text.starts_with("WEBVTT") {
+            Self::Vtt
+        } else if text.starts_with("[Script Info]") {
+            Self::Ass
+        } else if text.starts_with('{') {
+            Self::Json
+        } else if is_sbv_timing {
+            Self::Sbv
+        } else {
+            Self::Srt
+        }
+    }
+}
+
+/// 形式を指定して字幕を読み込む
+pub fn parse_subtitles(text: &str, format: SubtitleFormat) -> Result<Vec<SrtBlock>> {
+    match format {
+        SubtitleFormat::Srt => parse_srt(text),
+        SubtitleFormat::Vtt => parse_vtt(text),
+        SubtitleFormat::Ass => parse_ass(text),
+        SubtitleFormat::Sbv => parse_sbv(text),
+        SubtitleFormat::Json => Project::from_json(strip_bom(text).as_bytes())
+            .map(|project| project.cues)
+            .map_err(|e| parse_error(e.line(), &e.to_string())),
+    }
+}
+
+/// 字幕ファイルを読み込む
+///
+/// 形式は拡張子から、判定できなければ内容から決める
+pub fn read_subtitle_file(path: &Path) -> Result<Vec<SrtBlock>> {
+    let text = std::fs::read_to_string(path).map_err(|source| Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let format = SubtitleFormat::from_path(path).unwrap_or_else(|| SubtitleFormat::detect(&text));
+    parse_subtitles(&text, format)
+}
+
+/// 先頭のBOMを取り除く
+pub(crate) fn strip_bom(text: &str) -> &str {
+    text.strip_prefix('\u{feff}').unwrap_or(text)
+}
+
+/// 空行で区切られたブロックを行番号(1始まり)付きで取り出す
+pub(crate) fn split_blocks(text: &str) -> Vec<(usize, Vec<&str>)> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut start_line = 1;
+
+    for (i, line) in strip_bom(text).lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push((start_line, std::mem::take(&mut current)));
+            }
+            continue;
+        }
+        if current.is_empty() {
+            start_line = i + 1;
+        }
+        current.push(line);
+    }
+    if !current.is_empty() {
+        blocks.push((start_line, current));
+    }
+
+    blocks
+}
+
+/// `HH:MM:SS,mmm`、`HH:MM:SS.mmm`、`MM:SS.mmm`形式のタイムスタンプを読み込む
+pub fn parse_timestamp(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let (hms, frac) = text.rsplit_once([',', '.']).unwrap_or((text, "0"));
+    let parts: Vec<&str> = hms.split(':').collect();
+    let (h, m, s) = match parts.as_slice() {
+        [h, m, s] => (
+            h.parse::<u64>().ok()?,
+            m.parse::<u64>().ok()?,
+            s.parse::<u64>().ok()?,
+        ),
+        [m, s] => (0, m.parse::<u64>().ok()?, s.parse::<u64>().ok()?),
+        _ => return None,
+    };
+    if m >= 60 || s >= 60 || frac.is_empty() || !frac.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    // 小数部は桁数に応じてナノ秒に換算する
+    let digits = frac.len().min(9);
+    let nanos = frac[..digits].parse::<u32>().ok()? * 10u32.pow(9 - digits as u32);
+    Some(Duration::new(h * 3600 + m * 60 + s, nanos))
+}
+
+/// `開始 --> 終了`の行を読み込む(終了時刻の後ろの設定は無視する)
+pub(crate) fn parse_timing_line(line: &str) -> Option<(Duration, Duration)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+/// 読み込みエラーを作成する
+pub(crate) fn parse_error(line: usize, message: &str) -> Error {
+    Error::Parse {
+        line,
+        message: message.to_string(),
+    }
+}
+
+#[test]
+fn test_parse_timestamp() {
+    assert_eq!(
+        Some(Duration::from_millis(3_723_045)),
+        parse_timestamp("01:02:03,045")
+    );
+    assert_eq!(
+        Some(Duration::from_millis(3_723_045)),
+        parse_timestamp("01:02:03.045")
+    );
+    assert_eq!(
+        Some(Duration::from_millis(63_500)),
+        parse_timestamp("01:03.5")
+    );
+    assert_eq!(
+        Some(Duration::from_secs(100 * 3600)),
+        parse_timestamp("100:00:00,000")
+    );
+    assert_eq!(None, parse_timestamp("00:61:00,000"));
+    assert_eq!(None, parse_timestamp("abc"));
+}
+
+#[test]
+fn test_detect_format() {
+    assert_eq!(SubtitleFormat::Vtt, SubtitleFormat::detect("WEBVTT\n"));
+    assert_eq!(
+        SubtitleFormat::Ass,
+        SubtitleFormat::detect("\u{feff}[Script Info]\n")
+    );
+    assert_eq!(
+        SubtitleFormat::Json,
+        SubtitleFormat::detect("{\"cues\": []}")
+    );
+    assert_eq!(
+        SubtitleFormat::Sbv,
+        SubtitleFormat::detect("0:00:00.000,0:00:01.000\nテキスト")
+    );
+    assert_eq!(
+        SubtitleFormat::Srt,
+        SubtitleFormat::detect("1\n00:00:00,000 --> 00:00:01,000\nテキスト")
+    );
+}
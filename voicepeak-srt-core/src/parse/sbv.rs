@@ -0,0 +1,30 @@
+use super::{parse_error, parse_timestamp, split_blocks};
+use crate::{Result, SrtBlock};
+
+/// YouTubeのSBV形式の字幕を読み込む
+///
+/// 各ブロックは`開始,終了`の行とテキストの行からなる
+pub fn parse_sbv(text: &str) -> Result<Vec<SrtBlock>> {
+    let mut blocks = Vec::new();
+    for (line_no, lines) in split_blocks(text) {
+        let (start, end) = lines[0]
+            .split_once(',')
+            .and_then(|(start, end)| Some((parse_timestamp(start)?, parse_timestamp(end)?)))
+            .ok_or_else(|| parse_error(line_no, "タイムスタンプを読み込めません"))?;
+        let text = lines[1..].join("\n");
+
+        blocks.push(SrtBlock::new(blocks.len() + 1, start, end, text));
+    }
+
+    Ok(blocks)
+}
+
+#[test]
+fn test_parse_sbv() {
+    let text = "0:00:01.000,0:00:02.500\n一つ目\n\n0:00:02.500,0:00:04.000\n二つ目\n続き\n";
+    let blocks = parse_sbv(text).unwrap();
+
+    assert_eq!(2, blocks.len());
+    assert_eq!(2500, blocks[0].end.as_millis());
+    assert_eq!("二つ目\n続き", blocks[1].text);
+}
@@ -0,0 +1,53 @@
+use super::{parse_error, parse_timing_line, split_blocks};
+use crate::{Result, SrtBlock};
+
+/// srt形式の字幕を読み込む
+///
+/// 番号の行がないブロックは、ファイル内での順番を番号にする
+pub fn parse_srt(text: &str) -> Result<Vec<SrtBlock>> {
+    let mut blocks = Vec::new();
+
+    for (n, (line_no, lines)) in split_blocks(text).into_iter().enumerate() {
+        // 番号の行は省略されていてもよい
+        let (index, timing_at) = match lines[0].trim().parse::<usize>() {
+            Ok(index) if lines.len() > 1 => (index, 1),
+            _ => (n + 1, 0),
+        };
+        let (start, end) = parse_timing_line(lines[timing_at])
+            .ok_or_else(|| parse_error(line_no + timing_at, "タイムスタンプを読み込めません"))?;
+        let text = lines[timing_at + 1..].join("\n");
+
+        blocks.push(SrtBlock::new(index, start, end, text));
+    }
+
+    Ok(blocks)
+}
+
+#[test]
+fn test_parse_srt_round_trip() {
+    let text = std::fs::read_to_string("../subtitles.srt").unwrap();
+    let blocks = parse_srt(&text).unwrap();
+
+    let mut out = Vec::new();
+    crate::write_srt(blocks, &mut out).unwrap();
+    assert_eq!(text, String::from_utf8(out).unwrap());
+}
+
+#[test]
+fn test_parse_srt_crlf_and_multiline() {
+    let text = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,000\r\n一行目\r\n二行目\r\n\r\n\r\n2\r\n00:00:02,000 --> 00:00:03,500\r\n三行目\r\n";
+    let blocks = parse_srt(text).unwrap();
+
+    assert_eq!(2, blocks.len());
+    assert_eq!("一行目\n二行目", blocks[0].text);
+    assert_eq!(3500, blocks[1].end.as_millis());
+}
+
+#[test]
+fn test_parse_srt_error() {
+    let text = "1\n00:00:01,000 --> 00:00:02,000\nok\n\n2\n00:00:0x,000 --> 00:00:03,000\nng";
+    assert!(matches!(
+        parse_srt(text),
+        Err(crate::Error::Parse { line: 6, .. })
+    ));
+}
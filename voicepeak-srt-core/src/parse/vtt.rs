@@ -0,0 +1,50 @@
+use super::{parse_error, parse_timing_line, split_blocks, strip_bom};
+use crate::{Result, SrtBlock};
+
+/// WebVTT形式の字幕を読み込む
+///
+/// NOTE・STYLE・REGIONブロックは読み飛ばし、番号はファイル内での順番にする
+pub fn parse_vtt(text: &str) -> Result<Vec<SrtBlock>> {
+    if !strip_bom(text).starts_with("WEBVTT") {
+        return Err(parse_error(1, "WEBVTTで始まっていません"));
+    }
+
+    let mut blocks = Vec::new();
+    for (line_no, lines) in split_blocks(text).into_iter().skip(1) {
+        let first = lines[0];
+        if ["NOTE", "STYLE", "REGION"]
+            .iter()
+            .any(|kw| first == *kw || first.starts_with(&format!("{} ", kw)))
+        {
+            continue;
+        }
+
+        // 識別子の行は省略されていてもよい
+        let timing_at = if first.contains("-->") { 0 } else { 1 };
+        let (start, end) = lines
+            .get(timing_at)
+            .and_then(|line| parse_timing_line(line))
+            .ok_or_else(|| parse_error(line_no + timing_at, "タイムスタンプを読み込めません"))?;
+        let text = lines[timing_at + 1..].join("\n");
+
+        blocks.push(SrtBlock::new(blocks.len() + 1, start, end, text));
+    }
+
+    Ok(blocks)
+}
+
+#[test]
+fn test_parse_vtt() {
+    let text = "WEBVTT - title\n\nNOTE 作成メモ\n\nintro\n00:01.000 --> 00:02.500 line:0\n一つ目\n\n00:00:02.500 --> 00:00:04.000\n二つ目\n";
+    let blocks = parse_vtt(text).unwrap();
+
+    assert_eq!(2, blocks.len());
+    assert_eq!((1, "一つ目"), (blocks[0].index, blocks[0].text.as_str()));
+    assert_eq!(2500, blocks[0].end.as_millis());
+    assert_eq!(4000, blocks[1].end.as_millis());
+}
+
+#[test]
+fn test_parse_vtt_without_header() {
+    assert!(parse_vtt("00:01.000 --> 00:02.000\ntext").is_err());
+}
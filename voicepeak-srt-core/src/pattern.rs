@@ -0,0 +1,262 @@
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::{Error, Result, Timeline};
+
+/// ファイル名から連番・話者・テキストを取り出すパターン
+///
+/// 拡張子を除いたファイル名に対して正規表現を適用する。
+/// 名前付きキャプチャ`seq`(必須)・`speaker`・`emotion`・`text`を使う。
+/// `text`があればtxtがない連番はファイル名のテキストを使う
+///
+/// ```
+/// use voicepeak_srt_core::FilePattern;
+///
+/// let pattern = FilePattern::new(r"^take-(?P<seq>\d+)$").unwrap();
+/// assert_eq!(Some(12), pattern.sequence("take-12.wav".as_ref()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FilePattern {
+    regex: Regex,
+    first: usize,
+    /// 連番の代わりに使う並び順
+    timeline: Option<Timeline>,
+}
+
+impl FilePattern {
+    /// 正規表現からパターンを作成する
+    pub fn new(pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern).map_err(|e| Error::InvalidPattern {
+            message: e.to_string(),
+        })?;
+        if !regex.capture_names().any(|name| name == Some("seq")) {
+            return Err(Error::InvalidPattern {
+                message: "名前付きキャプチャseqがありません".to_string(),
+            });
+        }
+
+        Ok(Self {
+            regex,
+            first: 0,
+            timeline: None,
+        })
+    }
+
+    /// VOICEVOXの書き出し(`001_ずんだもん（ノーマル）_テキスト.wav`)に合わせたパターン
+    ///
+    /// 話者はスタイルを除いた名前にし、スタイルは`emotion`にする。連番は1から始まる
+    pub fn voicevox() -> Self {
+        Self::new(r"^(?P<seq>\d+)_(?P<speaker>[^_（]+)(?:（(?P<emotion>[^）]*)）)?_(?P<text>.*)$")
+            .unwrap()
+            .first_sequence(1)
+    }
+
+    /// A.I.VOICEの書き出し(`琴葉 茜_0001_テキスト.wav`)に合わせたパターン
+    ///
+    /// txtはShift-JISで書き出されるため[`TxtEncoding::ShiftJis`](crate::TxtEncoding::ShiftJis)と合わせて使う
+    pub fn ai_voice() -> Self {
+        Self::new(r"^(?P<speaker>[^_]+)_(?P<seq>\d+)(?:_(?P<text>.*))?$")
+            .unwrap()
+            .first_sequence(1)
+    }
+
+    /// CeVIO AIの書き出し(`0001_さとうささら_テキスト.wav`)に合わせたパターン
+    ///
+    /// txtはShift-JISで書き出されるため[`TxtEncoding::ShiftJis`](crate::TxtEncoding::ShiftJis)と合わせて使う
+    pub fn cevio() -> Self {
+        Self::new(r"^(?P<seq>\d+)_(?P<speaker>[^_]+)(?:_(?P<text>.*))?$")
+            .unwrap()
+            .first_sequence(1)
+    }
+
+    /// CoeFontの一括ダウンロード(`001-アリアル_テキスト.wav`)に合わせたパターン
+    ///
+    /// 話者はCoeFontの名前にする。txtはUTF-8で、ないものはファイル名のテキストを使う
+    pub fn coefont() -> Self {
+        Self::new(r"^(?P<seq>\d+)-(?P<speaker>[^_]+)(?:_(?P<text>.*))?$")
+            .unwrap()
+            .first_sequence(1)
+    }
+
+    /// ゆっくり実況の一括書き出し(`001.wav`・`001_霊夢.wav`)に合わせたパターン
+    ///
+    /// テキストは別の一覧から読み込むため、ファイル名からは連番と話者だけを取り出す
+    pub fn yukkuri() -> Self {
+        Self::new(r"^(?P<seq>\d+)(?:_(?P<speaker>[^_]+))?$")
+            .unwrap()
+            .first_sequence(1)
+    }
+
+    /// 最初の連番を指定する(これより前の連番は抜けていても扱わない)
+    pub fn first_sequence(mut self, first: usize) -> Self {
+        self.first = first;
+        self
+    }
+
+    /// ファイル名の連番の代わりに並び順の位置(1から)を連番にする
+    ///
+    /// 並び順にないファイルは連番のないファイルとして扱う。話者とテキストも並び順にあればそちらを使う
+    pub fn with_timeline(mut self, timeline: Timeline) -> Self {
+        self.timeline = Some(timeline);
+        self.first = 1;
+        self
+    }
+
+    /// 並び順を連番の代わりに使うか
+    pub(crate) fn has_timeline(&self) -> bool {
+        self.timeline.is_some()
+    }
+
+    /// 最初の連番
+    pub(crate) fn first(&self) -> usize {
+        self.first
+    }
+
+    /// ファイル名から連番を取り出す
+    pub fn sequence(&self, path: &Path) -> Option<usize> {
+        if let Some(timeline) = &self.timeline {
+            return timeline.find(path).map(|(seq, _)| seq);
+        }
+        self.capture(path, "seq")?.parse().ok()
+    }
+
+    /// 連番が`007`のように0埋めされているか
+    pub(crate) fn is_zero_padded(&self, path: &Path) -> bool {
+        self.timeline.is_none()
+            && self
+                .capture(path, "seq")
+                .is_some_and(|seq| seq.len() > 1 && seq.starts_with('0'))
+    }
+
+    /// ファイル名から話者を取り出す
+    pub fn speaker(&self, path: &Path) -> Option<String> {
+        self.timeline
+            .as_ref()
+            .and_then(|timeline| timeline.find(path)?.1.narrator.clone())
+            .or_else(|| self.capture(path, "speaker"))
+    }
+
+    /// ファイル名から感情やスタイルを取り出す
+    pub fn emotion(&self, path: &Path) -> Option<String> {
+        self.capture(path, "emotion")
+    }
+
+    /// ファイル名からテキストを取り出す
+    pub fn text(&self, path: &Path) -> Option<String> {
+        self.timeline
+            .as_ref()
+            .and_then(|timeline| timeline.find(path)?.1.text.clone())
+            .or_else(|| self.capture(path, "text"))
+    }
+
+    /// 正規表現
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    /// ファイル名からテキストを取り出すパターンか
+    pub fn has_text(&self) -> bool {
+        self.regex.capture_names().any(|name| name == Some("text"))
+            || self.timeline.as_ref().is_some_and(Timeline::has_text)
+    }
+
+    fn capture(&self, path: &Path, name: &str) -> Option<String> {
+        let stem = path.file_stem()?.to_str()?;
+        let captures = self.regex.captures(stem)?;
+        Some(captures.name(name)?.as_str().to_string())
+    }
+}
+
+impl Default for FilePattern {
+    /// Voicepeakの書き出し(`000-voice.wav`)に合わせ、先頭の数字を連番とする
+    fn default() -> Self {
+        Self::new(r"^(?P<seq>\d+)").unwrap()
+    }
+}
+
+#[test]
+fn test_file_pattern_default() {
+    let pattern = FilePattern::default();
+    assert_eq!(Some(14), pattern.sequence(Path::new("voice/014-voice.wav")));
+    assert_eq!(None, pattern.sequence(Path::new("voice.wav")));
+    assert_eq!(None, pattern.speaker(Path::new("000-voice.wav")));
+}
+
+#[test]
+fn test_file_pattern_captures() {
+    let pattern = FilePattern::new(r"^(?P<speaker>[^_]+)_(?P<seq>\d+)_(?P<text>.*)$").unwrap();
+    let path = Path::new("ナレーター_3_こんにちは.wav");
+
+    assert_eq!(Some(3), pattern.sequence(path));
+    assert_eq!(Some("ナレーター".to_string()), pattern.speaker(path));
+    assert_eq!(Some("こんにちは".to_string()), pattern.text(path));
+    assert!(pattern.has_text());
+}
+
+#[test]
+fn test_file_pattern_voicevox() {
+    let pattern = FilePattern::voicevox();
+    let path = Path::new("001_ずんだもん（ノーマル）_こんにちは.wav");
+
+    assert_eq!(Some(1), pattern.sequence(path));
+    assert_eq!(Some("ずんだもん".to_string()), pattern.speaker(path));
+    assert_eq!(Some("ノーマル".to_string()), pattern.emotion(path));
+    assert_eq!(Some("こんにちは".to_string()), pattern.text(path));
+    assert_eq!(1, pattern.first());
+}
+
+#[test]
+fn test_file_pattern_ai_voice_and_cevio() {
+    let path = Path::new("琴葉 茜_0002_こんにちは.wav");
+    assert_eq!(Some(2), FilePattern::ai_voice().sequence(path));
+    assert_eq!(
+        Some("琴葉 茜".to_string()),
+        FilePattern::ai_voice().speaker(path)
+    );
+
+    let path = Path::new("0003_さとうささら.wav");
+    assert_eq!(Some(3), FilePattern::cevio().sequence(path));
+    assert_eq!(
+        Some("さとうささら".to_string()),
+        FilePattern::cevio().speaker(path)
+    );
+    assert_eq!(None, FilePattern::cevio().text(path));
+}
+
+#[test]
+fn test_file_pattern_coefont() {
+    let pattern = FilePattern::coefont();
+    let path = Path::new("012-アリアル_おはようございます.wav");
+
+    assert_eq!(Some(12), pattern.sequence(path));
+    assert_eq!(Some("アリアル".to_string()), pattern.speaker(path));
+    assert_eq!(Some("おはようございます".to_string()), pattern.text(path));
+}
+
+#[test]
+fn test_file_pattern_without_seq() {
+    assert!(matches!(
+        FilePattern::new(r"^take-\d+$"),
+        Err(Error::InvalidPattern { .. })
+    ));
+    assert!(matches!(
+        FilePattern::new(r"(?P<seq>"),
+        Err(Error::InvalidPattern { .. })
+    ));
+}
+
+#[test]
+fn test_file_pattern_with_timeline() {
+    let timeline = Timeline::parse("outro.wav\tB\n000-intro.wav\n").unwrap();
+    let pattern = FilePattern::default().with_timeline(timeline);
+
+    assert_eq!(Some(2), pattern.sequence(Path::new("voice/000-intro.wav")));
+    assert_eq!(Some(1), pattern.sequence(Path::new("voice/outro.txt")));
+    assert_eq!(None, pattern.sequence(Path::new("voice/001-extra.wav")));
+    assert_eq!(
+        Some("B".to_string()),
+        pattern.speaker(Path::new("outro.wav"))
+    );
+    assert!(!pattern.is_zero_padded(Path::new("000-intro.wav")));
+}
@@ -0,0 +1,50 @@
+//! 音声の長さの並列取得
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use rayon::prelude::*;
+
+use crate::{DirSource, DurationProvider, InputSource};
+
+/// 先に並列で取得しておいた長さを返す
+///
+/// 取得していないファイルは元の実装で取得する
+pub(crate) struct PrefetchedDurations {
+    durations: RefCell<HashMap<PathBuf, io::Result<Duration>>>,
+    provider: Arc<dyn DurationProvider + Send + Sync>,
+}
+
+impl PrefetchedDurations {
+    /// `files`のうちwavの長さをCPUのコア数だけ並列に取得する
+    pub(crate) fn new(
+        source: &DirSource,
+        files: &[PathBuf],
+        provider: Arc<dyn DurationProvider + Send + Sync>,
+    ) -> Self {
+        let durations = files
+            .par_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wav"))
+            .map(|path| (path.clone(), provider.duration(source, path)))
+            .collect();
+        Self {
+            durations: RefCell::new(durations),
+            provider,
+        }
+    }
+}
+
+impl DurationProvider for PrefetchedDurations {
+    fn duration(&self, source: &dyn InputSource, path: &Path) -> io::Result<Duration> {
+        match self.durations.borrow_mut().remove(path) {
+            Some(duration) => duration,
+            None => self.provider.duration(source, path),
+        }
+    }
+}
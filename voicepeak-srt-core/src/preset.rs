@@ -0,0 +1,160 @@
+//! 音声合成ソフトごとの書き出しの違い(ファイル名・txtの文字コード)をまとめた設定
+
+use std::io::Read;
+
+use serde::Deserialize;
+
+use crate::{Error, FilePattern, Result, TxtEncoding};
+
+/// 入力のプリセット
+///
+/// ```
+/// use voicepeak_srt_core::preset::InputPreset;
+///
+/// let presets = InputPreset::builtin();
+/// let preset = InputPreset::find(&presets, "voicevox").unwrap();
+/// assert_eq!(Some(1), preset.pattern.sequence("001_ずんだもん（ノーマル）_こんにちは.wav".as_ref()));
+/// ```
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct InputPreset {
+    /// `--preset`で指定する名前
+    pub name: String,
+    /// 説明
+    pub description: String,
+    /// ファイル名のパターン
+    pub pattern: FilePattern,
+    /// txtの文字コード
+    pub encoding: TxtEncoding,
+}
+
+impl InputPreset {
+    /// プリセットを作成する
+    pub fn new(name: &str, description: &str, pattern: FilePattern, encoding: TxtEncoding) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            pattern,
+            encoding,
+        }
+    }
+
+    /// 組み込みのプリセット
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::new(
+                "voicepeak",
+                "Voicepeak(000-voice.wav)",
+                FilePattern::default(),
+                TxtEncoding::Utf8,
+            ),
+            Self::new(
+                "voicevox",
+                "VOICEVOX(001_ずんだもん（ノーマル）_テキスト.wav)",
+                FilePattern::voicevox(),
+                TxtEncoding::Utf8,
+            ),
+            Self::new(
+                "ai-voice",
+                "A.I.VOICE(琴葉 茜_0001_テキスト.wav、txtはShift-JIS)",
+                FilePattern::ai_voice(),
+                TxtEncoding::ShiftJis,
+            ),
+            Self::new(
+                "cevio",
+                "CeVIO AI(0001_さとうささら_テキスト.wav、txtはShift-JIS)",
+                FilePattern::cevio(),
+                TxtEncoding::ShiftJis,
+            ),
+            Self::new(
+                "coefont",
+                "CoeFont(001-アリアル_テキスト.wav)",
+                FilePattern::coefont(),
+                TxtEncoding::Utf8,
+            ),
+            Self::new(
+                "yukkuri",
+                "SofTalk・AquesTalk(001.wav・001_霊夢.wav)。--text-listと使う",
+                FilePattern::yukkuri(),
+                TxtEncoding::Utf8,
+            ),
+        ]
+    }
+
+    /// 名前でプリセットを探す
+    pub fn find<'a>(presets: &'a [Self], name: &str) -> Option<&'a Self> {
+        presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// JSONの設定からプリセットを読み込む
+    ///
+    /// `[{"name": "mytool", "pattern": "^(?P<seq>\\d+)", "encoding": "shift-jis", "first_sequence": 1}]`の形式。
+    /// `description`・`encoding`(`utf-8`か`shift-jis`)・`first_sequence`は省略できる
+    pub fn from_json<R: Read>(reader: R) -> Result<Vec<Self>> {
+        let configs: Vec<PresetConfig> =
+            serde_json::from_reader(reader).map_err(|e| Error::InvalidPreset {
+                message: e.to_string(),
+            })?;
+        configs
+            .into_iter()
+            .map(|config| {
+                let pattern =
+                    FilePattern::new(&config.pattern)?.first_sequence(config.first_sequence);
+                Ok(Self {
+                    name: config.name,
+                    description: config.description,
+                    pattern,
+                    encoding: config.encoding.into(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// JSONでのプリセットの設定
+#[derive(Deserialize)]
+struct PresetConfig {
+    name: String,
+    #[serde(default)]
+    description: String,
+    pattern: String,
+    #[serde(default)]
+    encoding: EncodingConfig,
+    #[serde(default)]
+    first_sequence: usize,
+}
+
+/// JSONでの文字コードの指定
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+enum EncodingConfig {
+    #[default]
+    Utf8,
+    ShiftJis,
+}
+
+impl From<EncodingConfig> for TxtEncoding {
+    fn from(encoding: EncodingConfig) -> Self {
+        match encoding {
+            EncodingConfig::Utf8 => TxtEncoding::Utf8,
+            EncodingConfig::ShiftJis => TxtEncoding::ShiftJis,
+        }
+    }
+}
+
+#[test]
+fn test_preset_from_json() {
+    let presets = InputPreset::from_json(
+        r#"[{"name": "mytool", "pattern": "^take(?P<seq>\\d+)", "encoding": "shift-jis", "first_sequence": 1}]"#
+            .as_bytes(),
+    )
+    .unwrap();
+
+    assert_eq!("mytool", presets[0].name);
+    assert_eq!(TxtEncoding::ShiftJis, presets[0].encoding);
+    assert_eq!(Some(3), presets[0].pattern.sequence("take3.wav".as_ref()));
+    assert!(matches!(
+        InputPreset::from_json(r#"[{"name": "x", "pattern": "^\\d+"}]"#.as_bytes()),
+        Err(Error::InvalidPattern { .. })
+    ));
+}
@@ -0,0 +1,44 @@
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::SrtBlock;
+
+/// 字幕ブロックをまとめたプロジェクト
+///
+/// JSONでの書き出し・読み込みはこの構造をそのまま使う
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Project {
+    /// 字幕ブロック
+    pub cues: Vec<SrtBlock>,
+}
+
+impl Project {
+    /// 字幕ブロックからプロジェクトを作成する
+    pub fn new(cues: Vec<SrtBlock>) -> Self {
+        Self { cues }
+    }
+
+    /// JSONからプロジェクトを読み込む
+    pub fn from_json<R: Read>(reader: R) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+
+    /// プロジェクトをJSONで書き出す
+    pub fn write_json<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+}
+
+#[test]
+fn test_project_json_round_trip() {
+    let files = crate::extract_wav_and_txt(std::path::Path::new("../voice")).unwrap();
+    let project = Project::new(crate::make_srt_blocks(files).unwrap());
+
+    let mut json = Vec::new();
+    project.write_json(&mut json).unwrap();
+    let restored = Project::from_json(json.as_slice()).unwrap();
+
+    assert_eq!(project, restored);
+}
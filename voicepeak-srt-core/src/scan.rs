@@ -0,0 +1,405 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    chapters::CHAPTERS_FILE_NAME, diagnostics::Diagnostics, DirSource, Error, FilePattern,
+    InputSource, Result,
+};
+
+/// 同じ連番のファイルが複数あったときの扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DuplicatePolicy {
+    /// エラーにする
+    #[default]
+    Error,
+    /// 更新日時が最も新しいwavと、それと同じ名前のtxtを使う(警告する)
+    Newest,
+}
+
+/// ディレクトリからwavとtxtを取り出す
+pub fn extract_wav_and_txt(path: &Path) -> Result<Vec<std::path::PathBuf>> {
+    extract_wav_and_txt_from(&DirSource::new(path))
+}
+
+/// 入力元からwavとtxtを取り出す
+pub fn extract_wav_and_txt_from<S: InputSource + ?Sized>(
+    source: &S,
+) -> Result<Vec<std::path::PathBuf>> {
+    extract_wav_and_txt_with(source, &FilePattern::default())
+}
+
+/// ファイル名のパターンを指定して入力元からwavとtxtを取り出す
+///
+/// パターンがテキストを取り出す場合、txtがない連番も許す
+pub fn extract_wav_and_txt_with<S: InputSource + ?Sized>(
+    source: &S,
+    pattern: &FilePattern,
+) -> Result<Vec<std::path::PathBuf>> {
+    scan(
+        source,
+        pattern,
+        DuplicatePolicy::default(),
+        false,
+        &mut Diagnostics::default(),
+    )
+}
+
+/// 問題の扱いを指定して入力元からwavとtxtを取り出す
+pub(crate) fn scan<S: InputSource + ?Sized>(
+    source: &S,
+    pattern: &FilePattern,
+    duplicates: DuplicatePolicy,
+    without_txt: bool,
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<std::path::PathBuf>> {
+    // パスが存在しなければエラー
+    let files: Vec<std::path::PathBuf> = source
+        .files()
+        .map_err(Error::PathNotFound)?
+        .into_iter()
+        .filter(|path| match path.extension() {
+            Some(ext) => ext == "wav" || ext == "txt",
+            None => false,
+        })
+        // 章の一覧は音声のtxtではない
+        .filter(|path| path.file_name() != Some(CHAPTERS_FILE_NAME.as_ref()))
+        // 並び順を使う場合、並び順にないtxt(一覧のファイル自身など)は音声のtxtではない
+        .filter(|path| {
+            !pattern.has_timeline()
+                || path.extension().is_some_and(|ext| ext == "wav")
+                || pattern.sequence(path).is_some()
+        })
+        .collect();
+
+    // 同じ連番のファイルが複数あれば指定に従って1組に絞る
+    let files = resolve_duplicates(source, files, pattern, duplicates, diagnostics)?;
+
+    let n_wav = files
+        .iter()
+        .filter(|p| p.extension().unwrap() == "wav")
+        .count();
+    let n_txt = files.len() - n_wav;
+
+    // パスの中にwavが入っていなければエラー
+    if n_wav == 0 {
+        return Err(Error::MissingWav);
+    };
+
+    // ファイル名や台本からテキストを取り出す場合、txtはなくてもよい
+    let txt_optional = without_txt || pattern.has_text();
+
+    // パスの中にtxtが入っていなければエラー
+    if n_txt == 0 && !txt_optional {
+        return Err(Error::MissingTxt);
+    };
+
+    // wavとtxtが同数でなければエラー
+    if n_wav != n_txt && !txt_optional {
+        diagnostics.recover(Error::CountMismatch {
+            wav: n_wav,
+            txt: n_txt,
+        })?;
+    }
+
+    // 連番ごとにwavとtxtが揃っていなければエラー
+    let (mut wav_only, txt_only) = unpaired_sequences(&files, pattern);
+    if txt_optional {
+        wav_only.clear();
+    }
+    if !wav_only.is_empty() || !txt_only.is_empty() {
+        diagnostics.recover(Error::UnpairedSequences { wav_only, txt_only })?;
+    }
+
+    // 0埋めされた連番が途中で抜けていればエラー
+    // (0埋めされていない場合は`1_intro`, `10_end`のように数値の順に並べるだけにする)
+    let padded = files.iter().any(|f| pattern.is_zero_padded(f));
+    let missing = missing_sequences(&files, pattern);
+    if padded && !missing.is_empty() {
+        diagnostics.recover(Error::SequenceGap { missing })?;
+    }
+
+    // 連番を取り出せないファイルは使われないため警告する
+    for path in files.iter().filter(|f| pattern.sequence(f).is_none()) {
+        diagnostics.warn(Error::IgnoredFile {
+            path: path.to_path_buf(),
+        })?;
+    }
+
+    Ok(files)
+}
+
+/// 重複している連番のファイルを1組に絞る
+fn resolve_duplicates<S: InputSource + ?Sized>(
+    source: &S,
+    files: Vec<PathBuf>,
+    pattern: &FilePattern,
+    policy: DuplicatePolicy,
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<PathBuf>> {
+    let is = |path: &Path, ext: &str| path.extension().is_some_and(|e| e == ext);
+
+    let mut groups: BTreeMap<usize, Vec<&PathBuf>> = BTreeMap::new();
+    for path in &files {
+        if let Some(seq) = pattern.sequence(path) {
+            groups.entry(seq).or_default().push(path);
+        }
+    }
+    let duplicated: Vec<(usize, Vec<&PathBuf>)> = groups
+        .into_iter()
+        .filter(|(_, paths)| {
+            paths.iter().filter(|p| is(p, "wav")).count() > 1
+                || paths.iter().filter(|p| is(p, "txt")).count() > 1
+        })
+        .collect();
+    if duplicated.is_empty() {
+        return Ok(files);
+    }
+
+    if policy == DuplicatePolicy::Error {
+        return Err(Error::DuplicateSequence {
+            seqs: duplicated.iter().map(|(seq, _)| *seq).collect(),
+        });
+    }
+
+    let newest = |paths: &[&PathBuf], ext: &str| -> Result<Option<PathBuf>> {
+        let mut newest = None;
+        for path in paths.iter().filter(|p| is(p, ext)) {
+            let modified = source.modified(path).map_err(|e| Error::Read {
+                path: path.to_path_buf(),
+                source: e,
+            })?;
+            if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+                newest = Some((modified, path.to_path_buf()));
+            }
+        }
+        Ok(newest.map(|(_, path)| path))
+    };
+
+    let mut removed = Vec::new();
+    for (seq, paths) in &duplicated {
+        let wav = newest(paths, "wav")?;
+        let txt = match &wav {
+            Some(wav) if paths.contains(&&wav.with_extension("txt")) => {
+                Some(wav.with_extension("txt"))
+            }
+            _ => newest(paths, "txt")?,
+        };
+        removed.extend(
+            paths
+                .iter()
+                .filter(|p| Some(**p) != wav.as_ref() && Some(**p) != txt.as_ref())
+                .map(|p| p.to_path_buf()),
+        );
+        if let Some(kept) = wav.or(txt) {
+            diagnostics.warn(Error::DuplicateResolved { seq: *seq, kept })?;
+        }
+    }
+
+    Ok(files.into_iter().filter(|f| !removed.contains(f)).collect())
+}
+
+/// wavしかない連番とtxtしかない連番を返す
+fn unpaired_sequences(
+    files: &[std::path::PathBuf],
+    pattern: &FilePattern,
+) -> (Vec<usize>, Vec<usize>) {
+    let seqs_of = |ext: &str| -> std::collections::BTreeSet<usize> {
+        files
+            .iter()
+            .filter(|f| f.extension().is_some_and(|e| e == ext))
+            .filter_map(|f| pattern.sequence(f))
+            .collect()
+    };
+    let wavs = seqs_of("wav");
+    let txts = seqs_of("txt");
+
+    (
+        wavs.difference(&txts).copied().collect(),
+        txts.difference(&wavs).copied().collect(),
+    )
+}
+
+/// 0から最大の連番までで、ファイルがない連番を返す
+fn missing_sequences(files: &[std::path::PathBuf], pattern: &FilePattern) -> Vec<usize> {
+    let seqs: std::collections::BTreeSet<usize> =
+        files.iter().filter_map(|f| pattern.sequence(f)).collect();
+    match seqs.last() {
+        Some(&max) => (pattern.first()..max)
+            .filter(|seq| !seqs.contains(seq))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+#[test]
+fn test_extract_wav_and_txt_ok() {
+    let path = Path::new("../voice");
+    assert_eq!(8, extract_wav_and_txt(path).unwrap().len());
+}
+
+#[test]
+fn test_extract_wav_and_txt_no_exits_path() {
+    let path = Path::new("no/exits/path/");
+    assert!(matches!(
+        extract_wav_and_txt(path),
+        Err(Error::PathNotFound(_))
+    ));
+}
+
+#[test]
+fn test_extract_wav_and_txt_no_wav() {
+    let path = Path::new("test_resource/no_wav");
+    assert!(matches!(extract_wav_and_txt(path), Err(Error::MissingWav)));
+}
+
+#[test]
+fn test_extract_wav_and_txt_no_txt() {
+    let path = Path::new("test_resource/no_txt");
+    assert!(matches!(extract_wav_and_txt(path), Err(Error::MissingTxt)));
+}
+
+#[test]
+fn test_extract_wav_and_txt_no_match() {
+    let path = Path::new("test_resource/not_match");
+    assert!(matches!(
+        extract_wav_and_txt(path),
+        Err(Error::CountMismatch { wav: 1, txt: 2 })
+    ));
+}
+
+#[test]
+fn test_extract_wav_and_txt_sequence_gap() {
+    let mut source = crate::MemorySource::new();
+    for name in [
+        "000-a.wav",
+        "000-a.txt",
+        "002-a.wav",
+        "002-a.txt",
+        "004-a.wav",
+        "004-a.txt",
+    ] {
+        source.insert(name, Vec::new());
+    }
+    let e = extract_wav_and_txt_from(&source).unwrap_err();
+
+    assert!(matches!(&e, Error::SequenceGap { missing } if missing == &[1, 3]));
+    assert_eq!("連番が抜けています: 001, 003", e.to_string());
+}
+
+#[test]
+fn test_extract_wav_and_txt_first_sequence() {
+    let mut source = crate::MemorySource::new();
+    source.insert("001_ずんだもん（ノーマル）_こんにちは.wav", Vec::new());
+    source.insert("002_四国めたん（あまあま）_はい.wav", Vec::new());
+
+    let files = extract_wav_and_txt_with(&source, &FilePattern::voicevox()).unwrap();
+    assert_eq!(2, files.len());
+}
+
+#[test]
+fn test_extract_wav_and_txt_with_pattern() {
+    let mut source = crate::MemorySource::new();
+    for name in ["take-0_hello.wav", "take-1_bye.wav", "take-1_bye.txt"] {
+        source.insert(name, Vec::new());
+    }
+    let pattern = FilePattern::new(r"^take-(?P<seq>\d+)_(?P<text>.*)$").unwrap();
+
+    assert_eq!(
+        3,
+        extract_wav_and_txt_with(&source, &pattern).unwrap().len()
+    );
+    assert!(matches!(
+        extract_wav_and_txt_from(&source),
+        Err(Error::CountMismatch { wav: 2, txt: 1 })
+    ));
+}
+
+#[test]
+fn test_scan_strictness() {
+    let mut source = crate::MemorySource::new();
+    for name in [
+        "000-a.wav",
+        "000-a.txt",
+        "001-a.wav",
+        "last.wav",
+        "last.txt",
+    ] {
+        source.insert(name, Vec::new());
+    }
+    let pattern = FilePattern::default();
+    let warnings = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let sink = {
+        let warnings = std::rc::Rc::clone(&warnings);
+        Box::new(move |e: Error| warnings.borrow_mut().push(e.to_string()))
+    };
+
+    let mut lenient = Diagnostics::new(crate::Strictness::Lenient, sink);
+    let duplicates = DuplicatePolicy::Error;
+    assert_eq!(
+        5,
+        scan(&source, &pattern, duplicates, false, &mut lenient)
+            .unwrap()
+            .len()
+    );
+    assert_eq!(4, warnings.borrow().len());
+
+    let mut strict = Diagnostics::new(crate::Strictness::Strict, Box::new(|_| {}));
+    assert!(matches!(
+        scan(&source, &pattern, duplicates, false, &mut strict),
+        Err(Error::CountMismatch { wav: 3, txt: 2 })
+    ));
+}
+
+#[test]
+fn test_scan_duplicates() {
+    use std::time::{Duration, SystemTime};
+
+    // ファイル名の"new"を新しい更新日時として扱う入力元
+    struct Dated(crate::MemorySource);
+    impl InputSource for Dated {
+        fn files(&self) -> std::io::Result<Vec<PathBuf>> {
+            self.0.files()
+        }
+        fn open(&self, path: &Path) -> std::io::Result<Box<dyn crate::ReadSeek + '_>> {
+            self.0.open(path)
+        }
+        fn modified(&self, path: &Path) -> std::io::Result<SystemTime> {
+            let new = path.to_string_lossy().contains("new");
+            Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(new as u64))
+        }
+    }
+
+    let mut source = crate::MemorySource::new();
+    for name in [
+        "000.wav",
+        "000.txt",
+        "001_new.wav",
+        "001_new.txt",
+        "001_old.wav",
+        "001_old.txt",
+    ] {
+        source.insert(name, Vec::new());
+    }
+    let source = Dated(source);
+    let pattern = FilePattern::default();
+
+    assert!(matches!(
+        extract_wav_and_txt_from(&source),
+        Err(Error::DuplicateSequence { seqs }) if seqs == [1]
+    ));
+
+    let files = scan(
+        &source,
+        &pattern,
+        DuplicatePolicy::Newest,
+        false,
+        &mut Diagnostics::default(),
+    )
+    .unwrap();
+    assert_eq!(4, files.len());
+    assert!(files.contains(&PathBuf::from("001_new.txt")));
+    assert!(!files.contains(&PathBuf::from("001_old.wav")));
+}
@@ -0,0 +1,200 @@
+//! `話者: セリフ`形式の台本
+
+/// 台本の1行(1つの音声に対応する)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ScreenplayLine {
+    /// 話者
+    pub speaker: Option<String>,
+    /// セリフ
+    pub text: String,
+}
+
+/// 話者名として扱う最大文字数(これより長い場合はセリフ中のコロンとみなす)
+const MAX_SPEAKER_CHARS: usize = 20;
+
+/// 台本を読み込む
+///
+/// - `話者: セリフ`(全角の`：`も可)の行は話者付きのセリフ、コロンのない行は話者なしのセリフ
+/// - `(ト書き)`のように全体が括弧で囲まれた行、空行、`#`で始まる行は飛ばす
+/// - セリフの先頭の`（小声で）`のような括弧書きは取り除く
+/// - `![拍手]`の行は音の注記(SDH向け)として、そのまま1つの音声に対応させる
+pub fn parse_screenplay(text: &str) -> Vec<ScreenplayLine> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            if let Some(annotation) = line
+                .strip_prefix('!')
+                .filter(|rest| crate::transform::is_annotation(rest))
+            {
+                return Some(ScreenplayLine {
+                    speaker: None,
+                    text: annotation.trim().to_string(),
+                });
+            }
+            if is_direction(line) {
+                return None;
+            }
+            let (speaker, text) = split_speaker(line);
+            let text = strip_direction(text);
+            (!text.is_empty()).then(|| ScreenplayLine {
+                speaker: speaker.map(str::to_string),
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// セリフの一覧を読み込む(ゆっくり実況のSofTalk・AquesTalkの一括書き出し用)
+///
+/// 空行以外の1行を1つのセリフとし、`霊夢,セリフ`のようにカンマかタブの前に話者があれば取り出す
+pub fn parse_text_list(text: &str) -> Vec<ScreenplayLine> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (speaker, text) = match line.split_once([',', '\t']) {
+                Some((speaker, text))
+                    if !speaker.is_empty()
+                        && speaker.chars().count() <= MAX_SPEAKER_CHARS
+                        && !speaker.chars().any(char::is_whitespace) =>
+                {
+                    (Some(speaker), text.trim())
+                }
+                _ => (None, line),
+            };
+            ScreenplayLine {
+                speaker: speaker.map(str::to_string),
+                text: text.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// すべての音声のテキストをまとめた1つのファイルを読み込む
+///
+/// `---`だけの行があればそれを区切りとし(区切りの間が空なら空のテキストにする)、
+/// なければ空行で区切る。セリフの中の改行はそのまま残す
+pub fn parse_combined_script(text: &str) -> Vec<ScreenplayLine> {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let dashed = lines.iter().any(|line| line.trim() == "---");
+    let entries: Vec<String> = match dashed {
+        true => lines
+            .split(|line| line.trim() == "---")
+            .map(|entry| entry.join("\n").trim().to_string())
+            .collect(),
+        false => lines
+            .split(|line| line.trim().is_empty())
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| entry.join("\n").trim().to_string())
+            .collect(),
+    };
+    // 最後の区切りの後ろが空なら、区切りで終わっているだけとみなす
+    let len = match entries.last() {
+        Some(last) if dashed && last.is_empty() => entries.len() - 1,
+        _ => entries.len(),
+    };
+    entries
+        .into_iter()
+        .take(len)
+        .map(|text| ScreenplayLine {
+            speaker: None,
+            text,
+        })
+        .collect()
+}
+
+/// 括弧の組
+const BRACKETS: [(char, char); 4] = [('(', ')'), ('（', '）'), ('[', ']'), ('［', '］')];
+
+/// 全体が括弧で囲まれたト書きか
+fn is_direction(line: &str) -> bool {
+    BRACKETS
+        .iter()
+        .any(|&(open, close)| line.starts_with(open) && line.ends_with(close))
+}
+
+/// 行を話者とセリフに分ける
+fn split_speaker(line: &str) -> (Option<&str>, &str) {
+    let Some(pos) = line.find([':', '：']) else {
+        return (None, line);
+    };
+    let speaker = line[..pos].trim();
+    let colon = line[pos..].chars().next().unwrap().len_utf8();
+    if speaker.is_empty()
+        || speaker.chars().count() > MAX_SPEAKER_CHARS
+        || speaker.chars().any(char::is_whitespace)
+    {
+        return (None, line);
+    }
+    (Some(speaker), line[pos + colon..].trim())
+}
+
+/// セリフの先頭の括弧書きを取り除く
+fn strip_direction(text: &str) -> &str {
+    for &(open, close) in &BRACKETS {
+        if let Some(rest) = text.strip_prefix(open) {
+            if let Some(end) = rest.find(close) {
+                return rest[end + close.len_utf8()..].trim();
+            }
+        }
+    }
+    text
+}
+
+#[test]
+fn test_parse_screenplay() {
+    let lines = parse_screenplay(
+        "# 第1話\n\
+         （ベンガジ空港）\n\
+         \n\
+         機長: 離陸する\n\
+         副操縦士：（小声で）了解\n\
+         ![エンジン音]\n\
+         時は1973年2月初旬\n",
+    );
+
+    assert_eq!(4, lines.len());
+    assert_eq!(Some("機長"), lines[0].speaker.as_deref());
+    assert_eq!("離陸する", lines[0].text);
+    assert_eq!(Some("副操縦士"), lines[1].speaker.as_deref());
+    assert_eq!("了解", lines[1].text);
+    assert_eq!(
+        (None, "[エンジン音]"),
+        (lines[2].speaker.as_deref(), lines[2].text.as_str())
+    );
+    assert_eq!(None, lines[3].speaker);
+    assert_eq!("時は1973年2月初旬", lines[3].text);
+}
+
+#[test]
+fn test_parse_text_list() {
+    let lines = parse_text_list("霊夢,ゆっくりしていってね\n\n魔理沙\tだぜ\nこんにちは\n");
+
+    assert_eq!(3, lines.len());
+    assert_eq!(Some("霊夢"), lines[0].speaker.as_deref());
+    assert_eq!("ゆっくりしていってね", lines[0].text);
+    assert_eq!(Some("魔理沙"), lines[1].speaker.as_deref());
+    assert_eq!(None, lines[2].speaker);
+    assert_eq!("こんにちは", lines[2].text);
+}
+
+#[test]
+fn test_parse_combined_script() {
+    let texts = |text| {
+        parse_combined_script(text)
+            .into_iter()
+            .map(|line| line.text)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        vec!["一つ目\n続き", "二つ目"],
+        texts("一つ目\n続き\n\n\n二つ目\n")
+    );
+    assert_eq!(
+        vec!["一つ目\n\n続き", "", "三つ目"],
+        texts("一つ目\n\n続き\n---\n---\n三つ目\n---\n")
+    );
+}
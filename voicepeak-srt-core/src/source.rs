@@ -0,0 +1,190 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufReader, Cursor, Read, Seek},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// 読み込みとシークができるファイル
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// 入力ファイルの取得元
+///
+/// ファイルシステム以外(ブラウザでドロップされたファイルなど)から読み込む場合はこれを実装する
+pub trait InputSource {
+    /// 入力に含まれるファイルの一覧
+    fn files(&self) -> io::Result<Vec<PathBuf>>;
+
+    /// ファイルを開く
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek + '_>>;
+
+    /// ファイルを文字列として読み込む
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let mut text = String::new();
+        self.open(path)?.read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    /// ファイルの更新日時(取得できない入力元ではエラー)
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{}の更新日時を取得できません", path.display()),
+        ))
+    }
+}
+
+/// ディレクトリ直下のファイルを入力にする
+#[derive(Debug, Clone)]
+pub struct DirSource {
+    root: PathBuf,
+}
+
+impl DirSource {
+    /// `root`直下のファイルを入力にする
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl InputSource for DirSource {
+    fn files(&self) -> io::Result<Vec<PathBuf>> {
+        Ok(fs::read_dir(&self.root)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect())
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek + '_>> {
+        open_file(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+}
+
+/// 指定したファイルだけを入力にする(別々のフォルダーにあってもよい)
+#[derive(Debug, Clone, Default)]
+pub struct FileListSource {
+    files: Vec<PathBuf>,
+}
+
+impl FileListSource {
+    /// `files`を入力にする
+    pub fn new<I, P>(files: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        Self {
+            files: files.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl InputSource for FileListSource {
+    fn files(&self) -> io::Result<Vec<PathBuf>> {
+        Ok(self.files.clone())
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek + '_>> {
+        open_file(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+}
+
+/// 長い音声もヒープにコピーせずに読めるよう、メモリマップして開く
+fn open_file(path: &Path) -> io::Result<Box<dyn ReadSeek>> {
+    let file = File::open(path)?;
+    // 空のファイルはマップできないため、そのまま読む
+    #[cfg(not(target_arch = "wasm32"))]
+    if file.metadata()?.len() > 0 {
+        // SAFETY: 読み込み中に他のプロセスがファイルを切り詰めると壊れた内容を読むが、
+        // 入力は書き出し済みの音声を想定している
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        return Ok(Box::new(Cursor::new(map)));
+    }
+    Ok(Box::new(BufReader::new(file)))
+}
+
+/// メモリ上のファイルを入力にする
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemorySource {
+    /// 空の入力を作成する
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ファイルを追加する
+    pub fn insert<P: Into<PathBuf>>(&mut self, name: P, data: Vec<u8>) {
+        self.files.insert(name.into(), data);
+    }
+}
+
+impl InputSource for MemorySource {
+    fn files(&self) -> io::Result<Vec<PathBuf>> {
+        Ok(self.files.keys().cloned().collect())
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek + '_>> {
+        match self.files.get(path) {
+            Some(data) => Ok(Box::new(Cursor::new(data.as_slice()))),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{}が存在しません", path.display()),
+            )),
+        }
+    }
+}
+
+#[test]
+fn test_memory_source() {
+    let mut source = MemorySource::new();
+    source.insert("000-voice.txt", "テキスト".as_bytes().to_vec());
+
+    assert_eq!(
+        vec![PathBuf::from("000-voice.txt")],
+        source.files().unwrap()
+    );
+    assert_eq!(
+        "テキスト",
+        source.read_to_string(Path::new("000-voice.txt")).unwrap()
+    );
+    assert!(source.open(Path::new("001-voice.txt")).is_err());
+}
+
+#[test]
+fn test_file_list_source() {
+    let source = FileListSource::new(["../voice/000-voice.txt"]);
+
+    assert_eq!(
+        vec![PathBuf::from("../voice/000-voice.txt")],
+        source.files().unwrap()
+    );
+    assert!(!source
+        .read_to_string(Path::new("../voice/000-voice.txt"))
+        .unwrap()
+        .is_empty());
+}
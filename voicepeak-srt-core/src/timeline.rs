@@ -0,0 +1,181 @@
+use std::{collections::HashMap, path::Path};
+
+use serde_json::Value;
+
+/// 書き出した音声の並び順(ファイル名の連番より優先する)
+///
+/// Voicepeakの書き出し一覧など、ブロックの順と話者を並べたファイルから読み込む。
+/// ファイル名を変えても順番が崩れないよう、[`FilePattern::with_timeline`](crate::FilePattern::with_timeline)で
+/// 連番の代わりに使う。連番は1から振る
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Timeline {
+    entries: Vec<TimelineEntry>,
+    /// ファイル名から`entries`の位置を引く
+    positions: HashMap<String, usize>,
+}
+
+/// 並び順の1ブロック
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TimelineEntry {
+    /// 拡張子を除いた音声のファイル名
+    pub stem: String,
+    /// ナレーター(話者)
+    pub narrator: Option<String>,
+    /// テキスト(txtがなければ使う)
+    pub text: Option<String>,
+}
+
+impl TimelineEntry {
+    /// 音声のファイル名(フォルダーや拡張子が付いていてもよい)から作成する
+    pub fn new(file: &str) -> Self {
+        let stem = Path::new(file.trim())
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self {
+            stem,
+            narrator: None,
+            text: None,
+        }
+    }
+}
+
+impl Timeline {
+    /// 並び順を読み込む
+    ///
+    /// JSONならブロックの配列(ルート・`blocks`・`files`・`project.blocks`)の各要素から、
+    /// ファイル名(`file`・`wav`・`path`・`name`)、ナレーター(`narrator`・`speaker`)、
+    /// テキスト(`text`)を取り出す。ナレーターが`{"key": "..."}`のオブジェクトでもよい。
+    /// それ以外は1行に`ファイル名`か`ファイル名<TAB>ナレーター`(カンマ区切りも可)として読む
+    pub fn parse(text: &str) -> serde_json::Result<Self> {
+        let text = text.trim_start_matches('\u{FEFF}').trim();
+        if !text.starts_with(['[', '{']) {
+            return Ok(Self::parse_list(text));
+        }
+
+        let root: Value = serde_json::from_str(text)?;
+        let blocks = root
+            .get("blocks")
+            .or_else(|| root.get("files"))
+            .or_else(|| root.pointer("/project/blocks"))
+            .unwrap_or(&root)
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let entries = blocks
+            .iter()
+            .filter_map(|block| {
+                if let Some(file) = block.as_str() {
+                    return Some(TimelineEntry::new(file));
+                }
+                let string = |keys: &[&str]| {
+                    keys.iter()
+                        .find_map(|key| block.get(*key).and_then(Value::as_str))
+                        .map(str::to_string)
+                };
+                let mut entry = TimelineEntry::new(&string(&["file", "wav", "path", "name"])?);
+                entry.narrator = string(&["narrator", "speaker"]).or_else(|| {
+                    block
+                        .pointer("/narrator/key")
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                });
+                entry.text = string(&["text"]);
+                Some(entry)
+            })
+            .filter(|entry| !entry.stem.is_empty())
+            .collect();
+        Ok(Self::from_entries(entries))
+    }
+
+    fn parse_list(text: &str) -> Self {
+        let entries = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| match line.split_once(['\t', ',']) {
+                Some((file, narrator)) => {
+                    let mut entry = TimelineEntry::new(file);
+                    entry.narrator = Some(narrator.trim().to_string()).filter(|n| !n.is_empty());
+                    entry
+                }
+                None => TimelineEntry::new(line),
+            })
+            .filter(|entry| !entry.stem.is_empty())
+            .collect();
+        Self::from_entries(entries)
+    }
+
+    /// 同じファイル名が何度もあれば最初のものを使う
+    fn from_entries(entries: Vec<TimelineEntry>) -> Self {
+        let mut positions = HashMap::with_capacity(entries.len());
+        for (i, entry) in entries.iter().enumerate() {
+            positions.entry(entry.stem.clone()).or_insert(i);
+        }
+        Self { entries, positions }
+    }
+
+    /// 並び順のブロック
+    pub fn entries(&self) -> &[TimelineEntry] {
+        &self.entries
+    }
+
+    /// ファイル名の連番(1から)と、そのブロック
+    pub fn find(&self, path: &Path) -> Option<(usize, &TimelineEntry)> {
+        let stem = path.file_stem()?.to_str()?;
+        let i = *self.positions.get(stem)?;
+        Some((i + 1, &self.entries[i]))
+    }
+
+    /// すべてのブロックにテキストがある(txtがなくてもよい)
+    pub fn has_text(&self) -> bool {
+        !self.entries.is_empty() && self.entries.iter().all(|entry| entry.text.is_some())
+    }
+}
+
+#[test]
+fn test_timeline_parse_json() {
+    let timeline = Timeline::parse(
+        r#"{"blocks": [
+            {"file": "out/intro_take2.wav", "narrator": {"key": "Japanese Female 1"}},
+            {"wav": "outro.wav", "speaker": "B", "text": "おわり"},
+            {"narrator": "ファイル名なし"}
+        ]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(2, timeline.entries().len());
+    let (seq, entry) = timeline.find(Path::new("voice/outro.txt")).unwrap();
+    assert_eq!(2, seq);
+    assert_eq!(Some("B"), entry.narrator.as_deref());
+    assert_eq!(
+        Some("Japanese Female 1"),
+        timeline.entries()[0].narrator.as_deref()
+    );
+    assert!(!timeline.has_text());
+}
+
+#[test]
+fn test_timeline_parse_list() {
+    let timeline = Timeline::parse("# 並び順\nb.wav\tナレーター\n\na\nb.wav\n").unwrap();
+
+    assert_eq!(
+        Some(1),
+        timeline.find(Path::new("b.wav")).map(|(seq, _)| seq)
+    );
+    assert_eq!(
+        Some(2),
+        timeline.find(Path::new("a.txt")).map(|(seq, _)| seq)
+    );
+    assert_eq!(None, timeline.find(Path::new("c.wav")));
+    assert_eq!(None, timeline.entries()[1].narrator);
+    // 重なったファイル名は最初のものを使う
+    assert_eq!(
+        Some("ナレーター"),
+        timeline
+            .find(Path::new("b.txt"))
+            .and_then(|(_, entry)| entry.narrator.as_deref())
+    );
+}
@@ -0,0 +1,943 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, Read},
+    ops::Add,
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    audio::{Clip, SilenceCheck},
+    diagnostics::Diagnostics,
+    extract_wav_and_txt_from,
+    screenplay::ScreenplayLine,
+    transform,
+    validate::{self, SpeechRate},
+    DirSource, DurationProvider, Error, FilePattern, InputSource, Result, SrtBlock,
+    WavDurationProvider,
+};
+
+/// wavとtxtの一覧から字幕ブロックを作成する
+pub fn make_srt_blocks(files: Vec<std::path::PathBuf>) -> Result<Vec<SrtBlock>> {
+    srt_blocks(files).collect()
+}
+
+/// wavとtxtの一覧から字幕ブロックを1つずつ作成するイテレータを返す
+///
+/// wavは取り出されたブロックの分だけ読み込まれるため、全体をメモリに載せずに処理できる。
+/// エラーが発生した場合はそれを返した後に終了する
+pub fn srt_blocks(files: Vec<std::path::PathBuf>) -> SrtBlocks {
+    srt_blocks_with(files, WavDurationProvider)
+}
+
+/// 音声の長さの取得方法を指定して字幕ブロックのイテレータを返す
+pub fn srt_blocks_with<P: DurationProvider + 'static>(
+    files: Vec<std::path::PathBuf>,
+    provider: P,
+) -> SrtBlocks {
+    SrtBlocks::new(files, Box::new(DirSource::new(".")), Box::new(provider))
+}
+
+/// 入力元から字幕ブロックのイテレータを返す
+pub fn srt_blocks_from_source<S, P>(source: S, provider: P) -> Result<SrtBlocks>
+where
+    S: InputSource + 'static,
+    P: DurationProvider + 'static,
+{
+    let files = extract_wav_and_txt_from(&source)?;
+    Ok(SrtBlocks::new(files, Box::new(source), Box::new(provider)))
+}
+
+/// txtが空(空白のみ)だったときの扱い
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EmptyTextPolicy {
+    /// 警告してブロックを作らない(音声の長さは後続の時刻に含める)
+    #[default]
+    Skip,
+    /// 警告して代わりのテキストを入れる
+    Placeholder(String),
+    /// エラーにする
+    Error,
+    /// 空のまま出力する
+    Keep,
+}
+
+/// txtに複数の行があるときの扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MultilinePolicy {
+    /// 複数行のまま1つのブロックにする(ブロックの区切りと間違われないよう空行は取り除く)
+    #[default]
+    Keep,
+    /// 1行につなげる(英単語の間だけ空白を入れる)
+    Join,
+    /// 行ごとのブロックに分け、音声の長さを読みの長さで割り振る
+    Split,
+}
+
+/// txtの文字コード
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TxtEncoding {
+    /// UTF-8(Voicepeak・VOICEVOXの書き出し)
+    #[default]
+    Utf8,
+    /// Shift-JIS(A.I.VOICE・CeVIO AIの書き出し)。BOMがあればUTF-8として読む
+    ShiftJis,
+}
+
+/// Shift-JISのテキストを読む(BOMがあればUTF-8として読む)
+pub fn decode_shift_jis(bytes: &[u8]) -> Option<String> {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return String::from_utf8(rest.to_vec()).ok();
+    }
+    encoding_rs::SHIFT_JIS
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(|text| text.into_owned())
+}
+
+/// 時刻を決めた音声の通知先
+pub(crate) type ClipSink = Box<dyn FnMut(Clip)>;
+
+/// 字幕ブロックを連番順に作成するイテレータ
+pub struct SrtBlocks {
+    files: Vec<std::path::PathBuf>,
+    source: Box<dyn InputSource>,
+    provider: Box<dyn DurationProvider>,
+    pattern: FilePattern,
+    diagnostics: Diagnostics,
+    empty_text: EmptyTextPolicy,
+    multiline: MultilinePolicy,
+    encoding: TxtEncoding,
+    normalize: bool,
+    speech_rate: Option<SpeechRate>,
+    silence: Option<SilenceCheck>,
+    on_clip: Option<ClipSink>,
+    gap: Duration,
+    crossfade: Duration,
+    /// 前の音声に次の音声を重ねる長さ(最初の音声の前は`None`)
+    overlap: Option<Duration>,
+    screenplay: Option<Vec<ScreenplayLine>>,
+    /// 1つの音声を行ごとに分けたブロックのうち、まだ返していないもの
+    pending: VecDeque<SrtBlock>,
+    /// これまでに時刻を決めた音声の数
+    clips: usize,
+    /// 連番ごとのファイルの位置(連番の順に並ぶ)
+    groups: BTreeMap<usize, Vec<usize>>,
+    seq: usize,
+    index: usize,
+    total_time: Duration,
+    finished: bool,
+}
+
+impl SrtBlocks {
+    pub(crate) fn new(
+        files: Vec<std::path::PathBuf>,
+        source: Box<dyn InputSource>,
+        provider: Box<dyn DurationProvider>,
+    ) -> Self {
+        let pattern = FilePattern::default();
+        let groups = group_by_sequence(&files, &pattern);
+        Self {
+            files,
+            source,
+            provider,
+            pattern,
+            diagnostics: Diagnostics::default(),
+            empty_text: EmptyTextPolicy::default(),
+            multiline: MultilinePolicy::default(),
+            encoding: TxtEncoding::default(),
+            normalize: false,
+            speech_rate: None,
+            silence: None,
+            on_clip: None,
+            gap: Duration::ZERO,
+            crossfade: Duration::ZERO,
+            overlap: None,
+            screenplay: None,
+            pending: VecDeque::new(),
+            clips: 0,
+            groups,
+            seq: 0,
+            index: 0,
+            total_time: Duration::from_secs_f64(0.),
+            finished: false,
+        }
+    }
+
+    /// ファイル名のパターンを差し替える
+    pub(crate) fn with_pattern(mut self, pattern: FilePattern) -> Self {
+        self.groups = group_by_sequence(&self.files, &pattern);
+        self.pattern = pattern;
+        self
+    }
+
+    /// 問題の扱いを差し替える
+    pub(crate) fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// 空のtxtの扱いを差し替える
+    pub(crate) fn with_empty_text(mut self, policy: EmptyTextPolicy) -> Self {
+        self.empty_text = policy;
+        self
+    }
+
+    /// 複数行のtxtの扱いを差し替える
+    pub(crate) fn with_multiline(mut self, policy: MultilinePolicy) -> Self {
+        self.multiline = policy;
+        self
+    }
+
+    /// txtの文字コードを差し替える
+    pub(crate) fn with_encoding(mut self, encoding: TxtEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// テキストをNFCに正規化するか
+    pub(crate) fn with_normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// 音声とテキストの長さの比較に使う読み上げ速度を設定する
+    pub(crate) fn with_speech_rate(mut self, rate: Option<SpeechRate>) -> Self {
+        self.speech_rate = rate;
+        self
+    }
+
+    /// ほとんど無音の音声を見つける基準を設定する
+    pub(crate) fn with_silence_check(mut self, check: Option<SilenceCheck>) -> Self {
+        self.silence = check;
+        self
+    }
+
+    /// 時刻を決めた音声ごとに呼ぶ関数を設定する
+    pub(crate) fn with_clip_sink(mut self, sink: Option<ClipSink>) -> Self {
+        self.on_clip = sink;
+        self
+    }
+
+    /// 音声の間に入れる無音の長さを設定する
+    pub(crate) fn with_gap(mut self, gap: Duration) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// つなぎ目をクロスフェードする長さを設定する
+    pub(crate) fn with_crossfade(mut self, crossfade: Duration) -> Self {
+        self.crossfade = crossfade;
+        self
+    }
+
+    /// txtの代わりに使う台本を設定する
+    pub(crate) fn with_screenplay(mut self, lines: Option<Vec<ScreenplayLine>>) -> Self {
+        self.screenplay = lines;
+        self
+    }
+
+    /// 次の連番のブロックを作成する
+    fn next_block(&mut self) -> Result<Option<SrtBlock>> {
+        if let Some(block) = self.pending.pop_front() {
+            return Ok(Some(block));
+        }
+        loop {
+            // 残っている中で最小の連番を次のブロックとする(桁数によらず数値の順に並べる)
+            let Some(i) = self.next_sequence() else {
+                return Ok(None);
+            };
+            self.seq = i + 1;
+
+            // 寛容モードでは作成できないブロックを飛ばす
+            match self.make_block(i) {
+                Ok(Some(block)) => return Ok(Some(block)),
+                Ok(None) => {}
+                Err(e) => self.diagnostics.recover(e)?,
+            }
+        }
+    }
+
+    /// まだブロックにしていない中で最小の連番
+    fn next_sequence(&self) -> Option<usize> {
+        self.groups.range(self.seq..).next().map(|(&seq, _)| seq)
+    }
+
+    /// txtを指定の文字コードで読み込む
+    fn read_txt(&self, path: &Path) -> io::Result<String> {
+        match self.encoding {
+            TxtEncoding::Utf8 => self.source.read_to_string(path),
+            TxtEncoding::ShiftJis => {
+                let mut bytes = Vec::new();
+                self.source.open(path)?.read_to_end(&mut bytes)?;
+                decode_shift_jis(&bytes).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "Shift-JISではありません")
+                })
+            }
+        }
+    }
+
+    /// 連番`i`のブロックを作成する(飛ばす場合は`None`)
+    fn make_block(&mut self, i: usize) -> Result<Option<SrtBlock>> {
+        // 対象ブロックのファイル抽出
+        let target_files: Vec<&std::path::PathBuf> = self.groups[&i]
+            .iter()
+            .map(|&file| &self.files[file])
+            .collect();
+
+        // wavとtxtが揃っていなければエラー(テキストをファイル名から取れる場合はtxtを省略できる)
+        let find = |ext: &str| {
+            target_files
+                .iter()
+                .find(|p| p.extension().unwrap() == ext)
+                .copied()
+        };
+        let wav_path = find("wav").ok_or(Error::UnpairedSequence { seq: i })?;
+
+        // wavから開始と終了時間取得
+        let wav_duration = self
+            .provider
+            .duration(self.source.as_ref(), Path::new(wav_path))
+            .map_err(|source| Error::WavDecode {
+                path: wav_path.to_path_buf(),
+                source,
+            })?;
+
+        // 2つ目以降の音声の前には無音を入れる(飛ばしたブロックの音声も数える)
+        // 無音よりクロスフェードが長い場合は、無音の代わりに前の音声に重ねる
+        match self.overlap {
+            Some(overlap) if !overlap.is_zero() => self.total_time -= overlap,
+            Some(_) => self.total_time = self.total_time.add(self.gap),
+            None => {}
+        }
+        let overlap = self
+            .crossfade
+            .saturating_sub(self.gap)
+            .min(wav_duration / 2);
+        self.overlap = Some(overlap);
+
+        // 重なる部分は次のブロックに譲り、字幕どうしは重ならないようにする
+        let is_last = self.next_sequence().is_none();
+        let start = self.total_time;
+        let end = match is_last {
+            true => start.add(wav_duration),
+            false => start.add(wav_duration) - overlap,
+        };
+
+        self.total_time = self.total_time.add(wav_duration);
+        if let Some(on_clip) = &mut self.on_clip {
+            on_clip(Clip::new(wav_path, start, wav_duration));
+        }
+        let clip = self.clips;
+        self.clips += 1;
+
+        // 台本がある場合は音声の順にセリフを割り当てる
+        let line = match &self.screenplay {
+            Some(lines) => Some(
+                lines
+                    .get(clip)
+                    .cloned()
+                    .ok_or(Error::UnpairedSequence { seq: i })?,
+            ),
+            None => None,
+        };
+
+        // 寛容モードで飛ばしても後続の時刻がずれないよう、時刻を進めてから確認する
+        let txt_path = find("txt");
+        let name_text = self.pattern.text(wav_path);
+        if txt_path.is_none() && name_text.is_none() && line.is_none() {
+            return Err(Error::UnpairedSequence { seq: i });
+        }
+
+        // txtからテキスト取得
+        let text = match (&line, txt_path) {
+            (Some(line), _) => line.text.clone(),
+            (None, Some(txt_path)) => self.read_txt(txt_path).map_err(|e| {
+                if e.kind() == io::ErrorKind::InvalidData {
+                    Error::TxtEncoding {
+                        path: txt_path.to_path_buf(),
+                    }
+                } else {
+                    Error::Read {
+                        path: txt_path.to_path_buf(),
+                        source: e,
+                    }
+                }
+            })?,
+            (None, None) => name_text.unwrap_or_default(),
+        };
+
+        // 濁点が分かれた文字は再生ソフトによって正しく表示されないため、正規化するか警告する
+        let text = if transform::is_nfc(&text) {
+            text
+        } else if self.normalize {
+            transform::normalize_nfc(&text)
+        } else {
+            self.diagnostics.warn(Error::NotNormalized { seq: i })?;
+            text
+        };
+
+        // 空のテキストは再生ソフトによっては読み込めないため、指定に従って扱う
+        let text = if text.trim().is_empty() {
+            match &self.empty_text {
+                EmptyTextPolicy::Keep => text,
+                EmptyTextPolicy::Error => return Err(Error::EmptyText { seq: i }),
+                EmptyTextPolicy::Skip => {
+                    self.diagnostics.warn(Error::EmptyText { seq: i })?;
+                    return Ok(None);
+                }
+                EmptyTextPolicy::Placeholder(placeholder) => {
+                    self.diagnostics.warn(Error::EmptyText { seq: i })?;
+                    placeholder.clone()
+                }
+            }
+        } else {
+            text
+        };
+
+        // 音声とテキストの長さが大きく違えば、組み合わせの間違いなどを疑って警告する
+        // `[音楽]`のような注記は読み上げないため確かめない
+        if let Some(rate) = &self.speech_rate {
+            if !transform::is_annotation(&text) && !rate.is_plausible(&text, wav_duration) {
+                self.diagnostics.warn(Error::ImplausibleDuration {
+                    seq: i,
+                    duration: wav_duration,
+                    expected: rate.expected_duration(&text),
+                })?;
+            }
+        }
+
+        // 書き出しに失敗した音声はそのまま無音として時間を取ってしまうため、警告する
+        // 16bit以外のwavなどサンプルを読めない音声は確かめない
+        if let Some(check) = &self.silence {
+            if let Ok((_, samples)) = crate::duration::read_wav(self.source.as_ref(), wav_path) {
+                if let Some((ratio, peak)) = check.check(&samples) {
+                    self.diagnostics.warn(Error::SilentClip {
+                        seq: i,
+                        path: wav_path.to_path_buf(),
+                        ratio,
+                        peak,
+                    })?;
+                }
+            }
+        }
+
+        let speaker = match line {
+            Some(line) => line.speaker,
+            None => self.pattern.speaker(wav_path),
+        };
+        let emotion = self.pattern.emotion(wav_path);
+        for (text, start, end) in split_multiline(&text, self.multiline, start, end) {
+            self.index += 1;
+            let mut block = SrtBlock::new(self.index, start, end, text);
+            block.speaker = speaker.clone();
+            block.emotion = emotion.clone();
+            block.source = Some(wav_path.to_path_buf());
+            self.pending.push_back(block);
+        }
+
+        Ok(self.pending.pop_front())
+    }
+}
+
+/// 複数行のテキストを扱いに従ってまとめるか分け、分けた行には読みの長さに応じて時刻を割り振る
+///
+/// 1行だけのテキストはそのまま返す
+fn split_multiline(
+    text: &str,
+    policy: MultilinePolicy,
+    start: Duration,
+    end: Duration,
+) -> Vec<(String, Duration, Duration)> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    if lines.len() < 2 {
+        return vec![(text.to_string(), start, end)];
+    }
+    match policy {
+        MultilinePolicy::Keep => vec![(lines.join("\n"), start, end)],
+        MultilinePolicy::Join => {
+            let joined =
+                lines
+                    .iter()
+                    .map(|line| line.trim())
+                    .fold(String::new(), |mut out, line| {
+                        let ascii = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+                        if ascii(out.chars().last()) && ascii(line.chars().next()) {
+                            out.push(' ');
+                        }
+                        out.push_str(line);
+                        out
+                    });
+            vec![(joined, start, end)]
+        }
+        MultilinePolicy::Split => {
+            let lengths: Vec<usize> = lines
+                .iter()
+                .map(|line| validate::spoken_length(line).max(1))
+                .collect();
+            let total: usize = lengths.iter().sum();
+            let duration = end.saturating_sub(start);
+            let mut spoken = 0;
+            let mut parts = Vec::new();
+            for (line, length) in lines.iter().zip(&lengths) {
+                let part_start = start + duration.mul_f64(spoken as f64 / total as f64);
+                spoken += length;
+                // 最後の行は丸めの誤差が出ないよう音声の終わりに合わせる
+                let part_end = match spoken == total {
+                    true => end,
+                    false => start + duration.mul_f64(spoken as f64 / total as f64),
+                };
+                parts.push((line.trim().to_string(), part_start, part_end));
+            }
+            parts
+        }
+    }
+}
+
+/// ファイルを連番ごとにまとめる(連番のないファイルは除く)
+fn group_by_sequence(
+    files: &[std::path::PathBuf],
+    pattern: &FilePattern,
+) -> BTreeMap<usize, Vec<usize>> {
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (file, path) in files.iter().enumerate() {
+        if let Some(seq) = pattern.sequence(path) {
+            groups.entry(seq).or_default().push(file);
+        }
+    }
+    groups
+}
+
+impl Iterator for SrtBlocks {
+    type Item = Result<SrtBlock>;
+
+    fn next(&mut self) -> Option<Result<SrtBlock>> {
+        if self.finished {
+            return None;
+        }
+
+        let block = self.next_block().transpose();
+        if !matches!(block, Some(Ok(_))) {
+            self.finished = true;
+        }
+        block
+    }
+}
+
+#[test]
+fn test_make_srt_blocks_ok() {
+    use crate::format_srt_time;
+
+    let path = Path::new("../voice");
+    let files = crate::extract_wav_and_txt(path).unwrap();
+    let srt_blocks = make_srt_blocks(files).unwrap();
+
+    let correct = [
+        (1, "00:00:00,000", "00:00:07,288", "時は第三次中東戦争と第四次中東戦争の間の1973年2月初旬"),
+        (2, "00:00:07,288", "00:00:13,722", "エジプトを盟主とする中東アラブ諸国とイスラエルは、とてもピリピリした状態にありました"),
+        (3, "00:00:13,722", "00:00:22,488", "砂塵舞うベンガジ空港を飛び立ち、リビアン・アラブ航空114便は地中海を渡ってエジプトの首都カイロへ向かいます"),
+        (4, "00:00:22,488", "00:00:31,547", "コックピットにはフランス人機長、その右隣にフランス人航空機関士、後ろにはリビア人副操縦士が乗っていました"),
+    ];
+
+    assert_eq!(correct.len(), srt_blocks.len());
+    for ((index, start, end, text), block) in correct.iter().zip(&srt_blocks) {
+        assert_eq!(*index, block.index);
+        assert_eq!(*start, format_srt_time(block.start));
+        assert_eq!(*end, format_srt_time(block.end));
+        assert_eq!(*text, block.text);
+    }
+}
+
+#[test]
+fn test_srt_blocks_lazy() {
+    let path = Path::new("../voice");
+    let files = crate::extract_wav_and_txt(path).unwrap();
+    let mut blocks = srt_blocks(files);
+
+    assert_eq!(1, blocks.next().unwrap().unwrap().index);
+    assert_eq!(2, blocks.next().unwrap().unwrap().index);
+    assert_eq!(2, blocks.count());
+}
+
+#[test]
+fn test_srt_blocks_with_custom_provider() {
+    let path = Path::new("../voice");
+    let files = crate::extract_wav_and_txt(path).unwrap();
+    let blocks: Vec<SrtBlock> = srt_blocks_with(files, |_: &dyn InputSource, _: &Path| {
+        Ok(Duration::from_secs(2))
+    })
+    .collect::<Result<_>>()
+    .unwrap();
+
+    assert_eq!(Duration::from_secs(6), blocks[3].start);
+    assert_eq!(Duration::from_secs(8), blocks[3].end);
+}
+
+#[test]
+fn test_srt_blocks_from_memory_source() {
+    let mut source = crate::MemorySource::new();
+    for name in ["000-voice.wav", "000-voice.txt"] {
+        let data = std::fs::read(Path::new("../voice").join(name)).unwrap();
+        source.insert(name, data);
+    }
+    let blocks: Vec<SrtBlock> = srt_blocks_from_source(source, WavDurationProvider)
+        .unwrap()
+        .collect::<Result<_>>()
+        .unwrap();
+
+    assert_eq!(1, blocks.len());
+    assert_eq!(7288, blocks[0].end.as_millis());
+    assert_eq!(
+        "時は第三次中東戦争と第四次中東戦争の間の1973年2月初旬",
+        blocks[0].text
+    );
+}
+
+#[test]
+fn test_srt_blocks_unpaired_sequence() {
+    let mut source = crate::MemorySource::new();
+    source.insert(
+        "000-voice.wav",
+        std::fs::read("../voice/000-voice.wav").unwrap(),
+    );
+    source.insert("000-voice.txt", b"text".to_vec());
+    source.insert("001-voice.txt", b"text".to_vec());
+    source.insert(
+        "002-voice.wav",
+        std::fs::read("../voice/000-voice.wav").unwrap(),
+    );
+    // 一覧の検査を通さずにイテレータを作る
+    let files = source.files().unwrap();
+    let mut blocks = SrtBlocks::new(files, Box::new(source), Box::new(WavDurationProvider));
+
+    assert!(blocks.next().unwrap().is_ok());
+    assert!(matches!(
+        blocks.next(),
+        Some(Err(Error::UnpairedSequence { seq: 1 }))
+    ));
+    assert!(blocks.next().is_none());
+}
+
+#[test]
+fn test_srt_blocks_variable_padding() {
+    let mut source = crate::MemorySource::new();
+    for seq in 0..1001 {
+        source.insert(format!("{:04}-voice.wav", seq), Vec::new());
+        source.insert(
+            format!("{:04}-voice.txt", seq),
+            seq.to_string().into_bytes(),
+        );
+    }
+    let provider = |_: &dyn InputSource, _: &Path| Ok(Duration::from_secs(1));
+    let blocks: Vec<SrtBlock> = srt_blocks_from_source(source, provider)
+        .unwrap()
+        .collect::<Result<_>>()
+        .unwrap();
+
+    assert_eq!(1001, blocks.len());
+    assert_eq!("1000", blocks[1000].text);
+    assert_eq!(Duration::from_secs(1000), blocks[1000].start);
+}
+
+#[test]
+fn test_srt_blocks_with_pattern() {
+    let mut source = crate::MemorySource::new();
+    source.insert("ナレーター_0_こんにちは.wav", Vec::new());
+    source.insert("ずんだもん_1_さようなら.wav", Vec::new());
+    source.insert("ずんだもん_1_さようなら.txt", b"txt".to_vec());
+    let pattern = FilePattern::new(r"^(?P<speaker>[^_]+)_(?P<seq>\d+)_(?P<text>.*)$").unwrap();
+    let files = crate::extract_wav_and_txt_with(&source, &pattern).unwrap();
+    let provider = |_: &dyn InputSource, _: &Path| Ok(Duration::from_secs(1));
+    let blocks: Vec<SrtBlock> = SrtBlocks::new(files, Box::new(source), Box::new(provider))
+        .with_pattern(pattern)
+        .collect::<Result<_>>()
+        .unwrap();
+
+    assert_eq!("こんにちは", blocks[0].text);
+    assert_eq!(Some("ナレーター"), blocks[0].speaker.as_deref());
+    assert_eq!("txt", blocks[1].text);
+    assert_eq!(Some("ずんだもん"), blocks[1].speaker.as_deref());
+}
+
+#[test]
+fn test_srt_blocks_natural_order() {
+    let mut source = crate::MemorySource::new();
+    for name in ["10_end", "1_intro", "2_body"] {
+        source.insert(format!("{}.wav", name), Vec::new());
+        source.insert(format!("{}.txt", name), name.as_bytes().to_vec());
+    }
+    let provider = |_: &dyn InputSource, _: &Path| Ok(Duration::from_secs(1));
+    let blocks: Vec<SrtBlock> = srt_blocks_from_source(source, provider)
+        .unwrap()
+        .collect::<Result<_>>()
+        .unwrap();
+
+    let texts: Vec<&str> = blocks.iter().map(|b| b.text.as_str()).collect();
+    assert_eq!(vec!["1_intro", "2_body", "10_end"], texts);
+    assert_eq!(1, blocks[0].index);
+}
+
+#[test]
+fn test_srt_blocks_lenient_skips_broken_wav() {
+    let mut source = crate::MemorySource::new();
+    for seq in 0..3 {
+        source.insert(format!("{:03}-voice.wav", seq), Vec::new());
+        source.insert(
+            format!("{:03}-voice.txt", seq),
+            seq.to_string().into_bytes(),
+        );
+    }
+    let provider = |_: &dyn InputSource, path: &Path| {
+        if path.to_string_lossy().starts_with("001") {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "broken"))
+        } else {
+            Ok(Duration::from_secs(1))
+        }
+    };
+    let files = source.files().unwrap();
+    let warnings = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let sink = {
+        let warnings = std::rc::Rc::clone(&warnings);
+        Box::new(move |e: Error| warnings.borrow_mut().push(e))
+    };
+    let blocks: Vec<SrtBlock> = SrtBlocks::new(files, Box::new(source), Box::new(provider))
+        .with_diagnostics(Diagnostics::new(crate::Strictness::Lenient, sink))
+        .collect::<Result<_>>()
+        .unwrap();
+
+    assert_eq!(2, blocks.len());
+    assert_eq!((2, "2"), (blocks[1].index, blocks[1].text.as_str()));
+    assert_eq!(Duration::from_secs(1), blocks[1].start);
+    assert!(matches!(warnings.borrow()[0], Error::WavDecode { .. }));
+}
+
+#[test]
+fn test_srt_blocks_empty_text() {
+    let mut source = crate::MemorySource::new();
+    for (seq, text) in ["a", " \n", "c"].iter().enumerate() {
+        source.insert(format!("{:03}-voice.wav", seq), Vec::new());
+        source.insert(format!("{:03}-voice.txt", seq), text.as_bytes().to_vec());
+    }
+    let blocks = |policy: EmptyTextPolicy| -> Result<Vec<SrtBlock>> {
+        let provider = |_: &dyn InputSource, _: &Path| Ok(Duration::from_secs(1));
+        let files = source.files().unwrap();
+        SrtBlocks::new(files, Box::new(source.clone()), Box::new(provider))
+            .with_empty_text(policy)
+            .collect()
+    };
+
+    let skipped = blocks(EmptyTextPolicy::Skip).unwrap();
+    assert_eq!(2, skipped.len());
+    assert_eq!(
+        (2, Duration::from_secs(2)),
+        (skipped[1].index, skipped[1].start)
+    );
+
+    let placeholder = blocks(EmptyTextPolicy::Placeholder("…".to_string())).unwrap();
+    assert_eq!("…", placeholder[1].text);
+
+    assert!(matches!(
+        blocks(EmptyTextPolicy::Error),
+        Err(Error::EmptyText { seq: 1 })
+    ));
+    assert_eq!(" \n", blocks(EmptyTextPolicy::Keep).unwrap()[1].text);
+}
+
+#[test]
+fn test_srt_blocks_shift_jis() {
+    let mut source = crate::MemorySource::new();
+    let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("こんにちは");
+    source.insert("000-voice.wav", Vec::new());
+    source.insert("000-voice.txt", sjis.to_vec());
+    source.insert("001-voice.wav", Vec::new());
+    source.insert("001-voice.txt", "\u{feff}はい".as_bytes().to_vec());
+    let blocks = |encoding: TxtEncoding| -> Result<Vec<SrtBlock>> {
+        let provider = |_: &dyn InputSource, _: &Path| Ok(Duration::from_secs(1));
+        let files = source.files().unwrap();
+        SrtBlocks::new(files, Box::new(source.clone()), Box::new(provider))
+            .with_encoding(encoding)
+            .collect()
+    };
+
+    let blocks_sjis = blocks(TxtEncoding::ShiftJis).unwrap();
+    assert_eq!("こんにちは", blocks_sjis[0].text);
+    assert_eq!("はい", blocks_sjis[1].text);
+    assert!(matches!(
+        blocks(TxtEncoding::Utf8),
+        Err(Error::TxtEncoding { .. })
+    ));
+}
+
+#[test]
+fn test_srt_blocks_normalize() {
+    let mut source = crate::MemorySource::new();
+    source.insert("000-voice.wav", Vec::new());
+    source.insert("000-voice.txt", "か\u{3099}".as_bytes().to_vec());
+    let provider = |_: &dyn InputSource, _: &Path| Ok(Duration::from_secs(1));
+    let files = source.files().unwrap();
+    let blocks: Vec<SrtBlock> = SrtBlocks::new(files, Box::new(source), Box::new(provider))
+        .with_normalize(true)
+        .collect::<Result<_>>()
+        .unwrap();
+
+    assert_eq!("が", blocks[0].text);
+}
+
+#[test]
+fn test_srt_blocks_implausible_duration() {
+    let mut source = crate::MemorySource::new();
+    source.insert("000-voice.wav", Vec::new());
+    source.insert(
+        "000-voice.txt",
+        "とても長いテキストのはずが一秒しかない".as_bytes().to_vec(),
+    );
+    let provider = |_: &dyn InputSource, _: &Path| Ok(Duration::from_millis(500));
+    let files = source.files().unwrap();
+    let blocks = SrtBlocks::new(files, Box::new(source), Box::new(provider))
+        .with_speech_rate(Some(SpeechRate::default()))
+        .with_diagnostics(Diagnostics::new(
+            crate::Strictness::Strict,
+            Box::new(|_| {}),
+        ))
+        .collect::<Result<Vec<_>>>();
+
+    assert!(matches!(
+        blocks,
+        Err(Error::ImplausibleDuration { seq: 0, .. })
+    ));
+}
+
+#[test]
+fn test_srt_blocks_annotation_duration() {
+    let mut source = crate::MemorySource::new();
+    source.insert("000-voice.wav", Vec::new());
+    source.insert(
+        "000-voice.txt",
+        "[静かなピアノの音楽が流れる]".as_bytes().to_vec(),
+    );
+    let provider = |_: &dyn InputSource, _: &Path| Ok(Duration::from_secs(30));
+    let files = source.files().unwrap();
+    let blocks = SrtBlocks::new(files, Box::new(source), Box::new(provider))
+        .with_speech_rate(Some(SpeechRate::default()))
+        .with_diagnostics(Diagnostics::new(
+            crate::Strictness::Strict,
+            Box::new(|_| {}),
+        ))
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+
+    assert_eq!("[静かなピアノの音楽が流れる]", blocks[0].text);
+}
+
+#[test]
+fn test_srt_blocks_silent_clip() {
+    use crate::audio::{write_wav_header, WavFormat};
+
+    let format = WavFormat {
+        sampling_rate: 44100,
+        channels: 1,
+    };
+    let mut wav = Vec::new();
+    write_wav_header(&mut wav, format, 44100).unwrap();
+    wav.extend(vec![0; 44100 * 2]);
+    let mut source = crate::MemorySource::new();
+    source.insert("000-voice.wav", wav);
+    source.insert("000-voice.txt", "テキスト".as_bytes().to_vec());
+    let files = source.files().unwrap();
+    let blocks = SrtBlocks::new(files, Box::new(source), Box::new(WavDurationProvider))
+        .with_silence_check(Some(SilenceCheck::default()))
+        .with_diagnostics(Diagnostics::new(
+            crate::Strictness::Strict,
+            Box::new(|_| {}),
+        ))
+        .collect::<Result<Vec<_>>>();
+
+    assert!(matches!(
+        blocks,
+        Err(Error::SilentClip {
+            seq: 0,
+            peak: 0,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_group_by_sequence() {
+    let files: Vec<std::path::PathBuf> = ["2-a.wav", "0-a.wav", "memo.txt", "2-a.txt", "0-a.txt"]
+        .iter()
+        .map(std::path::PathBuf::from)
+        .collect();
+    let groups = group_by_sequence(&files, &FilePattern::default());
+
+    assert_eq!(
+        vec![(&0, &vec![1, 4]), (&2, &vec![0, 3])],
+        groups.iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_split_multiline() {
+    let (start, end) = (Duration::from_secs(1), Duration::from_secs(4));
+    let text = "一行目です\n\nPart two\nabc\n";
+
+    assert_eq!(
+        vec![("一行目です\nPart two\nabc".to_string(), start, end)],
+        split_multiline(text, MultilinePolicy::Keep, start, end)
+    );
+    assert_eq!(
+        "一行目ですPart two abc",
+        split_multiline(text, MultilinePolicy::Join, start, end)[0].0
+    );
+    assert_eq!(
+        vec![("1行".to_string(), start, end)],
+        split_multiline("1行", MultilinePolicy::Split, start, end)
+    );
+
+    let parts = split_multiline("ああああ\nいい", MultilinePolicy::Split, start, end);
+    assert_eq!(
+        vec![
+            ("ああああ".to_string(), start, Duration::from_secs(3)),
+            ("いい".to_string(), Duration::from_secs(3), end),
+        ],
+        parts
+    );
+}
+
+#[test]
+fn test_srt_blocks_split_multiline() {
+    use crate::audio::{write_wav_header, WavFormat};
+
+    // 1秒の無音
+    let format = WavFormat {
+        sampling_rate: 44100,
+        channels: 1,
+    };
+    let mut wav = Vec::new();
+    write_wav_header(&mut wav, format, 44100).unwrap();
+    wav.extend(vec![0; 44100 * 2]);
+    let mut source = crate::MemorySource::new();
+    source.insert("000-voice.wav", wav.clone());
+    source.insert("000-voice.txt", "一つ目\n二つ目".as_bytes().to_vec());
+    source.insert("001-voice.wav", wav);
+    source.insert("001-voice.txt", "三つ目".as_bytes().to_vec());
+    let files = source.files().unwrap();
+    let blocks = SrtBlocks::new(files, Box::new(source), Box::new(WavDurationProvider))
+        .with_multiline(MultilinePolicy::Split)
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+
+    assert_eq!(3, blocks.len());
+    assert_eq!(
+        vec![1, 2, 3],
+        blocks.iter().map(|b| b.index).collect::<Vec<_>>()
+    );
+    assert_eq!(blocks[0].end, blocks[1].start);
+    assert_eq!(Duration::from_secs(1), blocks[2].start);
+}
@@ -0,0 +1,315 @@
+//! 字幕ブロックの変換
+
+use std::time::Duration;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::SrtBlock;
+
+/// 読み込みと書き出しの間で字幕ブロックごとに呼ばれる変換
+///
+/// テキストや時刻を書き換えたブロックを返す。`None`を返すとそのブロックは取り除かれる
+pub trait Transform {
+    /// ブロックを変換する
+    fn apply(&mut self, block: SrtBlock) -> Option<SrtBlock>;
+}
+
+impl<F: FnMut(SrtBlock) -> Option<SrtBlock>> Transform for F {
+    fn apply(&mut self, block: SrtBlock) -> Option<SrtBlock> {
+        self(block)
+    }
+}
+
+/// 登録された変換を順に適用する
+///
+/// 取り除かれたブロックがあれば、後続のブロックの番号を詰める
+#[derive(Default)]
+pub struct Hooks {
+    transforms: Vec<Box<dyn Transform>>,
+    dropped: usize,
+}
+
+impl Hooks {
+    /// 変換が登録されていない状態で作成する
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 変換を登録する
+    pub fn register<T: Transform + 'static>(&mut self, transform: T) {
+        self.transforms.push(Box::new(transform));
+    }
+
+    /// 登録された変換がない
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+}
+
+impl Transform for Hooks {
+    fn apply(&mut self, block: SrtBlock) -> Option<SrtBlock> {
+        let mut block = block;
+        for transform in self.transforms.iter_mut() {
+            match transform.apply(block) {
+                Some(b) => block = b,
+                None => {
+                    self.dropped += 1;
+                    return None;
+                }
+            }
+        }
+        block.index = block.index.saturating_sub(self.dropped);
+        Some(block)
+    }
+}
+
+/// 全体が`[音楽]`や`［拍手］`のような音の注記のテキストか
+pub fn is_annotation(text: &str) -> bool {
+    let text = text.trim();
+    let inner = text
+        .strip_prefix('[')
+        .and_then(|text| text.strip_suffix(']'))
+        .or_else(|| {
+            text.strip_prefix('［')
+                .and_then(|text| text.strip_suffix('］'))
+        });
+    inner.is_some_and(|inner| !inner.is_empty() && !inner.contains([']', '］']))
+}
+
+/// 聴覚障害者向け字幕(SDH)のため、話者が替わるたびにテキストの先頭に`（話者）`を付ける
+///
+/// 音の注記だけのブロックには付けず、その次のセリフには改めて付ける
+#[derive(Debug, Clone, Default)]
+pub struct SpeakerLabels {
+    last: Option<String>,
+}
+
+impl SpeakerLabels {
+    /// まだ話者がいない状態で作成する
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Transform for SpeakerLabels {
+    fn apply(&mut self, mut block: SrtBlock) -> Option<SrtBlock> {
+        if is_annotation(&block.text) {
+            self.last = None;
+            return Some(block);
+        }
+        if let Some(speaker) = &block.speaker {
+            if self.last.as_ref() != Some(speaker) {
+                block.text = format!("（{}）{}", speaker, block.text);
+            }
+        }
+        self.last = block.speaker.clone();
+        Some(block)
+    }
+}
+
+/// ブロックの時刻をずらす
+pub fn offset(block: &mut SrtBlock, offset: Duration) {
+    block.start += offset;
+    block.end += offset;
+}
+
+/// 1行が`width`文字を超えないようにテキストを折り返す
+///
+/// 句読点や空白の直後で折り返せる場合はそこで、できなければ`width`文字で区切る
+pub fn wrap_text(text: &str, width: usize) -> String {
+    if width == 0 {
+        return text.to_string();
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let mut rest: Vec<char> = line.chars().collect();
+        while rest.len() > width {
+            // 折り返し位置を探す
+            let split = rest[..width]
+                .iter()
+                .rposition(|c| matches!(c, '、' | '。' | '，' | '．' | '！' | '？' | ' '))
+                .map(|i| i + 1)
+                .unwrap_or(width);
+            let head: String = rest.drain(..split).collect();
+            lines.push(head.trim_end().to_string());
+        }
+        lines.push(rest.into_iter().collect());
+    }
+
+    lines.join("\n")
+}
+
+/// 行(縦書きでは列)の先頭に置かない文字
+const NO_LINE_START: &str =
+    "、。，．」』）〕】！？!?ー…‥ぁぃぅぇぉっゃゅょゎァィゥェォッャュョヮヵヶ";
+
+/// 行(縦書きでは列)の末尾に置かない文字
+const NO_LINE_END: &str = "「『（〔【";
+
+/// 縦書きで表示するテキストにする
+///
+/// 半角の英数字と記号は縦に並ぶよう全角にし、1列`column`文字で折り返す。
+/// 句読点や閉じ括弧は列の先頭に来ないよう前の列にぶら下げ、開き括弧は次の列に送る。
+/// ふりがななどのASSの`{\fs24}`のような指定はそのまま残し、文字数にも数えない
+pub fn vertical_text(text: &str, column: usize) -> String {
+    let full_width = |c: char| match c {
+        '!'..='~' => char::from_u32(c as u32 - '!' as u32 + '！' as u32).unwrap_or(c),
+        ' ' => '　',
+        c => c,
+    };
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        // 表示する文字ごとに、その前に付いている指定を持つ
+        let mut rest: Vec<(String, char)> = Vec::new();
+        let mut tags = String::new();
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if c == '{' && chars.as_str().contains('}') {
+                let (tag, after) = chars.as_str().split_once('}').unwrap();
+                tags.push_str(&format!("{{{}}}", tag));
+                chars = after.chars();
+            } else {
+                rest.push((std::mem::take(&mut tags), full_width(c)));
+            }
+        }
+        let join = |chars: Vec<(String, char)>| {
+            chars.into_iter().fold(String::new(), |mut out, (tags, c)| {
+                out.push_str(&tags);
+                out.push(c);
+                out
+            })
+        };
+        while column > 0 && rest.len() > column {
+            let mut split = column;
+            if NO_LINE_START.contains(rest[split].1) {
+                split += 1;
+            } else if NO_LINE_END.contains(rest[split - 1].1) && split > 1 {
+                split -= 1;
+            }
+            let head = rest.drain(..split.min(rest.len())).collect();
+            lines.push(join(head));
+        }
+        lines.push(join(rest) + &tags);
+    }
+    lines.retain(|line| !line.is_empty());
+    lines.join("\n")
+}
+
+/// テキストがNFCに正規化されているか
+pub fn is_nfc(text: &str) -> bool {
+    unicode_normalization::is_nfc(text)
+}
+
+/// テキストをNFCに正規化する(「か」+濁点を「が」にまとめるなど)
+pub fn normalize_nfc(text: &str) -> String {
+    text.nfc().collect()
+}
+
+#[test]
+fn test_offset() {
+    let mut block = SrtBlock {
+        index: 1,
+        start: Duration::from_millis(100),
+        end: Duration::from_millis(200),
+        text: String::new(),
+        speaker: None,
+        translation: None,
+        emotion: None,
+        source: None,
+    };
+    offset(&mut block, Duration::from_millis(1000));
+    assert_eq!(Duration::from_millis(1100), block.start);
+    assert_eq!(Duration::from_millis(1200), block.end);
+}
+
+#[test]
+fn test_wrap_text() {
+    assert_eq!("あいう\nえお", wrap_text("あいうえお", 3));
+    assert_eq!("あい、\nうえお", wrap_text("あい、うえお", 4));
+    assert_eq!("あいうえお", wrap_text("あいうえお", 0));
+}
+
+#[test]
+fn test_vertical_text() {
+    assert_eq!("１９７３年", vertical_text("1973年", 0));
+    // 句読点は前の列にぶら下げ、開き括弧は次の列に送る
+    assert_eq!("あいう。\nえお", vertical_text("あいう。えお", 3));
+    assert_eq!("あい\n「うえ\nお」", vertical_text("あい「うえお」", 3));
+    // ASSの指定は全角にせず、文字数にも数えない
+    assert_eq!(
+        "贔屓{\\fs24}（ひい\nき）{\\fs}に",
+        vertical_text("贔屓{\\fs24}(ひいき){\\fs}に", 5)
+    );
+}
+
+#[test]
+fn test_speaker_labels() {
+    let mut labels = SpeakerLabels::new();
+    let blocks: Vec<String> = [
+        (Some("めたん"), "こんにちは"),
+        (Some("めたん"), "続きです"),
+        (None, "[拍手]"),
+        (Some("めたん"), "ありがとう"),
+        (Some("ずんだもん"), "なのだ"),
+    ]
+    .iter()
+    .filter_map(|(speaker, text)| {
+        let mut block = SrtBlock::new(1, Duration::ZERO, Duration::ZERO, *text);
+        block.speaker = speaker.map(str::to_string);
+        labels.apply(block)
+    })
+    .map(|block| block.text)
+    .collect();
+
+    assert_eq!(
+        vec![
+            "（めたん）こんにちは",
+            "続きです",
+            "[拍手]",
+            "（めたん）ありがとう",
+            "（ずんだもん）なのだ"
+        ],
+        blocks
+    );
+    assert!(is_annotation("［音楽］"));
+    assert!(!is_annotation("[笑] そうですね"));
+}
+
+#[test]
+fn test_hooks() {
+    let mut hooks = Hooks::new();
+    hooks.register(|mut block: SrtBlock| {
+        block.text = block.text.to_uppercase();
+        Some(block)
+    });
+    hooks.register(|block: SrtBlock| (block.text != "DROP").then_some(block));
+
+    let blocks: Vec<SrtBlock> = ["a", "drop", "c"]
+        .iter()
+        .enumerate()
+        .map(|(i, text)| SrtBlock {
+            index: i + 1,
+            start: Duration::ZERO,
+            end: Duration::ZERO,
+            text: text.to_string(),
+            speaker: None,
+            translation: None,
+            emotion: None,
+            source: None,
+        })
+        .filter_map(|block| hooks.apply(block))
+        .collect();
+
+    assert_eq!(2, blocks.len());
+    assert_eq!((1, "A"), (blocks[0].index, blocks[0].text.as_str()));
+    assert_eq!((2, "C"), (blocks[1].index, blocks[1].text.as_str()));
+}
+
+#[test]
+fn test_normalize_nfc() {
+    let decomposed = "か\u{3099}";
+    assert!(!is_nfc(decomposed));
+    assert_eq!("が", normalize_nfc(decomposed));
+    assert!(is_nfc("が"));
+}
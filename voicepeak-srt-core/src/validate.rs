@@ -0,0 +1,558 @@
+//! 字幕の検査
+
+use std::{fmt, time::Duration};
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{format_srt_time, SrtBlock};
+
+/// 検査の設定
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ValidateOptions {
+    /// 1秒あたりの最大文字数(`None`なら検査しない)
+    pub max_cps: Option<f64>,
+    /// 番号が1から連番になっているかを検査する
+    pub check_numbering: bool,
+}
+
+impl ValidateOptions {
+    /// 既定の設定(番号を検査し、文字数は検査しない)
+    pub fn new() -> Self {
+        Self {
+            max_cps: None,
+            check_numbering: true,
+        }
+    }
+
+    /// 1秒あたりの最大文字数を設定する
+    pub fn max_cps(mut self, max_cps: Option<f64>) -> Self {
+        self.max_cps = max_cps;
+        self
+    }
+
+    /// 番号を検査するかを設定する
+    pub fn check_numbering(mut self, check: bool) -> Self {
+        self.check_numbering = check;
+        self
+    }
+}
+
+/// 検出された問題
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    /// 問題のあるブロックの番号
+    pub index: usize,
+    /// 問題の種類
+    pub kind: IssueKind,
+}
+
+/// 問題の種類
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum IssueKind {
+    /// 前のブロックと表示時間が重なっている
+    Overlap {
+        /// 前のブロックの終了時刻
+        previous_end: Duration,
+    },
+    /// 前のブロックより前に始まっている
+    NonMonotonic {
+        /// 前のブロックの開始時刻
+        previous_start: Duration,
+    },
+    /// 終了時刻が開始時刻以前
+    EndBeforeStart,
+    /// テキストが空
+    EmptyText,
+    /// 番号が連番になっていない
+    NumberingGap {
+        /// 期待される番号
+        expected: usize,
+    },
+    /// 1秒あたりの文字数が多すぎる
+    CpsExceeded {
+        /// 1秒あたりの文字数
+        cps: f64,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ブロック{}: ", self.index)?;
+        match &self.kind {
+            IssueKind::Overlap { previous_end } => write!(
+                f,
+                "前のブロック(終了 {})と重なっています",
+                format_srt_time(*previous_end)
+            ),
+            IssueKind::NonMonotonic { previous_start } => write!(
+                f,
+                "前のブロック(開始 {})より前に始まっています",
+                format_srt_time(*previous_start)
+            ),
+            IssueKind::EndBeforeStart => write!(f, "終了時刻が開始時刻以前です"),
+            IssueKind::EmptyText => write!(f, "テキストが空です"),
+            IssueKind::NumberingGap { expected } => {
+                write!(f, "番号が連続していません(期待値: {})", expected)
+            }
+            IssueKind::CpsExceeded { cps } => write!(f, "1秒あたり{:.1}文字は速すぎます", cps),
+        }
+    }
+}
+
+/// 数字の読み
+const DIGITS: [&str; 10] = [
+    "ぜろ",
+    "いち",
+    "に",
+    "さん",
+    "よん",
+    "ご",
+    "ろく",
+    "なな",
+    "はち",
+    "きゅう",
+];
+
+/// 数字の後ろに付く単位の読み(長いものから探す)
+const UNITS: [(&str, &str); 12] = [
+    ("km", "きろめーとる"),
+    ("cm", "せんちめーとる"),
+    ("mm", "みりめーとる"),
+    ("kg", "きろぐらむ"),
+    ("mg", "みりぐらむ"),
+    ("ml", "みりりっとる"),
+    ("m", "めーとる"),
+    ("g", "ぐらむ"),
+    ("%", "ぱーせんと"),
+    ("℃", "ど"),
+    ("°", "ど"),
+    ("円", "えん"),
+];
+
+/// 記号の読み
+const SYMBOLS: [(char, &str); 9] = [
+    ('%', "ぱーせんと"),
+    ('&', "あんど"),
+    ('+', "ぷらす"),
+    ('=', "いこーる"),
+    ('@', "あっと"),
+    ('$', "どる"),
+    ('~', "から"),
+    ('〜', "から"),
+    ('×', "かける"),
+];
+
+/// 略語として1文字ずつ読む英字の読み
+const LETTERS: [&str; 26] = [
+    "えー",
+    "びー",
+    "しー",
+    "でぃー",
+    "いー",
+    "えふ",
+    "じー",
+    "えいち",
+    "あい",
+    "じぇー",
+    "けー",
+    "える",
+    "えむ",
+    "えぬ",
+    "おー",
+    "ぴー",
+    "きゅー",
+    "あーる",
+    "えす",
+    "てぃー",
+    "ゆー",
+    "ぶい",
+    "だぶりゅー",
+    "えっくす",
+    "わい",
+    "ぜっと",
+];
+
+/// 4桁までの数の読み
+fn four_digits(n: u64) -> String {
+    let (thousands, hundreds, tens, ones) = (n / 1000, n / 100 % 10, n / 10 % 10, n % 10);
+    let mut reading = String::new();
+    reading.push_str(match thousands {
+        0 => "",
+        1 => "せん",
+        3 => "さんぜん",
+        8 => "はっせん",
+        d => DIGITS[d as usize],
+    });
+    if !matches!(thousands, 0 | 1 | 3 | 8) {
+        reading.push_str("せん");
+    }
+    reading.push_str(match hundreds {
+        0 => "",
+        1 => "ひゃく",
+        3 => "さんびゃく",
+        6 => "ろっぴゃく",
+        8 => "はっぴゃく",
+        d => DIGITS[d as usize],
+    });
+    if !matches!(hundreds, 0 | 1 | 3 | 6 | 8) {
+        reading.push_str("ひゃく");
+    }
+    if tens > 1 {
+        reading.push_str(DIGITS[tens as usize]);
+    }
+    if tens > 0 {
+        reading.push_str("じゅう");
+    }
+    if ones > 0 {
+        reading.push_str(DIGITS[ones as usize]);
+    }
+    reading
+}
+
+/// 数字の並びの読み(`1973`なら`せんきゅうひゃくななじゅうさん`)
+///
+/// 0で始まる数や兆を超える数は1桁ずつ読む
+pub fn number_reading(digits: &str) -> String {
+    let by_digit = || {
+        digits
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .map(|d| DIGITS[d as usize])
+            .collect()
+    };
+    if digits.len() > 16 || (digits.len() > 1 && digits.starts_with('0')) {
+        return by_digit();
+    }
+    let Ok(mut n) = digits.parse::<u64>() else {
+        return by_digit();
+    };
+    if n == 0 {
+        return DIGITS[0].to_string();
+    }
+    let mut groups = Vec::new();
+    for unit in ["", "まん", "おく", "ちょう"] {
+        if n % 10000 > 0 {
+            groups.push(format!("{}{}", four_digits(n % 10000), unit));
+        }
+        n /= 10000;
+    }
+    groups.reverse();
+    groups.concat()
+}
+
+/// 読み上げたときの文字数(空白と改行は数えない)
+///
+/// 数字・単位・記号・略語の英大文字は読みに置き換えて数える(`1973年`は`せんきゅうひゃくななじゅうさん年`の16文字)
+pub fn spoken_length(text: &str) -> usize {
+    // 全角の英数字と記号は半角にしてから読む
+    let chars: Vec<char> = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '！'..='～' => char::from_u32(c as u32 - '！' as u32 + '!' as u32).unwrap_or(c),
+            c => c,
+        })
+        .collect();
+    let count = |reading: &str| reading.chars().count();
+    let mut len = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_digit() {
+            // 桁区切りのカンマは読まない
+            let mut digits = String::new();
+            while i < chars.len()
+                && (chars[i].is_ascii_digit()
+                    || (chars[i] == ',' && chars.get(i + 1).is_some_and(char::is_ascii_digit)))
+            {
+                if chars[i] != ',' {
+                    digits.push(chars[i]);
+                }
+                i += 1;
+            }
+            len += count(&number_reading(&digits));
+            // 小数点以下は「てん」と1桁ずつ読む
+            if chars.get(i) == Some(&'.') && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+                len += count("てん");
+                i += 1;
+                while let Some(d) = chars.get(i).and_then(|c| c.to_digit(10)) {
+                    len += count(DIGITS[d as usize]);
+                    i += 1;
+                }
+            }
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            if let Some((unit, reading)) = UNITS.iter().find(|(unit, _)| rest.starts_with(unit)) {
+                len += count(reading);
+                i += unit.chars().count();
+            }
+            continue;
+        }
+        if c.is_ascii_uppercase() {
+            len += count(LETTERS[(c as u8 - b'A') as usize]);
+        } else if let Some((_, reading)) = SYMBOLS.iter().find(|(symbol, _)| *symbol == c) {
+            len += count(reading);
+        } else {
+            len += 1;
+        }
+        i += 1;
+    }
+    len
+}
+
+/// 1秒あたりの文字数(読み上げたときの文字数で数える。[`spoken_length`]を参照)
+pub fn chars_per_second(block: &SrtBlock) -> f64 {
+    let chars = spoken_length(&block.text);
+    let secs = block.duration().as_secs_f64();
+    if secs > 0. {
+        chars as f64 / secs
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// テキストの長さから音声の長さを推定するための読み上げ速度
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct SpeechRate {
+    /// 1秒あたりに読み上げる文字数
+    pub expected_cps: f64,
+    /// 推定した長さの何倍(何分の1)までを許すか
+    pub tolerance: f64,
+}
+
+impl SpeechRate {
+    /// 1秒あたり`expected_cps`文字で読み上げるとする
+    pub fn new(expected_cps: f64) -> Self {
+        Self {
+            expected_cps,
+            ..Self::default()
+        }
+    }
+
+    /// 許容する倍率を設定する
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// テキストを読み上げるのにかかる長さを推定する
+    pub fn expected_duration(&self, text: &str) -> Duration {
+        let chars = spoken_length(text);
+        Duration::from_secs_f64(chars as f64 / self.expected_cps)
+    }
+
+    /// 音声の長さがテキストから推定した長さに近いか
+    ///
+    /// 数文字しかないテキストは推定できないため常に`true`
+    pub fn is_plausible(&self, text: &str, duration: Duration) -> bool {
+        if text.chars().filter(|c| !c.is_whitespace()).count() < 5 {
+            return true;
+        }
+        let expected = self.expected_duration(text).as_secs_f64();
+        let actual = duration.as_secs_f64();
+        actual >= expected / self.tolerance && actual <= expected * self.tolerance
+    }
+}
+
+impl Default for SpeechRate {
+    /// Voicepeakの標準的な速さ(1秒あたり7文字)で、3倍までのずれを許す
+    fn default() -> Self {
+        Self {
+            expected_cps: 7.,
+            tolerance: 3.,
+        }
+    }
+}
+
+/// 読みやすくするための分割位置を提案する
+///
+/// 中央に最も近い句読点の直後で、句読点がなければ中央で分ける。分けられない場合は`None`
+pub fn suggest_split(text: &str) -> Option<(String, String)> {
+    let chars: Vec<char> = text.trim().chars().collect();
+    if chars.len() < 2 {
+        return None;
+    }
+
+    let middle = chars.len() / 2;
+    let at = chars[..chars.len() - 1]
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| matches!(c, '、' | '。' | '，' | '．' | '！' | '？' | ' ' | ','))
+        .map(|(i, _)| i + 1)
+        .min_by_key(|i| i.abs_diff(middle))
+        .unwrap_or(middle);
+
+    let head: String = chars[..at].iter().collect();
+    let tail: String = chars[at..].iter().collect();
+    Some((head.trim().to_string(), tail.trim().to_string()))
+}
+
+/// 2つのテキストがどれだけ一致しているか(0〜1)
+///
+/// 音声認識の結果と比べるため、NFKCで正規化し、空白と句読点を除いた文字の編集距離から求める
+pub fn text_similarity(a: &str, b: &str) -> f64 {
+    let normalize =
+        |text: &str| -> Vec<char> { text.nfkc().filter(|c| c.is_alphanumeric()).collect() };
+    let (a, b) = (normalize(a), normalize(b));
+    let len = a.len().max(b.len());
+    if len == 0 {
+        return 1.;
+    }
+
+    // 1行ずつ更新する編集距離
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            let next = (prev + cost).min(row[j] + 1).min(row[j + 1] + 1);
+            prev = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    1. - row[b.len()] as f64 / len as f64
+}
+
+/// 字幕ブロックを検査して問題を列挙する
+pub fn validate(blocks: &[SrtBlock], options: &ValidateOptions) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut push = |index, kind| issues.push(Issue { index, kind });
+
+    for (i, block) in blocks.iter().enumerate() {
+        if options.check_numbering {
+            let expected = match i {
+                0 => 1,
+                _ => blocks[i - 1].index + 1,
+            };
+            if block.index != expected {
+                push(block.index, IssueKind::NumberingGap { expected });
+            }
+        }
+
+        if let Some(previous) = i.checked_sub(1).map(|p| &blocks[p]) {
+            if block.start < previous.start {
+                push(
+                    block.index,
+                    IssueKind::NonMonotonic {
+                        previous_start: previous.start,
+                    },
+                );
+            } else if block.start < previous.end {
+                push(
+                    block.index,
+                    IssueKind::Overlap {
+                        previous_end: previous.end,
+                    },
+                );
+            }
+        }
+
+        if block.end <= block.start {
+            push(block.index, IssueKind::EndBeforeStart);
+        }
+
+        if block.text.trim().is_empty() {
+            push(block.index, IssueKind::EmptyText);
+        } else if let Some(max_cps) = options.max_cps {
+            let cps = chars_per_second(block);
+            if block.end > block.start && cps > max_cps {
+                push(block.index, IssueKind::CpsExceeded { cps });
+            }
+        }
+    }
+
+    issues
+}
+
+#[test]
+fn test_validate() {
+    let ms = Duration::from_millis;
+    let blocks = vec![
+        SrtBlock::new(1, ms(0), ms(1000), "一つ目"),
+        SrtBlock::new(2, ms(900), ms(2000), "重なり"),
+        SrtBlock::new(4, ms(500), ms(600), ""),
+        SrtBlock::new(5, ms(3000), ms(3000), "長さ0"),
+        SrtBlock::new(6, ms(4000), ms(5000), "とても長いテキストです"),
+    ];
+    let issues = validate(&blocks, &ValidateOptions::new().max_cps(Some(8.)));
+    let kinds: Vec<(usize, &IssueKind)> = issues.iter().map(|i| (i.index, &i.kind)).collect();
+
+    assert_eq!(
+        vec![
+            (
+                2,
+                &IssueKind::Overlap {
+                    previous_end: ms(1000)
+                }
+            ),
+            (4, &IssueKind::NumberingGap { expected: 3 }),
+            (
+                4,
+                &IssueKind::NonMonotonic {
+                    previous_start: ms(900)
+                }
+            ),
+            (4, &IssueKind::EmptyText),
+            (5, &IssueKind::EndBeforeStart),
+            (6, &IssueKind::CpsExceeded { cps: 11. }),
+        ],
+        kinds
+    );
+}
+
+#[test]
+fn test_validate_generated_output() {
+    let text = std::fs::read_to_string("../subtitles.srt").unwrap();
+    let blocks = crate::parse::parse_srt(&text).unwrap();
+    assert!(validate(&blocks, &ValidateOptions::new()).is_empty());
+}
+
+#[test]
+fn test_suggest_split() {
+    assert_eq!(
+        Some(("あいう、".to_string(), "えおかきくけこ".to_string())),
+        suggest_split("あいう、えおかきくけこ")
+    );
+    assert_eq!(
+        Some(("あい".to_string(), "うえ".to_string())),
+        suggest_split("あいうえ")
+    );
+    assert_eq!(None, suggest_split("あ"));
+}
+
+#[test]
+fn test_speech_rate_is_plausible() {
+    let rate = SpeechRate::default();
+    let text = "あいうえおかきくけこさしすせそ";
+
+    assert_eq!(2142, rate.expected_duration(text).as_millis());
+    assert!(rate.is_plausible(text, Duration::from_secs(2)));
+    assert!(!rate.is_plausible(text, Duration::from_millis(500)));
+    assert!(!rate.is_plausible(text, Duration::from_secs(10)));
+    assert!(rate.is_plausible("あ", Duration::from_secs(10)));
+}
+
+#[test]
+fn test_spoken_length() {
+    assert_eq!("せんきゅうひゃくななじゅうさん", number_reading("1973"));
+    assert_eq!("さんびゃくまんはっせん", number_reading("3008000"));
+    assert_eq!("ぜろぜろなな", number_reading("007"));
+    assert_eq!(16, spoken_length("1973年"));
+    assert_eq!(spoken_length("1973年"), spoken_length("１９７３年"));
+    // ごじゅう+ぱーせんと、さん+てん+ご+きろめーとる
+    assert_eq!(9, spoken_length("50%"));
+    assert_eq!(11, spoken_length("3.5km"));
+    assert_eq!(7, spoken_length("NHK"));
+    assert_eq!(5, spoken_length("あいう えお"));
+}
+
+#[test]
+fn test_text_similarity() {
+    assert_eq!(1., text_similarity("こんにちは、世界。", "こんにちは 世界"));
+    assert_eq!(1., text_similarity("ＡＢＣ", "ABC"));
+    assert!((text_similarity("あいうえ", "あいかえ") - 0.75).abs() < 1e-9);
+    assert_eq!(0., text_similarity("あいう", ""));
+}
@@ -0,0 +1,35 @@
+//! ブラウザ向けのバインディング(`wasm`フィーチャー)
+
+use wasm_bindgen::prelude::*;
+
+use crate::{MemorySource, SubtitleBuilder};
+
+/// ドロップされたwavとtxtを保持し、srtを作成する
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmProject {
+    source: MemorySource,
+}
+
+#[wasm_bindgen]
+impl WasmProject {
+    /// ファイルが追加されていない状態で作成する
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ファイルを追加する
+    #[wasm_bindgen(js_name = addFile)]
+    pub fn add_file(&mut self, name: String, data: Vec<u8>) {
+        self.source.insert(name, data);
+    }
+
+    /// 追加済みのファイルからsrtを作成する
+    #[wasm_bindgen(js_name = toSrt)]
+    pub fn to_srt(&self) -> Result<String, JsError> {
+        let mut out = Vec::new();
+        SubtitleBuilder::from_source(self.source.clone()).write(&mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+}
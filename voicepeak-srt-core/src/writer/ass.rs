@@ -0,0 +1,277 @@
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use super::{Positions, SubtitleWriter};
+use crate::SrtBlock;
+
+/// 縦書きの字幕を置く画面の右上からの余白
+const VERTICAL_MARGIN: u32 = 60;
+
+/// 時刻をASSのタイムスタンプ形式(`H:MM:SS.cc`)に整形する
+pub fn format_ass_time(time: Duration) -> String {
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        time.as_secs() / 3600,
+        (time.as_secs() % 3600) / 60,
+        time.as_secs() % 60,
+        time.subsec_millis() / 10
+    )
+}
+
+/// ASS(Advanced SubStation Alpha)形式の書き出し
+pub struct AssWriter<W: Write> {
+    out: W,
+    positions: Positions,
+    font: String,
+    font_size: u32,
+    translation_font_size: u32,
+    vertical: bool,
+    /// 感情ごとのスタイルの名前と文字の色
+    emotion_styles: Vec<(String, String)>,
+    audio_file: Option<String>,
+    video_file: Option<String>,
+}
+
+impl<W: Write> AssWriter<W> {
+    /// `out`へ書き出す
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            positions: Positions::default(),
+            font: "Arial".to_string(),
+            font_size: 48,
+            translation_font_size: 36,
+            vertical: false,
+            emotion_styles: Vec::new(),
+            audio_file: None,
+            video_file: None,
+        }
+    }
+
+    /// ブロックの表示位置を指定する(画面上は`{\an8}`タグで表す)
+    pub fn with_positions(mut self, positions: Positions) -> Self {
+        self.positions = positions;
+        self
+    }
+
+    /// 既定のスタイルのフォントと大きさ(1080pでの大きさ)を指定する
+    pub fn with_font<S: Into<String>>(mut self, font: S, size: u32) -> Self {
+        self.font = font.into();
+        self.font_size = size;
+        self
+    }
+
+    /// 訳のスタイル(`Translation`)の大きさを指定する
+    pub fn with_translation_font_size(mut self, size: u32) -> Self {
+        self.translation_font_size = size;
+        self
+    }
+
+    /// 縦書きにして画面の右端に置く
+    ///
+    /// フォント名に`@`を付けた縦書き用のフォントを270度回して表示する(表示位置の指定は使わない)。
+    /// 折り返しや全角への変換は[`crate::transform::vertical_text`]で済ませておく
+    pub fn with_vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    /// 感情が`emotion`のブロックを、文字の色を`color`(`#ff8800`など)にした同じ名前のスタイルで書き出す
+    pub fn with_emotion_style<S: Into<String>>(mut self, emotion: S, color: &str) -> Self {
+        let name = emotion.into().replace(',', "，");
+        self.emotion_styles
+            .retain(|(existing, _)| *existing != name);
+        self.emotion_styles.push((name, color.to_string()));
+        self
+    }
+
+    /// Aegisubで開いたときに読み込む音声と動画を指定する
+    pub fn with_aegisub_files(mut self, audio: Option<String>, video: Option<String>) -> Self {
+        self.audio_file = audio;
+        self.video_file = video;
+        self
+    }
+
+    /// 書き出し先を取り出す
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// 既定のスタイルのフォント名(縦書きでは`@`を付ける)
+    fn default_font(&self) -> String {
+        match self.vertical {
+            true => format!("@{}", self.font),
+            false => self.font.clone(),
+        }
+    }
+}
+
+/// `#RRGGBB`をASSの色(`&H00BBGGRR`)にする
+fn ass_color(color: &str) -> String {
+    let hex = color.trim_start_matches('#');
+    match (hex.get(0..2), hex.get(2..4), hex.get(4..6)) {
+        (Some(r), Some(g), Some(b)) => format!("&H00{}{}{}", b, g, r).to_uppercase(),
+        _ => "&H00FFFFFF".to_string(),
+    }
+}
+
+impl<W: Write> SubtitleWriter for AssWriter<W> {
+    fn write_header(&mut self) -> io::Result<()> {
+        write!(
+            self.out,
+            "[Script Info]\n\
+             ; voicepeak-srtで作成\n\
+             ScriptType: v4.00+\n\
+             PlayResX: 1920\n\
+             PlayResY: 1080\n\
+             WrapStyle: 0\n\
+             ScaledBorderAndShadow: yes\n"
+        )?;
+
+        // Aegisubは音声・動画のパスをこの節から読み込む
+        if self.audio_file.is_some() || self.video_file.is_some() {
+            self.out.write_all(b"\n[Aegisub Project Garbage]\n")?;
+            if let Some(audio) = &self.audio_file {
+                writeln!(self.out, "Audio File: {}", audio)?;
+            }
+            if let Some(video) = &self.video_file {
+                writeln!(self.out, "Video File: {}", video)?;
+            }
+        }
+
+        write!(
+            self.out,
+            "\n[V4+ Styles]\n\
+             Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n\
+             Style: Default,{},{},&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,3,0,2,40,40,40,1\n\
+             Style: Translation,{},{},&H0080FFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,0,2,40,40,40,1\n",
+            self.default_font(),
+            self.font_size,
+            self.default_font(),
+            self.translation_font_size
+        )?;
+        for (name, color) in &self.emotion_styles {
+            writeln!(
+                self.out,
+                "Style: {},{},{},{},&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,3,0,2,40,40,40,1",
+                name,
+                self.default_font(),
+                self.font_size,
+                ass_color(color)
+            )?;
+        }
+        write!(
+            self.out,
+            "\n[Events]\n\
+             Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n"
+        )
+    }
+
+    fn write_cue(&mut self, cue: &SrtBlock) -> io::Result<()> {
+        // 縦書きでは左上を起点に時計回りに回すため、起点を右上に置くと列が左へ並ぶ
+        let tag = match self.vertical {
+            true => format!(
+                "{{\\an7\\frz270\\pos({},{})}}",
+                1920 - VERTICAL_MARGIN,
+                VERTICAL_MARGIN
+            ),
+            false => self.positions.an_tag(cue).unwrap_or_default(),
+        };
+        let escape = |text: &str| text.trim_end().replace("\r\n", "\n").replace('\n', "\\N");
+        // 訳は同じイベントの下の行に、スタイルを切り替えて重ねる
+        let text = match &cue.translation {
+            Some(translation) => format!(
+                "{}\\N{{\\rTranslation}}{}",
+                escape(&cue.text),
+                escape(translation)
+            ),
+            None => escape(&cue.text),
+        };
+        let style = cue
+            .emotion
+            .as_deref()
+            .map(|emotion| emotion.replace(',', "，"))
+            .filter(|emotion| self.emotion_styles.iter().any(|(name, _)| name == emotion))
+            .unwrap_or_else(|| "Default".to_string());
+        writeln!(
+            self.out,
+            "Dialogue: 0,{},{},{},{},0,0,0,,{}{}",
+            format_ass_time(cue.start),
+            format_ass_time(cue.end),
+            style,
+            cue.speaker
+                .as_deref()
+                .unwrap_or_default()
+                .replace(',', "，"),
+            tag,
+            text
+        )
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[test]
+fn test_write_ass() {
+    let mut cue = SrtBlock::new(
+        1,
+        Duration::from_millis(1234),
+        Duration::from_millis(3005),
+        "一行目\n二行目",
+    );
+    cue.speaker = Some("A".to_string());
+    let mut writer =
+        AssWriter::new(Vec::new()).with_aegisub_files(Some("voice.wav".to_string()), None);
+    super::write_cues(vec![cue], &mut writer).unwrap();
+    let text = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert!(text.contains("[Aegisub Project Garbage]\nAudio File: voice.wav\n"));
+    assert!(!text.contains("Video File"));
+    assert!(text.ends_with("Dialogue: 0,0:00:01.23,0:00:03.00,Default,A,0,0,0,,一行目\\N二行目\n"));
+}
+
+#[test]
+fn test_write_ass_translation() {
+    let mut cue = SrtBlock::new(1, Duration::ZERO, Duration::from_secs(1), "こんにちは");
+    cue.translation = Some("Hello".to_string());
+    let mut writer = AssWriter::new(Vec::new()).with_translation_font_size(30);
+    super::write_cues(vec![cue], &mut writer).unwrap();
+    let text = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert!(text.contains("Style: Translation,Arial,30,"));
+    assert!(text.ends_with(",,こんにちは\\N{\\rTranslation}Hello\n"));
+}
+
+#[test]
+fn test_write_ass_vertical() {
+    let cue = SrtBlock::new(1, Duration::ZERO, Duration::from_secs(1), "縦書き");
+    let mut writer = AssWriter::new(Vec::new())
+        .with_font("MS Gothic", 48)
+        .with_vertical(true);
+    super::write_cues(vec![cue], &mut writer).unwrap();
+    let text = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert!(text.contains("Style: Default,@MS Gothic,48,"));
+    assert!(text.contains("Style: Translation,@MS Gothic,36,"));
+    assert!(text.ends_with(",,{\\an7\\frz270\\pos(1860,60)}縦書き\n"));
+}
+
+#[test]
+fn test_write_ass_emotion_style() {
+    let mut happy = SrtBlock::new(1, Duration::ZERO, Duration::from_secs(1), "やった");
+    happy.emotion = Some("happy".to_string());
+    let mut sad = SrtBlock::new(2, Duration::from_secs(1), Duration::from_secs(2), "ああ");
+    sad.emotion = Some("sad".to_string());
+    let mut writer = AssWriter::new(Vec::new()).with_emotion_style("happy", "#ffd700");
+    super::write_cues(vec![happy, sad], &mut writer).unwrap();
+    let text = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert!(text.contains("Style: happy,Arial,48,&H0000D7FF,"));
+    assert!(text.contains("0:00:01.00,happy,"));
+    // スタイルのない感情は既定のスタイルにする
+    assert!(text.contains("0:00:02.00,Default,"));
+}
@@ -0,0 +1,161 @@
+use std::io::{self, Write};
+
+/// 書き出す字幕の文字コード
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OutputEncoding {
+    /// UTF-8(BOMなし)
+    #[default]
+    Utf8,
+    /// BOM付きのUTF-8(Windowsのエディターや一部の再生機器向け)
+    Utf8Bom,
+    /// Shift-JIS(古い再生ソフトやDVDオーサリングソフト向け)
+    ShiftJis,
+    /// BOM付きのUTF-16LE
+    Utf16Le,
+}
+
+impl OutputEncoding {
+    /// BOMを付けた文字コード(BOMのないShift-JISでは`None`)
+    pub fn with_bom(self) -> Option<Self> {
+        match self {
+            Self::Utf8 | Self::Utf8Bom => Some(Self::Utf8Bom),
+            Self::Utf16Le => Some(Self::Utf16Le),
+            Self::ShiftJis => None,
+        }
+    }
+}
+
+/// UTF-8で受け取ったテキストを指定の文字コードに変換して書き出す
+///
+/// 書き出しの途中で文字が分かれても、続きを受け取るまで待ってから変換する。
+/// Shift-JISで表せない文字があればエラーにする
+pub struct EncodedWriter<W: Write> {
+    out: W,
+    encoding: OutputEncoding,
+    /// 文字の途中で切れたUTF-8の残り
+    pending: Vec<u8>,
+    bom_written: bool,
+}
+
+impl<W: Write> EncodedWriter<W> {
+    /// `out`へ`encoding`で書き出す
+    pub fn new(out: W, encoding: OutputEncoding) -> Self {
+        Self {
+            out,
+            encoding,
+            pending: Vec::new(),
+            bom_written: false,
+        }
+    }
+
+    /// 書き出し先を取り出す
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// 完全な文字だけを変換して書き出す
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        if !self.bom_written {
+            self.bom_written = true;
+            match self.encoding {
+                OutputEncoding::Utf8Bom => self.out.write_all(b"\xEF\xBB\xBF")?,
+                OutputEncoding::Utf16Le => self.out.write_all(b"\xFF\xFE")?,
+                _ => {}
+            }
+        }
+        match self.encoding {
+            OutputEncoding::Utf8 | OutputEncoding::Utf8Bom => self.out.write_all(text.as_bytes()),
+            OutputEncoding::Utf16Le => {
+                let bytes: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                self.out.write_all(&bytes)
+            }
+            OutputEncoding::ShiftJis => {
+                let (bytes, _, unmappable) = encoding_rs::SHIFT_JIS.encode(text);
+                if unmappable {
+                    let c = text
+                        .chars()
+                        .find(|c| encoding_rs::SHIFT_JIS.encode(c.encode_utf8(&mut [0; 4])).2)
+                        .unwrap_or_default();
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Shift-JISで表せない文字があります: {}", c),
+                    ));
+                }
+                self.out.write_all(&bytes)
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for EncodedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let pending = std::mem::take(&mut self.pending);
+        let valid = match std::str::from_utf8(&pending) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "UTF-8ではないテキストは変換できません",
+                ))
+            }
+        };
+        // valid_up_toまでは正しいUTF-8であることが確かめられている
+        let text = std::str::from_utf8(&pending[..valid]).unwrap();
+        self.write_text(text)?;
+        self.pending = pending[valid..].to_vec();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[test]
+fn test_encoded_writer() {
+    let encode = |encoding, chunks: &[&[u8]]| {
+        let mut writer = EncodedWriter::new(Vec::new(), encoding);
+        for chunk in chunks {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.into_inner()
+    };
+    // 「あ」(E3 81 82)を途中で分けて渡す
+    let split: &[&[u8]] = &[b"1\n\xE3\x81", b"\x82"];
+
+    assert_eq!(
+        b"1\n\xE3\x81\x82".to_vec(),
+        encode(OutputEncoding::Utf8, split)
+    );
+    assert_eq!(
+        b"\xEF\xBB\xBF1\n\xE3\x81\x82".to_vec(),
+        encode(OutputEncoding::Utf8Bom, split)
+    );
+    assert_eq!(
+        b"1\n\x82\xA0".to_vec(),
+        encode(OutputEncoding::ShiftJis, split)
+    );
+    assert_eq!(
+        b"\xFF\xFE1\0\n\0\x42\x30".to_vec(),
+        encode(OutputEncoding::Utf16Le, split)
+    );
+
+    let mut writer = EncodedWriter::new(Vec::new(), OutputEncoding::ShiftJis);
+    assert!(writer.write_all("😀".as_bytes()).is_err());
+}
+
+#[test]
+fn test_with_bom() {
+    assert_eq!(
+        Some(OutputEncoding::Utf8Bom),
+        OutputEncoding::Utf8.with_bom()
+    );
+    assert_eq!(
+        Some(OutputEncoding::Utf16Le),
+        OutputEncoding::Utf16Le.with_bom()
+    );
+    assert_eq!(None, OutputEncoding::ShiftJis.with_bom());
+}
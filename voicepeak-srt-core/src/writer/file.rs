@@ -0,0 +1,84 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// 書き出しが完了した時点で出力先に置き換わるファイル
+///
+/// 途中でエラーになった場合は既存の出力を壊さずに一時ファイルを削除する
+pub struct OutputFile {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    file: Option<BufWriter<File>>,
+}
+
+impl OutputFile {
+    /// `path`への書き出しを開始する(確定するまでは`<path>.part`に書き込む)
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut tmp_path = OsString::from(path.as_os_str());
+        tmp_path.push(".part");
+        let tmp_path = PathBuf::from(tmp_path);
+        let file = BufWriter::new(File::create(&tmp_path)?);
+
+        Ok(Self {
+            path,
+            tmp_path,
+            file: Some(file),
+        })
+    }
+
+    /// 確定するまで書き込む一時ファイルのパス(外部のプログラムに書き出させるときに使う)
+    pub fn part_path(&self) -> &Path {
+        &self.tmp_path
+    }
+
+    /// 書き出しを確定して出力先に置き換える
+    pub fn commit(mut self) -> io::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        fs::rename(&self.tmp_path, &self.path)
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.as_mut().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().unwrap().flush()
+    }
+}
+
+impl Drop for OutputFile {
+    fn drop(&mut self) {
+        // 確定されなかった一時ファイルは削除する
+        if self.file.take().is_some() {
+            let _ = fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+#[test]
+fn test_output_file() {
+    let dir = std::env::temp_dir().join("voicepeak-srt-output-file");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("out.srt");
+    fs::write(&path, "old").unwrap();
+
+    // 確定しなければ既存のファイルはそのまま
+    let mut out = OutputFile::create(&path).unwrap();
+    out.write_all(b"new").unwrap();
+    drop(out);
+    assert_eq!("old", fs::read_to_string(&path).unwrap());
+    assert!(!dir.join("out.srt.part").exists());
+
+    let mut out = OutputFile::create(&path).unwrap();
+    out.write_all(b"new").unwrap();
+    out.commit().unwrap();
+    assert_eq!("new", fs::read_to_string(&path).unwrap());
+}
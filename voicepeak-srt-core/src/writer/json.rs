@@ -0,0 +1,51 @@
+use std::io::{self, Write};
+
+use super::SubtitleWriter;
+use crate::SrtBlock;
+
+/// `Project`と同じスキーマのJSONを1ブロックずつ書き出す
+pub struct JsonWriter<W: Write> {
+    out: W,
+    written: usize,
+}
+
+impl<W: Write> JsonWriter<W> {
+    /// `out`へ書き出す
+    pub fn new(out: W) -> Self {
+        Self { out, written: 0 }
+    }
+}
+
+impl<W: Write> SubtitleWriter for JsonWriter<W> {
+    fn write_header(&mut self) -> io::Result<()> {
+        self.out.write_all(b"{\n  \"cues\": [")
+    }
+
+    fn write_cue(&mut self, cue: &SrtBlock) -> io::Result<()> {
+        if self.written > 0 {
+            self.out.write_all(b",")?;
+        }
+        self.out.write_all(b"\n    ")?;
+        serde_json::to_writer(&mut self.out, cue)?;
+        self.written += 1;
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.write_all(b"\n  ]\n}\n")?;
+        self.out.flush()
+    }
+}
+
+#[test]
+fn test_json_writer_matches_project_schema() {
+    let files = crate::extract_wav_and_txt(std::path::Path::new("../voice")).unwrap();
+    let blocks = crate::make_srt_blocks(files).unwrap();
+
+    let mut out = Vec::new();
+    crate::write_cues(blocks.clone(), &mut JsonWriter::new(&mut out)).unwrap();
+
+    let project = crate::Project::from_json(out.as_slice()).unwrap();
+    assert_eq!(blocks, project.cues);
+}
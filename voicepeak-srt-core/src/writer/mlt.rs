@@ -0,0 +1,95 @@
+use std::io::{self, Write};
+
+use super::{SrtWriter, SubtitleWriter};
+use crate::SrtBlock;
+
+/// Shotcut(MLT XML)の字幕トラックの書き出し
+///
+/// Shotcutが字幕トラックとして保存する`subtitle_feed`フィルターに、srt形式の字幕を入れる。
+/// 書き出したファイルはShotcutで開くか、フィルターをプロジェクトの`<tractor>`に貼り付けて使う
+pub struct MltWriter<W: Write> {
+    out: W,
+    srt: SrtWriter<Vec<u8>>,
+    track: String,
+    language: String,
+}
+
+impl<W: Write> MltWriter<W> {
+    /// `out`へ書き出す
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            srt: SrtWriter::new(Vec::new()),
+            track: "Subtitles".to_string(),
+            language: "jpn".to_string(),
+        }
+    }
+
+    /// 字幕トラックの名前と言語(ISO 639-2)を指定する
+    pub fn with_track(mut self, name: &str, language: &str) -> Self {
+        self.track = name.to_string();
+        self.language = language.to_string();
+        self
+    }
+
+    /// 書き出し先を取り出す
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> SubtitleWriter for MltWriter<W> {
+    fn write_cue(&mut self, cue: &SrtBlock) -> io::Result<()> {
+        self.srt.write_cue(cue)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let srt = std::mem::replace(&mut self.srt, SrtWriter::new(Vec::new())).into_inner();
+        let text = String::from_utf8_lossy(&srt);
+        write!(
+            self.out,
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <mlt LC_NUMERIC=\"C\" version=\"7.0.0\">\n\
+             \x20 <tractor id=\"tractor0\">\n\
+             \x20   <filter id=\"subtitles\">\n\
+             \x20     <property name=\"mlt_service\">subtitle_feed</property>\n\
+             \x20     <property name=\"feed\">{}</property>\n\
+             \x20     <property name=\"lang\">{}</property>\n\
+             \x20     <property name=\"text\">{}\n</property>\n\
+             \x20   </filter>\n\
+             \x20 </tractor>\n\
+             </mlt>\n",
+            escape_xml(&self.track),
+            escape_xml(&self.language),
+            escape_xml(&text)
+        )?;
+        self.out.flush()
+    }
+}
+
+/// XMLの特殊文字をエスケープする(srtの`-->`は読みやすいようにそのままにする)
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
+}
+
+#[test]
+fn test_write_mlt() {
+    use std::time::Duration;
+
+    let blocks = vec![SrtBlock::new(
+        1,
+        Duration::ZERO,
+        Duration::from_millis(1500),
+        "A & B",
+    )];
+    let mut writer = MltWriter::new(Vec::new()).with_track("ナレーション", "jpn");
+    super::write_cues(blocks, &mut writer).unwrap();
+    let xml = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert!(xml.contains("<property name=\"feed\">ナレーション</property>"));
+    assert!(xml.contains(
+        "<property name=\"text\">1\n00:00:00,000 --> 00:00:01,500\nA &amp; B\n</property>"
+    ));
+}
@@ -0,0 +1,122 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{parse::SubtitleFormat, SrtBlock};
+
+mod ass;
+mod encoding;
+mod file;
+mod json;
+mod mlt;
+mod newline;
+mod position;
+mod regions;
+mod sbv;
+mod srt;
+mod timestamp;
+mod vtt;
+
+pub use ass::{format_ass_time, AssWriter};
+pub use encoding::{EncodedWriter, OutputEncoding};
+pub use file::OutputFile;
+pub use json::JsonWriter;
+pub use mlt::MltWriter;
+pub use newline::{LineEnding, NewlineWriter};
+pub use position::{CueAlign, CuePosition, Positions};
+pub use regions::RegionWriter;
+pub use sbv::SbvWriter;
+pub use srt::{format_srt_time, SrtWriter};
+pub use timestamp::{HourOverflow, TimestampFormat};
+pub use vtt::{format_vtt_time, CueIdentifier, VttWriter};
+
+/// 字幕の書き出し先
+///
+/// `write_header`→`write_cue`(ブロックの数だけ)→`finish`の順に呼ばれる。
+/// 独自形式の書き出しはこのトレイトを実装すれば、タイミング計算に手を入れずに追加できる
+pub trait SubtitleWriter {
+    /// ファイル先頭のヘッダを書き出す
+    fn write_header(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// 字幕ブロックを1つ書き出す
+    fn write_cue(&mut self, cue: &SrtBlock) -> io::Result<()>;
+
+    /// 書き出しを完了する
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: SubtitleWriter + ?Sized> SubtitleWriter for Box<S> {
+    fn write_header(&mut self) -> io::Result<()> {
+        (**self).write_header()
+    }
+
+    fn write_cue(&mut self, cue: &SrtBlock) -> io::Result<()> {
+        (**self).write_cue(cue)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+/// 字幕ブロックを順に書き出す
+pub fn write_cues<I, S>(cues: I, writer: &mut S) -> io::Result<()>
+where
+    I: IntoIterator<Item = SrtBlock>,
+    S: SubtitleWriter + ?Sized,
+{
+    writer.write_header()?;
+    for cue in cues {
+        writer.write_cue(&cue)?;
+    }
+    writer.finish()
+}
+
+/// 字幕ブロックをsrt形式で書き出す
+///
+/// ブロックは受け取った順に書き出すため、イテレータを渡せば全体を保持せずに済む
+pub fn write_srt<I, W>(srt_blocks: I, writer: &mut W) -> io::Result<()>
+where
+    I: IntoIterator<Item = SrtBlock>,
+    W: Write,
+{
+    write_cues(srt_blocks, &mut SrtWriter::new(writer))
+}
+
+/// 字幕ブロックをsrtファイルとして書き出す
+pub fn make_srt<I: IntoIterator<Item = SrtBlock>>(srt_blocks: I, path: &Path) -> io::Result<()> {
+    let mut file = OutputFile::create(path)?;
+    write_cues(srt_blocks, &mut SrtWriter::new(&mut file))?;
+    file.commit()
+}
+
+/// 字幕ブロックを指定の形式で書き出す
+pub fn write_subtitles<I, W>(srt_blocks: I, format: SubtitleFormat, writer: W) -> io::Result<()>
+where
+    I: IntoIterator<Item = SrtBlock>,
+    W: Write,
+{
+    match format {
+        SubtitleFormat::Vtt => write_cues(srt_blocks, &mut VttWriter::new(writer)),
+        SubtitleFormat::Ass => write_cues(srt_blocks, &mut AssWriter::new(writer)),
+        SubtitleFormat::Sbv => write_cues(srt_blocks, &mut SbvWriter::new(writer)),
+        SubtitleFormat::Json => write_cues(srt_blocks, &mut JsonWriter::new(writer)),
+        SubtitleFormat::Srt => write_cues(srt_blocks, &mut SrtWriter::new(writer)),
+    }
+}
+
+/// 字幕ブロックを拡張子に合わせた形式のファイルとして書き出す(判定できなければsrt)
+pub fn write_subtitle_file<I: IntoIterator<Item = SrtBlock>>(
+    srt_blocks: I,
+    path: &Path,
+) -> io::Result<()> {
+    let mut file = OutputFile::create(path)?;
+    let format = SubtitleFormat::from_path(path).unwrap_or(SubtitleFormat::Srt);
+    write_subtitles(srt_blocks, format, &mut file)?;
+    file.commit()
+}
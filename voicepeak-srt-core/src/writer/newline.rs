@@ -0,0 +1,71 @@
+use std::io::{self, Write};
+
+/// 書き出す字幕の改行コード
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// LF(`\n`)
+    #[default]
+    Lf,
+    /// CRLF(`\r\n`、LFだけのファイルを読めないWindowsのソフト向け)
+    Crlf,
+}
+
+/// 書き出すテキストの改行を指定の改行コードにそろえる
+///
+/// すでにCRLFになっている改行はそのまま書き出す
+pub struct NewlineWriter<W: Write> {
+    out: W,
+    line_ending: LineEnding,
+    /// 直前に書き出したのが`\r`か
+    after_cr: bool,
+}
+
+impl<W: Write> NewlineWriter<W> {
+    /// `out`へ`line_ending`の改行で書き出す
+    pub fn new(out: W, line_ending: LineEnding) -> Self {
+        Self {
+            out,
+            line_ending,
+            after_cr: false,
+        }
+    }
+
+    /// 書き出し先を取り出す
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> Write for NewlineWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.line_ending == LineEnding::Lf {
+            return self.out.write(buf);
+        }
+        let mut converted = Vec::with_capacity(buf.len());
+        for &byte in buf {
+            if byte == b'\n' && !self.after_cr {
+                converted.push(b'\r');
+            }
+            converted.push(byte);
+            self.after_cr = byte == b'\r';
+        }
+        self.out.write_all(&converted)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[test]
+fn test_newline_writer() {
+    let mut writer = NewlineWriter::new(Vec::new(), LineEnding::Crlf);
+    writer.write_all(b"1\n00:00:00,000\r").unwrap();
+    writer.write_all(b"\na\n\n").unwrap();
+
+    assert_eq!(
+        b"1\r\n00:00:00,000\r\na\r\n\r\n".to_vec(),
+        writer.into_inner()
+    );
+}
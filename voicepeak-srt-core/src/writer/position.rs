@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+
+use crate::SrtBlock;
+
+/// 字幕の表示位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CuePosition {
+    /// 画面下(再生ソフトの既定)
+    #[default]
+    Bottom,
+    /// 画面上
+    Top,
+    /// 画面上端からの割合(%)で指定した行
+    ///
+    /// WebVTTの`line:NN%`として書き出す。srtでは50%未満なら画面上、それ以外は画面下になる
+    Line(u8),
+}
+
+impl CuePosition {
+    /// WebVTTのキュー設定(`line:0%`など、画面下なら`None`)
+    pub fn vtt_settings(self) -> Option<String> {
+        match self {
+            Self::Bottom => None,
+            Self::Top => Some("line:0%".to_string()),
+            Self::Line(percent) => Some(format!("line:{}%", percent.min(100))),
+        }
+    }
+
+    /// srtのテキストの先頭に付けるタグ(`{\an8}`、画面下なら`None`)
+    pub fn srt_tag(self) -> Option<&'static str> {
+        match self {
+            Self::Top => Some("{\\an8}"),
+            Self::Line(percent) if percent < 50 => Some("{\\an8}"),
+            _ => None,
+        }
+    }
+}
+
+/// 字幕の横方向の揃え
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CueAlign {
+    /// 中央(再生ソフトの既定)
+    #[default]
+    Center,
+    /// 左寄せ
+    Left,
+    /// 右寄せ
+    Right,
+}
+
+impl CueAlign {
+    /// WebVTTのキュー設定(`align:left`など、中央なら`None`)
+    pub fn vtt_settings(self) -> Option<&'static str> {
+        match self {
+            Self::Center => None,
+            Self::Left => Some("align:left"),
+            Self::Right => Some("align:right"),
+        }
+    }
+}
+
+/// 全体と話者ごとの表示位置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Positions {
+    default: CuePosition,
+    speakers: BTreeMap<String, CuePosition>,
+    default_align: CueAlign,
+    speaker_aligns: BTreeMap<String, CueAlign>,
+}
+
+impl Positions {
+    /// すべて画面下にする
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 話者の指定がないブロックの位置
+    pub fn default_position(mut self, position: CuePosition) -> Self {
+        self.default = position;
+        self
+    }
+
+    /// `speaker`のブロックの位置
+    pub fn speaker<S: Into<String>>(mut self, speaker: S, position: CuePosition) -> Self {
+        self.speakers.insert(speaker.into(), position);
+        self
+    }
+
+    /// 話者の指定がないブロックの揃え
+    pub fn default_align(mut self, align: CueAlign) -> Self {
+        self.default_align = align;
+        self
+    }
+
+    /// `speaker`のブロックの揃え
+    pub fn speaker_align<S: Into<String>>(mut self, speaker: S, align: CueAlign) -> Self {
+        self.speaker_aligns.insert(speaker.into(), align);
+        self
+    }
+
+    /// ブロックの位置を求める
+    pub fn position(&self, cue: &SrtBlock) -> CuePosition {
+        cue.speaker
+            .as_ref()
+            .and_then(|speaker| self.speakers.get(speaker))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// ブロックの揃えを求める
+    pub fn align(&self, cue: &SrtBlock) -> CueAlign {
+        cue.speaker
+            .as_ref()
+            .and_then(|speaker| self.speaker_aligns.get(speaker))
+            .copied()
+            .unwrap_or(self.default_align)
+    }
+
+    /// srt・ASSのテキストの先頭に付ける`{\anN}`タグ(画面下の中央なら`None`)
+    ///
+    /// `N`はテンキーの配置で、下段が1〜3、上段が7〜9になる
+    pub fn an_tag(&self, cue: &SrtBlock) -> Option<String> {
+        let row = match self.position(cue).srt_tag() {
+            Some(_) => 7,
+            None => 1,
+        };
+        let column = match self.align(cue) {
+            CueAlign::Left => 0,
+            CueAlign::Center => 1,
+            CueAlign::Right => 2,
+        };
+        match row + column {
+            2 => None,
+            an => Some(format!("{{\\an{}}}", an)),
+        }
+    }
+
+    /// WebVTTのキュー設定(画面下の中央なら`None`)
+    pub fn vtt_settings(&self, cue: &SrtBlock) -> Option<String> {
+        let settings: Vec<String> = [
+            self.position(cue).vtt_settings(),
+            self.align(cue).vtt_settings().map(str::to_string),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!settings.is_empty()).then(|| settings.join(" "))
+    }
+}
+
+#[test]
+fn test_positions_align() {
+    use std::time::Duration;
+
+    let mut cue = SrtBlock::new(1, Duration::ZERO, Duration::from_secs(1), "a");
+    cue.speaker = Some("B".to_string());
+    let positions = Positions::new()
+        .speaker("B", CuePosition::Top)
+        .speaker_align("B", CueAlign::Right);
+    assert_eq!(Some("{\\an9}".to_string()), positions.an_tag(&cue));
+    assert_eq!(
+        Some("line:0% align:right".to_string()),
+        positions.vtt_settings(&cue)
+    );
+
+    cue.speaker = None;
+    assert_eq!(None, positions.an_tag(&cue));
+    let left = Positions::new().default_align(CueAlign::Left);
+    assert_eq!(Some("{\\an1}".to_string()), left.an_tag(&cue));
+}
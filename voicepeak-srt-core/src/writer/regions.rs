@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+
+use super::{format_vtt_time, SubtitleWriter};
+use crate::SrtBlock;
+
+/// Vegas Proなどの編集ソフトに読み込むリージョン一覧(タブ区切り)の書き出し
+///
+/// `Start`・`End`・`Length`・`Name`の列で、時刻は`HH:MM:SS.mmm`。
+/// 名前は`話者: テキスト`(話者がなければテキストだけ)を1行にしたもの
+pub struct RegionWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> RegionWriter<W> {
+    /// `out`へ書き出す
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// 書き出し先を取り出す
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> SubtitleWriter for RegionWriter<W> {
+    fn write_header(&mut self) -> io::Result<()> {
+        self.out.write_all(b"Start\tEnd\tLength\tName\n")
+    }
+
+    fn write_cue(&mut self, cue: &SrtBlock) -> io::Result<()> {
+        let text = cue.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let name = match &cue.speaker {
+            Some(speaker) => format!("{}: {}", speaker, text),
+            None => text,
+        };
+        writeln!(
+            self.out,
+            "{}\t{}\t{}\t{}",
+            format_vtt_time(cue.start),
+            format_vtt_time(cue.end),
+            format_vtt_time(cue.end.saturating_sub(cue.start)),
+            name
+        )
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[test]
+fn test_write_regions() {
+    use std::time::Duration;
+
+    let mut second = SrtBlock::new(
+        2,
+        Duration::from_millis(1500),
+        Duration::from_secs(3),
+        "二つ目\n続き",
+    );
+    second.speaker = Some("B".to_string());
+    let blocks = vec![
+        SrtBlock::new(1, Duration::ZERO, Duration::from_millis(1500), "一つ目"),
+        second,
+    ];
+    let mut writer = RegionWriter::new(Vec::new());
+    super::write_cues(blocks, &mut writer).unwrap();
+
+    assert_eq!(
+        "Start\tEnd\tLength\tName\n\
+         00:00:00.000\t00:00:01.500\t00:00:01.500\t一つ目\n\
+         00:00:01.500\t00:00:03.000\t00:00:01.500\tB: 二つ目 続き\n",
+        String::from_utf8(writer.into_inner()).unwrap()
+    );
+}
@@ -0,0 +1,78 @@
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use super::SubtitleWriter;
+use crate::SrtBlock;
+
+/// YouTubeのSBV形式の書き出し
+pub struct SbvWriter<W: Write> {
+    out: W,
+    written: usize,
+}
+
+impl<W: Write> SbvWriter<W> {
+    /// `out`へ書き出す
+    pub fn new(out: W) -> Self {
+        Self { out, written: 0 }
+    }
+
+    /// 書き出し先を取り出す
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> SubtitleWriter for SbvWriter<W> {
+    fn write_cue(&mut self, cue: &SrtBlock) -> io::Result<()> {
+        if self.written > 0 {
+            self.out.write_all(b"\n")?;
+        }
+        writeln!(
+            self.out,
+            "{},{}\n{}",
+            format_sbv_time(cue.start),
+            format_sbv_time(cue.end),
+            cue.text
+        )?;
+        self.written += 1;
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// 時刻をSBVのタイムスタンプ形式(`H:MM:SS.mmm`)に整形する
+fn format_sbv_time(time: Duration) -> String {
+    format!(
+        "{}:{:02}:{:02}.{:03}",
+        time.as_secs() / 3600,
+        (time.as_secs() % 3600) / 60,
+        time.as_secs() % 60,
+        time.subsec_millis()
+    )
+}
+
+#[test]
+fn test_write_sbv() {
+    let blocks = vec![
+        SrtBlock::new(1, Duration::ZERO, Duration::from_millis(1500), "一つ目"),
+        SrtBlock::new(
+            2,
+            Duration::from_millis(1500),
+            Duration::from_secs(3),
+            "二つ目",
+        ),
+    ];
+    let mut writer = SbvWriter::new(Vec::new());
+    super::write_cues(blocks, &mut writer).unwrap();
+
+    assert_eq!(
+        "0:00:00.000,0:00:01.500\n一つ目\n\n0:00:01.500,0:00:03.000\n二つ目\n",
+        String::from_utf8(writer.into_inner()).unwrap()
+    );
+}
@@ -0,0 +1,205 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    time::Duration,
+};
+
+use super::{Positions, SubtitleWriter, TimestampFormat};
+use crate::SrtBlock;
+
+/// 時刻をsrtのタイムスタンプ形式(`HH:MM:SS,mmm`)に整形する
+pub fn format_srt_time(time: Duration) -> String {
+    TimestampFormat::srt().format(time)
+}
+
+/// srt形式の書き出し
+pub struct SrtWriter<W: Write> {
+    out: W,
+    written: usize,
+    positions: Positions,
+    colors: BTreeMap<String, String>,
+    emotion_colors: BTreeMap<String, String>,
+    timestamps: TimestampFormat,
+    index: bool,
+}
+
+impl<W: Write> SrtWriter<W> {
+    /// `out`へ書き出す
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            written: 0,
+            positions: Positions::default(),
+            colors: BTreeMap::new(),
+            emotion_colors: BTreeMap::new(),
+            timestamps: TimestampFormat::srt(),
+            index: true,
+        }
+    }
+
+    /// ブロックの表示位置を指定する(画面上は`{\an8}`タグで表す)
+    pub fn with_positions(mut self, positions: Positions) -> Self {
+        self.positions = positions;
+        self
+    }
+
+    /// `speaker`のブロックのテキストを`<font color="...">`で囲む(`color`は`#ff8800`など)
+    ///
+    /// 色を表示できる再生ソフト向けで、対応していないソフトではタグがそのまま見えることがある
+    pub fn with_speaker_color<S: Into<String>>(mut self, speaker: S, color: &str) -> Self {
+        self.colors.insert(speaker.into(), color.to_string());
+        self
+    }
+
+    /// 感情が`emotion`のブロックのテキストを`<font color="...">`で囲む(話者の色より優先する)
+    pub fn with_emotion_color<S: Into<String>>(mut self, emotion: S, color: &str) -> Self {
+        self.emotion_colors
+            .insert(emotion.into(), color.to_string());
+        self
+    }
+
+    /// タイムスタンプの書き方を指定する(既定は`00:00:01,500`)
+    pub fn with_timestamp_format(mut self, format: TimestampFormat) -> Self {
+        self.timestamps = format;
+        self
+    }
+
+    /// 番号の行を書き出すか(既定は書き出す。番号を振り直すソフト向けに省ける)
+    pub fn with_index(mut self, index: bool) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// 書き出し先を取り出す
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> SubtitleWriter for SrtWriter<W> {
+    fn write_cue(&mut self, cue: &SrtBlock) -> io::Result<()> {
+        // 書き出せない時刻なら途中まで書き出さずにエラーにする
+        let start = self.timestamps.try_format(cue.start)?;
+        let end = self.timestamps.try_format(cue.end)?;
+        // ブロック間は空行で区切る
+        if self.written > 0 {
+            self.out.write_all(b"\n\n")?;
+        }
+        let color = cue
+            .emotion
+            .as_ref()
+            .and_then(|e| self.emotion_colors.get(e))
+            .or_else(|| cue.speaker.as_ref().and_then(|s| self.colors.get(s)));
+        let text = match color {
+            Some(color) => format!("<font color=\"{}\">{}</font>", color, cue.text.trim_end()),
+            None => cue.text.trim_end().to_string(),
+        };
+        if self.index {
+            writeln!(self.out, "{}", cue.index)?;
+        }
+        write!(
+            self.out,
+            "{} --> {}\n{}{}",
+            start,
+            end,
+            self.positions.an_tag(cue).unwrap_or_default(),
+            text
+        )?;
+        self.written += 1;
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[test]
+fn test_format_srt_time() {
+    assert_eq!("00:00:00,000", format_srt_time(Duration::ZERO));
+    assert_eq!(
+        "01:02:03,045",
+        format_srt_time(Duration::from_millis(3_723_045))
+    );
+}
+
+#[test]
+fn test_write_srt() {
+    let blocks = vec![
+        SrtBlock {
+            index: 1,
+            start: Duration::ZERO,
+            end: Duration::from_millis(1500),
+            text: "一つ目\n".to_string(),
+            speaker: None,
+            translation: None,
+            emotion: None,
+            source: None,
+        },
+        SrtBlock {
+            index: 2,
+            start: Duration::from_millis(1500),
+            end: Duration::from_millis(3000),
+            text: "二つ目".to_string(),
+            speaker: None,
+            translation: None,
+            emotion: None,
+            source: None,
+        },
+    ];
+    let mut out = Vec::new();
+    crate::write_srt(blocks, &mut out).unwrap();
+
+    assert_eq!(
+        "1\n00:00:00,000 --> 00:00:01,500\n一つ目\n\n2\n00:00:01,500 --> 00:00:03,000\n二つ目",
+        String::from_utf8(out).unwrap()
+    );
+}
+
+#[test]
+fn test_write_srt_speaker_color() {
+    let mut cue = SrtBlock::new(1, Duration::ZERO, Duration::from_secs(1), "こんにちは");
+    cue.speaker = Some("A".to_string());
+    let mut writer = SrtWriter::new(Vec::new()).with_speaker_color("A", "#ff8800");
+    crate::write_cues(vec![cue], &mut writer).unwrap();
+
+    assert_eq!(
+        "1\n00:00:00,000 --> 00:00:01,000\n<font color=\"#ff8800\">こんにちは</font>",
+        String::from_utf8(writer.into_inner()).unwrap()
+    );
+}
+
+#[test]
+fn test_write_srt_emotion_color() {
+    let mut cue = SrtBlock::new(1, Duration::ZERO, Duration::from_secs(1), "やった");
+    cue.speaker = Some("A".to_string());
+    cue.emotion = Some("happy".to_string());
+    let mut writer = SrtWriter::new(Vec::new())
+        .with_speaker_color("A", "#ff8800")
+        .with_emotion_color("happy", "#ffd700");
+    crate::write_cues(vec![cue], &mut writer).unwrap();
+
+    assert_eq!(
+        "1\n00:00:00,000 --> 00:00:01,000\n<font color=\"#ffd700\">やった</font>",
+        String::from_utf8(writer.into_inner()).unwrap()
+    );
+}
+
+#[test]
+fn test_write_srt_without_index() {
+    let blocks = vec![
+        SrtBlock::new(1, Duration::ZERO, Duration::from_secs(1), "一つ目"),
+        SrtBlock::new(2, Duration::from_secs(1), Duration::from_secs(2), "二つ目"),
+    ];
+    let mut writer = SrtWriter::new(Vec::new()).with_index(false);
+    crate::write_cues(blocks.clone(), &mut writer).unwrap();
+    let text = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert_eq!(
+        "00:00:00,000 --> 00:00:01,000\n一つ目\n\n00:00:01,000 --> 00:00:02,000\n二つ目",
+        text
+    );
+    // 番号のないブロックはファイル内での順番が番号になる
+    assert_eq!(blocks, crate::parse::parse_srt(&text).unwrap());
+}
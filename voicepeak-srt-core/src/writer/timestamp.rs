@@ -0,0 +1,133 @@
+use std::{io, time::Duration};
+
+/// 2桁で表せない100時間以上の時刻の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HourOverflow {
+    /// 時の桁を増やして書き出す(`100:00:00,000`)
+    #[default]
+    Extend,
+    /// 書き出さずにエラーにする(時が2桁のファイルしか読めないソフト向け)
+    Error,
+}
+
+/// srt・vttのタイムスタンプ(`HH:MM:SS,mmm`)の書き方
+///
+/// 一方の書き方しか読めないソフト向けに、秒の区切り文字と小数点以下の桁数を変えられる
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampFormat {
+    separator: char,
+    precision: u8,
+    hours: HourOverflow,
+}
+
+impl TimestampFormat {
+    /// 区切り文字`separator`のあとに秒の小数点以下を`precision`桁(最大3桁、0なら省く)書く
+    pub fn new(separator: char, precision: u8) -> Self {
+        Self {
+            separator,
+            precision: precision.min(3),
+            hours: HourOverflow::default(),
+        }
+    }
+
+    /// srtの書き方(`00:00:01,500`)
+    pub fn srt() -> Self {
+        Self::new(',', 3)
+    }
+
+    /// WebVTTの書き方(`00:00:01.500`)
+    pub fn vtt() -> Self {
+        Self::new('.', 3)
+    }
+
+    /// 区切り文字を差し替える
+    pub fn with_separator(self, separator: char) -> Self {
+        Self { separator, ..self }
+    }
+
+    /// 小数点以下の桁数を差し替える
+    pub fn with_precision(self, precision: u8) -> Self {
+        Self {
+            precision: precision.min(3),
+            ..self
+        }
+    }
+
+    /// 100時間以上の時刻の扱いを差し替える
+    pub fn with_hour_overflow(self, hours: HourOverflow) -> Self {
+        Self { hours, ..self }
+    }
+
+    /// 100時間以上の時刻の扱いに従って時刻を整形する
+    pub fn try_format(&self, time: Duration) -> io::Result<String> {
+        if self.hours == HourOverflow::Error && time.as_secs() >= 100 * 3600 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("100時間以上の時刻は書き出せません: {}", self.format(time)),
+            ));
+        }
+        Ok(self.format(time))
+    }
+
+    /// 時刻を整形する(表せない桁は切り捨て、100時間以上は時の桁を増やす)
+    pub fn format(&self, time: Duration) -> String {
+        let secs = time.as_secs();
+        let mut text = format!(
+            "{:02}:{:02}:{:02}",
+            secs / 3600,
+            (secs % 3600) / 60,
+            secs % 60
+        );
+        if self.precision > 0 {
+            let fraction = time.subsec_millis() / 10u32.pow(3 - u32::from(self.precision));
+            text.push_str(&format!(
+                "{}{:0width$}",
+                self.separator,
+                fraction,
+                width = usize::from(self.precision)
+            ));
+        }
+        text
+    }
+}
+
+#[test]
+fn test_timestamp_format() {
+    let time = Duration::from_millis(3_723_456);
+
+    assert_eq!("01:02:03,456", TimestampFormat::srt().format(time));
+    assert_eq!(
+        "01:02:03.45",
+        TimestampFormat::srt()
+            .with_separator('.')
+            .with_precision(2)
+            .format(time)
+    );
+    assert_eq!(
+        "01:02:03",
+        TimestampFormat::vtt().with_precision(0).format(time)
+    );
+    assert_eq!(
+        "00:00:00.05",
+        TimestampFormat::new('.', 2).format(Duration::from_millis(59))
+    );
+}
+
+#[test]
+fn test_timestamp_hour_overflow() {
+    let time = Duration::from_secs(123 * 3600 + 4);
+
+    assert_eq!("123:00:04,000", TimestampFormat::srt().format(time));
+    assert_eq!(
+        "99:59:59,999",
+        TimestampFormat::srt()
+            .with_hour_overflow(HourOverflow::Error)
+            .try_format(Duration::from_millis(100 * 3600 * 1000 - 1))
+            .unwrap()
+    );
+    assert!(TimestampFormat::srt()
+        .with_hour_overflow(HourOverflow::Error)
+        .try_format(time)
+        .is_err());
+}
@@ -0,0 +1,213 @@
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use super::{Positions, SubtitleWriter, TimestampFormat};
+use crate::{FilePattern, SrtBlock};
+
+/// 時刻をWebVTTのタイムスタンプ形式(`HH:MM:SS.mmm`)に整形する
+pub fn format_vtt_time(time: Duration) -> String {
+    TimestampFormat::vtt().format(time)
+}
+
+/// WebVTTのブロックの識別子に使う値
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub enum CueIdentifier {
+    /// 字幕番号
+    #[default]
+    Index,
+    /// 元になった音声のファイル名(`000-voice.wav`)
+    FileName,
+    /// 元になった音声のファイル名からパターンで取り出した連番
+    Sequence(FilePattern),
+}
+
+impl CueIdentifier {
+    /// ブロックの識別子(元の音声が分からなければ字幕番号)
+    fn of(&self, cue: &SrtBlock) -> String {
+        let source = cue.source.as_deref();
+        let id = match self {
+            Self::Index => None,
+            Self::FileName => source
+                .and_then(|path| path.file_name())
+                .map(|name| name.to_string_lossy().into_owned()),
+            Self::Sequence(pattern) => source
+                .and_then(|path| pattern.sequence(path))
+                .map(|seq| seq.to_string()),
+        };
+        id.unwrap_or_else(|| cue.index.to_string())
+    }
+}
+
+/// WebVTT形式の書き出し
+pub struct VttWriter<W: Write> {
+    out: W,
+    positions: Positions,
+    timestamps: TimestampFormat,
+    index: bool,
+    identifier: CueIdentifier,
+    metadata: Vec<(String, String)>,
+    notes: Vec<String>,
+}
+
+impl<W: Write> VttWriter<W> {
+    /// `out`へ書き出す
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            positions: Positions::default(),
+            timestamps: TimestampFormat::vtt(),
+            index: true,
+            identifier: CueIdentifier::default(),
+            metadata: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    /// ブロックの表示位置を指定する
+    pub fn with_positions(mut self, positions: Positions) -> Self {
+        self.positions = positions;
+        self
+    }
+
+    /// タイムスタンプの書き方を指定する(既定は`00:00:01.500`)
+    pub fn with_timestamp_format(mut self, format: TimestampFormat) -> Self {
+        self.timestamps = format;
+        self
+    }
+
+    /// 番号を識別子として書き出すか(既定は書き出す)
+    pub fn with_index(mut self, index: bool) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// 識別子に使う値を指定する(元の音声のファイル名にすれば、表示中の字幕から音声をたどれる)
+    pub fn with_identifier(mut self, identifier: CueIdentifier) -> Self {
+        self.identifier = identifier;
+        self
+    }
+
+    /// `WEBVTT`の次の行にヘッダの情報(`Language: ja`など)を書き足す
+    pub fn with_metadata<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+
+    /// ヘッダの後、最初のブロックの前にNOTEブロックを書き足す
+    ///
+    /// 空行や`-->`を含むテキストはNOTEブロックにできないため、呼び出し側で取り除く
+    pub fn with_note<S: Into<String>>(mut self, note: S) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// 書き出し先を取り出す
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> SubtitleWriter for VttWriter<W> {
+    fn write_header(&mut self) -> io::Result<()> {
+        self.out.write_all(b"WEBVTT\n")?;
+        for (key, value) in &self.metadata {
+            writeln!(self.out, "{}: {}", key, value)?;
+        }
+        for note in &self.notes {
+            writeln!(self.out, "\nNOTE {}", note)?;
+        }
+        Ok(())
+    }
+
+    fn write_cue(&mut self, cue: &SrtBlock) -> io::Result<()> {
+        // 書き出せない時刻なら途中まで書き出さずにエラーにする
+        let start = self.timestamps.try_format(cue.start)?;
+        let end = self.timestamps.try_format(cue.end)?;
+        self.out.write_all(b"\n")?;
+        if self.index {
+            writeln!(self.out, "{}", self.identifier.of(cue))?;
+        }
+        write!(self.out, "{} --> {}", start, end)?;
+        if let Some(settings) = self.positions.vtt_settings(cue) {
+            write!(self.out, " {}", settings)?;
+        }
+        writeln!(self.out, "\n{}", cue.text.trim_end())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[test]
+fn test_write_vtt() {
+    use super::CuePosition;
+
+    let mut second = SrtBlock::new(2, Duration::from_secs(1), Duration::from_secs(2), "二つ目");
+    second.speaker = Some("B".to_string());
+    let blocks = vec![
+        SrtBlock::new(1, Duration::ZERO, Duration::from_millis(1500), "一つ目"),
+        second,
+    ];
+
+    let positions = Positions::new()
+        .default_position(CuePosition::Line(80))
+        .speaker("B", CuePosition::Top);
+    let mut writer = VttWriter::new(Vec::new()).with_positions(positions);
+    super::write_cues(blocks.clone(), &mut writer).unwrap();
+    let text = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert_eq!(
+        "WEBVTT\n\n1\n00:00:00.000 --> 00:00:01.500 line:80%\n一つ目\n\n2\n00:00:01.000 --> 00:00:02.000 line:0%\n二つ目\n",
+        text
+    );
+    let parsed = crate::parse::parse_vtt(&text).unwrap();
+    assert_eq!(blocks[1].end, parsed[1].end);
+    assert_eq!(blocks[1].text, parsed[1].text);
+}
+
+#[test]
+fn test_write_vtt_header() {
+    let blocks = vec![SrtBlock::new(
+        1,
+        Duration::ZERO,
+        Duration::from_secs(1),
+        "一つ目",
+    )];
+    let mut writer = VttWriter::new(Vec::new())
+        .with_metadata("Language", "ja")
+        .with_note("作成: voicepeak-srt");
+    super::write_cues(blocks, &mut writer).unwrap();
+    let text = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert_eq!(
+        "WEBVTT\nLanguage: ja\n\nNOTE 作成: voicepeak-srt\n\n1\n00:00:00.000 --> 00:00:01.000\n一つ目\n",
+        text
+    );
+    assert_eq!(1, crate::parse::parse_vtt(&text).unwrap().len());
+}
+
+#[test]
+fn test_write_vtt_identifier() {
+    let mut cue = SrtBlock::new(1, Duration::ZERO, Duration::from_secs(1), "一つ目");
+    cue.source = Some("voice/007-voice.wav".into());
+    let blocks = vec![
+        cue,
+        SrtBlock::new(2, Duration::from_secs(1), Duration::from_secs(2), "二つ目"),
+    ];
+    let write = |identifier| {
+        let mut writer = VttWriter::new(Vec::new()).with_identifier(identifier);
+        super::write_cues(blocks.clone(), &mut writer).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    };
+
+    // 元の音声が分からないブロックは番号にする
+    assert_eq!(
+        "WEBVTT\n\n007-voice.wav\n00:00:00.000 --> 00:00:01.000\n一つ目\n\n2\n00:00:01.000 --> 00:00:02.000\n二つ目\n",
+        write(CueIdentifier::FileName)
+    );
+    assert!(write(CueIdentifier::Sequence(FilePattern::default())).starts_with("WEBVTT\n\n7\n"));
+}
@@ -0,0 +1,38 @@
+#ifndef VOICEPEAK_SRT_H
+#define VOICEPEAK_SRT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define VPSRT_OK 0
+#define VPSRT_INVALID_ARGUMENT (-1)
+#define VPSRT_FAILED (-2)
+
+typedef struct VpsrtCueList VpsrtCueList;
+
+typedef struct VpsrtCue {
+    size_t index;
+    uint64_t start_ms;
+    uint64_t end_ms;
+    /* VpsrtCueListが解放されるまで有効 */
+    const char *text;
+} VpsrtCue;
+
+/* ディレクトリ内のwavとtxtからsrtファイルを作成する */
+int generate_srt_from_dir(const char *input_dir, const char *output_path);
+
+/* 字幕ブロックの一覧を作成する。失敗時はNULL */
+VpsrtCueList *cue_list_from_dir(const char *input_dir);
+size_t cue_list_len(const VpsrtCueList *list);
+int cue_list_get(const VpsrtCueList *list, size_t index, VpsrtCue *out);
+void cue_list_free(VpsrtCueList *list);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
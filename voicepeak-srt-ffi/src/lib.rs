@@ -0,0 +1,191 @@
+//! C言語から呼び出すためのインターフェース
+//!
+//! 宣言は`include/voicepeak_srt.h`を参照
+
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    ptr,
+};
+
+use voicepeak_srt_core::{SrtBlock, SubtitleBuilder};
+
+/// 成功
+pub const VPSRT_OK: c_int = 0;
+/// 引数が不正
+pub const VPSRT_INVALID_ARGUMENT: c_int = -1;
+/// 字幕の作成に失敗
+pub const VPSRT_FAILED: c_int = -2;
+
+/// C側に渡す字幕ブロック
+///
+/// `text`は`VpsrtCueList`が解放されるまで有効
+#[repr(C)]
+pub struct VpsrtCue {
+    pub index: usize,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: *const c_char,
+}
+
+/// 字幕ブロックの一覧
+pub struct VpsrtCueList {
+    blocks: Vec<SrtBlock>,
+    texts: Vec<CString>,
+}
+
+/// C文字列をパスに変換する
+///
+/// # Safety
+/// `ptr`はNULLまたはNUL終端された文字列を指していること
+unsafe fn to_path<'a>(ptr: *const c_char) -> Option<&'a Path> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok().map(Path::new)
+}
+
+/// ディレクトリ内のwavとtxtからsrtファイルを作成する
+///
+/// # Safety
+/// `input_dir`と`output_path`はNUL終端されたUTF-8文字列を指していること
+#[no_mangle]
+pub unsafe extern "C" fn generate_srt_from_dir(
+    input_dir: *const c_char,
+    output_path: *const c_char,
+) -> c_int {
+    let (Some(input_dir), Some(output_path)) = (to_path(input_dir), to_path(output_path)) else {
+        return VPSRT_INVALID_ARGUMENT;
+    };
+
+    // パニックをCへ伝搬させない
+    let result =
+        panic::catch_unwind(|| SubtitleBuilder::from_dir(input_dir).write_file(output_path));
+    match result {
+        Ok(Ok(())) => VPSRT_OK,
+        _ => VPSRT_FAILED,
+    }
+}
+
+/// ディレクトリ内のwavとtxtから字幕ブロックの一覧を作成する
+///
+/// 失敗した場合はNULLを返す。不要になったら`cue_list_free`で解放すること
+///
+/// # Safety
+/// `input_dir`はNUL終端されたUTF-8文字列を指していること
+#[no_mangle]
+pub unsafe extern "C" fn cue_list_from_dir(input_dir: *const c_char) -> *mut VpsrtCueList {
+    let Some(input_dir) = to_path(input_dir) else {
+        return ptr::null_mut();
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        SubtitleBuilder::from_dir(input_dir).build()
+    }));
+    let Ok(Ok(blocks)) = result else {
+        return ptr::null_mut();
+    };
+
+    // NULを含むテキストはCに渡せないため取り除く
+    let texts = blocks
+        .iter()
+        .map(|b| CString::new(b.text.replace('\0', "")).unwrap())
+        .collect();
+    Box::into_raw(Box::new(VpsrtCueList { blocks, texts }))
+}
+
+/// 字幕ブロックの数を返す
+///
+/// # Safety
+/// `list`はNULLまたは`cue_list_from_dir`が返したポインタであること
+#[no_mangle]
+pub unsafe extern "C" fn cue_list_len(list: *const VpsrtCueList) -> usize {
+    match list.as_ref() {
+        Some(list) => list.blocks.len(),
+        None => 0,
+    }
+}
+
+/// `index`番目(0始まり)の字幕ブロックを`out`に書き込む
+///
+/// # Safety
+/// `list`は`cue_list_from_dir`が返したポインタ、`out`は書き込み可能な`VpsrtCue`を指していること
+#[no_mangle]
+pub unsafe extern "C" fn cue_list_get(
+    list: *const VpsrtCueList,
+    index: usize,
+    out: *mut VpsrtCue,
+) -> c_int {
+    let (Some(list), Some(out)) = (list.as_ref(), out.as_mut()) else {
+        return VPSRT_INVALID_ARGUMENT;
+    };
+    let (Some(block), Some(text)) = (list.blocks.get(index), list.texts.get(index)) else {
+        return VPSRT_INVALID_ARGUMENT;
+    };
+
+    *out = VpsrtCue {
+        index: block.index,
+        start_ms: block.start.as_millis() as u64,
+        end_ms: block.end.as_millis() as u64,
+        text: text.as_ptr(),
+    };
+    VPSRT_OK
+}
+
+/// 字幕ブロックの一覧を解放する
+///
+/// # Safety
+/// `list`はNULLまたは`cue_list_from_dir`が返した未解放のポインタであること
+#[no_mangle]
+pub unsafe extern "C" fn cue_list_free(list: *mut VpsrtCueList) {
+    if !list.is_null() {
+        drop(Box::from_raw(list));
+    }
+}
+
+#[test]
+fn test_cue_list() {
+    let dir = CString::new("../voice").unwrap();
+    unsafe {
+        let list = cue_list_from_dir(dir.as_ptr());
+        assert!(!list.is_null());
+        assert_eq!(4, cue_list_len(list));
+
+        let mut cue = VpsrtCue {
+            index: 0,
+            start_ms: 0,
+            end_ms: 0,
+            text: ptr::null(),
+        };
+        assert_eq!(VPSRT_OK, cue_list_get(list, 1, &mut cue));
+        assert_eq!(2, cue.index);
+        assert_eq!(7288, cue.start_ms);
+        assert_eq!(
+            "エジプトを盟主とする中東アラブ諸国とイスラエルは、とてもピリピリした状態にありました",
+            CStr::from_ptr(cue.text).to_str().unwrap()
+        );
+        assert_eq!(VPSRT_INVALID_ARGUMENT, cue_list_get(list, 4, &mut cue));
+
+        cue_list_free(list);
+    }
+}
+
+#[test]
+fn test_generate_srt_from_dir_failed() {
+    let dir = CString::new("no/exits/path/").unwrap();
+    // 失敗しても空のファイルができることがあるため、一時フォルダーに書く
+    let path = std::env::temp_dir().join(format!("voicepeak-srt-ffi-{}.srt", std::process::id()));
+    let out = CString::new(path.to_str().unwrap()).unwrap();
+    unsafe {
+        assert_eq!(
+            VPSRT_FAILED,
+            generate_srt_from_dir(dir.as_ptr(), out.as_ptr())
+        );
+        assert_eq!(
+            VPSRT_INVALID_ARGUMENT,
+            generate_srt_from_dir(ptr::null(), out.as_ptr())
+        );
+    }
+    let _ = std::fs::remove_file(&path);
+}
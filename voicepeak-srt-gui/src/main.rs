@@ -0,0 +1,212 @@
+// リリースビルドではコンソールの窓を開かない
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+use std::path::{Path, PathBuf};
+
+use eframe::egui;
+use voicepeak_srt_core::{
+    format_srt_time, parse, preset::InputPreset, write_subtitle_file, SrtBlock, SubtitleBuilder,
+};
+
+/// 日本語を表示するために読み込むフォント(見つかった最初のものを使う)
+const JAPANESE_FONTS: [&str; 5] = [
+    "C:\\Windows\\Fonts\\meiryo.ttc",
+    "C:\\Windows\\Fonts\\YuGothM.ttc",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+    "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+];
+
+fn main() -> eframe::Result {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([900.0, 600.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "voicepeak-srt",
+        options,
+        Box::new(|cc| {
+            load_japanese_font(&cc.egui_ctx);
+            Ok(Box::new(App::new()))
+        }),
+    )
+}
+
+/// 既定のフォントには日本語がないため、OSのフォントを追加する
+fn load_japanese_font(ctx: &egui::Context) {
+    let Some(bytes) = JAPANESE_FONTS
+        .iter()
+        .find_map(|path| std::fs::read(path).ok())
+    else {
+        return;
+    };
+    let mut fonts = egui::FontDefinitions::default();
+    fonts
+        .font_data
+        .insert("japanese".to_string(), egui::FontData::from_owned(bytes));
+    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+        fonts
+            .families
+            .entry(family)
+            .or_default()
+            .push("japanese".to_string());
+    }
+    ctx.set_fonts(fonts);
+}
+
+/// 表の1行(時刻は編集中の文字列も持つ)
+struct Row {
+    block: SrtBlock,
+    start: String,
+    end: String,
+}
+
+impl Row {
+    fn new(block: SrtBlock) -> Self {
+        Self {
+            start: format_srt_time(block.start),
+            end: format_srt_time(block.end),
+            block,
+        }
+    }
+}
+
+struct App {
+    presets: Vec<InputPreset>,
+    preset: String,
+    folder: Option<PathBuf>,
+    rows: Vec<Row>,
+    status: String,
+}
+
+impl App {
+    fn new() -> Self {
+        Self {
+            presets: InputPreset::builtin(),
+            preset: "voicepeak".to_string(),
+            folder: None,
+            rows: Vec::new(),
+            status: "音声のフォルダーを開いてください".to_string(),
+        }
+    }
+
+    /// フォルダーの音声とtxtから字幕を作成する
+    fn open(&mut self, folder: &Path) {
+        let Some(preset) = InputPreset::find(&self.presets, &self.preset) else {
+            return;
+        };
+        match SubtitleBuilder::from_dir(folder).preset(preset).build() {
+            Ok(blocks) => {
+                self.status = format!("{}ブロックを読み込みました", blocks.len());
+                self.rows = blocks.into_iter().map(Row::new).collect();
+            }
+            Err(e) => {
+                self.status = format!("読み込めません: {}", e);
+                self.rows.clear();
+            }
+        }
+        self.folder = Some(folder.to_path_buf());
+    }
+
+    /// 編集したブロックを選んだ形式で書き出す
+    fn export(&mut self, extension: &str) {
+        let mut dialog = rfd::FileDialog::new()
+            .add_filter(extension, &[extension])
+            .set_file_name(format!("subtitles.{}", extension));
+        if let Some(folder) = self.folder.as_ref().and_then(|folder| folder.parent()) {
+            dialog = dialog.set_directory(folder);
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+        let blocks = self.rows.iter().map(|row| row.block.clone());
+        self.status = match write_subtitle_file(blocks, &path) {
+            Ok(()) => format!("書き出しました: {}", path.display()),
+            Err(e) => format!("書き出せません: {}", e),
+        };
+    }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("フォルダーを開く…").clicked() {
+                if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                    self.open(&folder);
+                }
+            }
+            egui::ComboBox::from_label("プリセット")
+                .selected_text(self.preset.as_str())
+                .show_ui(ui, |ui| {
+                    for preset in &self.presets {
+                        ui.selectable_value(&mut self.preset, preset.name.clone(), &preset.name)
+                            .on_hover_text(&preset.description);
+                    }
+                });
+            if let Some(folder) = self.folder.clone() {
+                if ui.button("読み込み直す").clicked() {
+                    self.open(&folder);
+                }
+            }
+            ui.separator();
+            let enabled = !self.rows.is_empty();
+            for extension in ["srt", "vtt", "ass"] {
+                let label = format!("{}に書き出す", extension);
+                if ui.add_enabled(enabled, egui::Button::new(label)).clicked() {
+                    self.export(extension);
+                }
+            }
+        });
+    }
+
+    fn cue_table(&mut self, ui: &mut egui::Ui) {
+        let mut invalid = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                egui::Grid::new("cues")
+                    .striped(true)
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        ui.strong("番号");
+                        ui.strong("開始");
+                        ui.strong("終了");
+                        ui.strong("テキスト");
+                        ui.end_row();
+
+                        for row in &mut self.rows {
+                            ui.label(row.block.index.to_string());
+                            for (text, time) in [
+                                (&mut row.start, &mut row.block.start),
+                                (&mut row.end, &mut row.block.end),
+                            ] {
+                                let edit = egui::TextEdit::singleline(text).desired_width(100.0);
+                                if ui.add(edit).lost_focus() {
+                                    // 読めない時刻は元に戻す
+                                    match parse::parse_timestamp(text) {
+                                        Some(parsed) => *time = parsed,
+                                        None => invalid = Some(text.clone()),
+                                    }
+                                    *text = format_srt_time(*time);
+                                }
+                            }
+                            ui.add(
+                                egui::TextEdit::multiline(&mut row.block.text)
+                                    .desired_rows(1)
+                                    .desired_width(f32::INFINITY),
+                            );
+                            ui.end_row();
+                        }
+                    });
+            });
+        if let Some(text) = invalid {
+            self.status = format!("時刻を読み込めません: {}", text);
+        }
+    }
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| self.toolbar(ui));
+        egui::TopBottomPanel::bottom("status").show(ctx, |ui| ui.label(self.status.as_str()));
+        egui::CentralPanel::default().show(ctx, |ui| self.cue_table(ui));
+    }
+}
@@ -0,0 +1,123 @@
+//! Python向けのバインディング
+
+use std::{fs::File, io::BufWriter, path::PathBuf, time::Duration};
+
+use pyo3::{
+    exceptions::{PyIOError, PyValueError},
+    prelude::*,
+};
+use voicepeak_srt_core::{
+    extract_wav_and_txt, write_cues, Error, JsonWriter, SrtBlock, SrtWriter, SubtitleBuilder,
+};
+
+/// ライブラリのエラーをPythonの例外に変換する
+fn to_py_err(e: Error) -> PyErr {
+    match e {
+        Error::PathNotFound(_) | Error::Read { .. } | Error::Write(_) => {
+            PyIOError::new_err(e.to_string())
+        }
+        _ => PyValueError::new_err(e.to_string()),
+    }
+}
+
+/// 字幕1ブロック分の情報(時刻は秒)
+#[pyclass(name = "Cue")]
+#[derive(Clone)]
+struct PyCue {
+    #[pyo3(get, set)]
+    index: usize,
+    #[pyo3(get, set)]
+    start: f64,
+    #[pyo3(get, set)]
+    end: f64,
+    #[pyo3(get, set)]
+    text: String,
+}
+
+#[pymethods]
+impl PyCue {
+    #[new]
+    fn new(index: usize, start: f64, end: f64, text: String) -> Self {
+        Self {
+            index,
+            start,
+            end,
+            text,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Cue(index={}, start={}, end={}, text={:?})",
+            self.index, self.start, self.end, self.text
+        )
+    }
+}
+
+impl From<SrtBlock> for PyCue {
+    fn from(block: SrtBlock) -> Self {
+        Self {
+            index: block.index,
+            start: block.start.as_secs_f64(),
+            end: block.end.as_secs_f64(),
+            text: block.text,
+        }
+    }
+}
+
+impl From<&PyCue> for SrtBlock {
+    fn from(cue: &PyCue) -> Self {
+        SrtBlock::new(
+            cue.index,
+            Duration::from_secs_f64(cue.start),
+            Duration::from_secs_f64(cue.end),
+            cue.text.clone(),
+        )
+    }
+}
+
+/// ディレクトリからwavとtxtのパスを取り出す
+#[pyfunction]
+fn scan_dir(path: PathBuf) -> PyResult<Vec<PathBuf>> {
+    extract_wav_and_txt(&path).map_err(to_py_err)
+}
+
+/// ディレクトリ内のwavとtxtから字幕ブロックを作成する
+#[pyfunction]
+#[pyo3(signature = (path, offset = 0.0, wrap = 0))]
+fn make_cues(path: PathBuf, offset: f64, wrap: usize) -> PyResult<Vec<PyCue>> {
+    SubtitleBuilder::from_dir(path)
+        .offset(Duration::from_secs_f64(offset))
+        .wrap(wrap)
+        .into_iter()
+        .map(|block| block.map(PyCue::from).map_err(to_py_err))
+        .collect()
+}
+
+/// 字幕ブロックをsrtファイルとして書き出す
+#[pyfunction]
+fn write_srt(cues: Vec<PyRef<PyCue>>, path: PathBuf) -> PyResult<()> {
+    let file = File::create(path).map_err(|e| PyIOError::new_err(e.to_string()))?;
+    let blocks = cues.iter().map(|c| SrtBlock::from(&**c));
+    write_cues(blocks, &mut SrtWriter::new(BufWriter::new(file)))
+        .map_err(|e| PyIOError::new_err(e.to_string()))
+}
+
+/// 字幕ブロックをjsonファイルとして書き出す
+#[pyfunction]
+fn write_json(cues: Vec<PyRef<PyCue>>, path: PathBuf) -> PyResult<()> {
+    let file = File::create(path).map_err(|e| PyIOError::new_err(e.to_string()))?;
+    let blocks = cues.iter().map(|c| SrtBlock::from(&**c));
+    write_cues(blocks, &mut JsonWriter::new(BufWriter::new(file)))
+        .map_err(|e| PyIOError::new_err(e.to_string()))
+}
+
+#[pymodule]
+fn voicepeak_srt(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyCue>()?;
+    m.add_function(wrap_pyfunction!(scan_dir, m)?)?;
+    m.add_function(wrap_pyfunction!(make_cues, m)?)?;
+    m.add_function(wrap_pyfunction!(write_srt, m)?)?;
+    m.add_function(wrap_pyfunction!(write_json, m)?)?;
+    Ok(())
+}